@@ -7,7 +7,7 @@ use std::io::{self, Write};
 use clap::{App, AppSettings};
 
 use immeta::GenericMetadata;
-use immeta::formats::{jpeg, gif, png, webp};
+use immeta::formats::{jpeg, gif, png, tiff, webp, heif, avif, bmp, ico};
 
 fn main() {
     let matches = App::new("immeta image analyzer")
@@ -36,6 +36,11 @@ fn main() {
         GenericMetadata::Gif(md) => print_gif_metadata(md),
         GenericMetadata::Png(md) => print_png_metadata(md),
         GenericMetadata::Webp(md) => print_webp_metadata(md),
+        GenericMetadata::Tiff(md) => print_tiff_metadata(md),
+        GenericMetadata::Heif(md) => print_heif_metadata(md),
+        GenericMetadata::Avif(md) => print_avif_metadata(md),
+        GenericMetadata::Bmp(md) => print_bmp_metadata(md),
+        GenericMetadata::Ico(md) => print_ico_metadata(md),
     }
 }
 
@@ -48,6 +53,20 @@ fn print_jpeg_metadata(md: jpeg::Metadata) {
     println!("  Differential: {}", md.differential);
     println!("  Entropy coding: {}", md.entropy_coding);
     println!("  Coding process: {}", md.coding_process);
+    if let Some(exif) = md.exif {
+        for (kind, directory) in exif.directories() {
+            println!("  {} directory:", kind);
+            for field in &directory.fields {
+                println!("    {}", field.display(kind));
+            }
+        }
+    }
+    if let Some(iptc) = md.iptc {
+        println!("  IPTC:");
+        for dataset in &iptc.datasets {
+            println!("    {}:{:02} ({} bytes)", dataset.record, dataset.number, dataset.data.len());
+        }
+    }
 }
 
 fn print_gif_metadata(md: gif::Metadata) {
@@ -70,3 +89,85 @@ fn print_webp_metadata(md: webp::Metadata) {
     println!("WEBP image:");
     // TODO
 }
+
+fn print_tiff_metadata(md: tiff::Metadata) {
+    println!("TIFF image:");
+    println!("  BigTIFF: {}", md.big_tiff);
+    for (i, page) in md.pages.iter().enumerate() {
+        println!("  Page {}:", i);
+        println!("    Width: {}", page.dimensions.width);
+        println!("    Height: {}", page.dimensions.height);
+        println!("    Color depth: {} bpp", page.color_depth());
+        println!("    Compression: {}", page.compression);
+        if let Some(pi) = page.photometric_interpretation {
+            println!("    Photometric interpretation: {}", pi);
+        }
+        println!("    Planar configuration: {}", page.planar_configuration);
+    }
+}
+
+fn print_heif_metadata(md: heif::Metadata) {
+    println!("HEIF image:");
+    println!("  Width: {}", md.dimensions.width);
+    println!("  Height: {}", md.dimensions.height);
+    println!("  Major brand: {}", md.major_brand);
+    println!("  Items: {}", md.items.len());
+    println!("  Grid: {}", md.has_grid());
+    println!("  Orientation: {}", md.orientation());
+    if let Some(ref bits) = md.bits_per_channel {
+        println!("  Bits per channel: {:?}", bits);
+    }
+}
+
+fn print_avif_metadata(md: avif::Metadata) {
+    println!("AVIF image:");
+    println!("  Width: {}", md.dimensions.width);
+    println!("  Height: {}", md.dimensions.height);
+    println!("  Major brand: {}", md.major_brand);
+    if let Some(ref config) = md.av1_config {
+        println!("  Profile: {}", config.profile);
+        println!("  Bit depth: {}", config.bit_depth);
+        println!("  Chroma subsampling: {}", config.chroma_subsampling());
+    }
+    println!("  Alpha: {}", md.has_alpha());
+    println!("  Orientation: {}", md.orientation());
+    if let Some(ref sequence) = md.sequence {
+        println!("  Frames: {}", sequence.frames_number);
+        println!("  Duration: {} ms", sequence.duration_ms());
+    }
+}
+
+fn print_bmp_metadata(md: bmp::Metadata) {
+    println!("BMP image:");
+    println!("  Width: {}", md.header.dimensions.width);
+    println!("  Height: {}", md.header.dimensions.height);
+    println!("  Header: {}", md.header.kind);
+    println!("  Color depth: {} bpp", md.header.bit_count);
+    println!("  Compression: {}", md.header.compression);
+    println!("  Top-down: {}", md.header.top_down);
+    if let Some((x, y)) = md.header.dpi() {
+        println!("  Resolution: {:.0}x{:.0} dpi", x, y);
+    }
+    if let Some(color_space) = md.header.color_space {
+        println!("  Color space: {}", color_space);
+    }
+}
+
+fn print_ico_metadata(md: ico::Metadata) {
+    println!("{} file:", md.kind);
+    for (i, entry) in md.entries.iter().enumerate() {
+        let dimensions = entry.dimensions();
+        println!("  Entry {}:", i);
+        println!("    Width: {}", dimensions.width);
+        println!("    Height: {}", dimensions.height);
+        println!("    Color depth: {} bpp", entry.bits_per_pixel());
+        if let Some((x, y)) = entry.hotspot {
+            println!("    Hotspot: {}, {}", x, y);
+        }
+        match entry.image {
+            Some(ico::Image::Png(_)) => println!("    Format: PNG"),
+            Some(ico::Image::Bmp(_)) => println!("    Format: BMP"),
+            None => println!("    Format: unknown"),
+        }
+    }
+}
@@ -0,0 +1,233 @@
+//! Support for loading metadata from asynchronous streams.
+//!
+//! Parsers of all formats are synchronous, so data is read from the asynchronous stream
+//! into memory and parsed from there. Parsing is retried with more data each time it fails
+//! because of the unexpected end of data, so only as much of an image is read as its parser
+//! consumes. This is just the beginning of JPEG images, but the whole stream for formats
+//! whose parsers walk to the end of an image, like PNG and GIF. The buffer grows
+//! geometrically, which keeps the total parsing time linear in the amount of data read.
+//!
+//! Seekable streams are parsed with the `load_from_seek()` parsers instead. Only the ranges of
+//! the stream which the parser reads are fetched into memory, so formats whose parsers seek
+//! over unneeded data, like TIFF, HEIF and AVIF, are loaded without reading whole images.
+
+use std::collections::BTreeMap;
+use std::future::{self, Future};
+use std::io::{self, BufRead, Read, Seek, SeekFrom};
+use std::pin::Pin;
+use std::task::{ready, Poll};
+
+use futures_io::{AsyncBufRead, AsyncSeek};
+
+use crate::types::{Error, Result};
+
+// parsing is first attempted when this much data is available
+const INITIAL_ATTEMPT_SIZE: usize = 4096;
+
+// appends the currently buffered data of the stream to `data`, returning its length
+fn read_more<'a, R>(
+    r: &'a mut R,
+    data: &'a mut Vec<u8>,
+) -> impl Future<Output = io::Result<usize>> + 'a
+where
+    R: ?Sized + AsyncBufRead + Unpin,
+{
+    future::poll_fn(move |cx| {
+        let mut r = Pin::new(&mut *r);
+        let buf = ready!(r.as_mut().poll_fill_buf(cx))?;
+        let n = buf.len();
+        data.extend_from_slice(buf);
+        r.consume(n);
+        Poll::Ready(Ok(n))
+    })
+}
+
+// reads data until there are at least `size` bytes in `data` or the stream ends;
+// returns `true` if the stream has ended
+async fn read_at_least<R>(r: &mut R, data: &mut Vec<u8>, size: usize) -> io::Result<bool>
+where
+    R: ?Sized + AsyncBufRead + Unpin,
+{
+    while data.len() < size {
+        if read_more(r, data).await? == 0 {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+/// Reads the whole stream into memory.
+pub(crate) async fn read_to_end<R>(r: &mut R, data: &mut Vec<u8>) -> io::Result<()>
+where
+    R: ?Sized + AsyncBufRead + Unpin,
+{
+    while read_more(r, data).await? > 0 {}
+    Ok(())
+}
+
+/// Reads data from the stream until `load` succeeds or fails for a reason other than
+/// the end of data. `data` may already contain the beginning of the stream.
+pub(crate) async fn load_buffered<T, R, F>(r: &mut R, mut data: Vec<u8>, load: F) -> Result<T>
+where
+    R: ?Sized + AsyncBufRead + Unpin,
+    F: Fn(&[u8]) -> Result<T>,
+{
+    let mut attempt_size = INITIAL_ATTEMPT_SIZE;
+    loop {
+        let eof = read_at_least(r, &mut data, attempt_size).await?;
+        match load(&data) {
+            Err(ref e) if !eof && is_eof(e) => attempt_size = data.len() * 2,
+            result => return result,
+        }
+    }
+}
+
+/// Reads at least `size` bytes from the stream unless it ends earlier.
+pub(crate) async fn read_prefix<R>(r: &mut R, size: usize) -> io::Result<Vec<u8>>
+where
+    R: ?Sized + AsyncBufRead + Unpin,
+{
+    let mut data = Vec::new();
+    read_at_least(r, &mut data, size).await?;
+    Ok(data)
+}
+
+/// Fetches ranges of the stream which `load` reads until it succeeds without reading past
+/// the fetched data. `load` is given a reader positioned at the current stream position.
+pub(crate) async fn load_seekable<T, R, F>(r: &mut R, load: F) -> Result<T>
+where
+    R: ?Sized + AsyncBufRead + AsyncSeek + Unpin,
+    F: Fn(&mut Fetched) -> Result<T>,
+{
+    let start = seek(r, SeekFrom::Current(0)).await?;
+    let len = seek(r, SeekFrom::End(0)).await?;
+    let mut fetched = Fetched {
+        ranges: BTreeMap::new(),
+        len,
+        position: start,
+        missing: None,
+    };
+
+    let mut offset = start;
+    loop {
+        // fetch at least as much as has been fetched already, so the number of attempts
+        // is logarithmic in the amount of data read
+        let size = fetched.ranges.values().map(Vec::len).sum::<usize>();
+        seek(r, SeekFrom::Start(offset)).await?;
+        let data = read_prefix(r, size.max(INITIAL_ATTEMPT_SIZE)).await?;
+        if data.is_empty() && offset < len {
+            return Err(unexpected_eof!("when fetching data at offset {}", offset));
+        }
+        fetched.insert(offset, data);
+
+        fetched.position = start;
+        let result = load(&mut fetched);
+        // errors caused by missing data may be ignored by lenient parsers, so the result
+        // is only final if nothing was missing
+        match fetched.missing.take() {
+            Some(missing) => offset = missing,
+            None => return result,
+        }
+    }
+}
+
+pub(crate) fn seek<'a, R>(r: &'a mut R, pos: SeekFrom) -> impl Future<Output = io::Result<u64>> + 'a
+where
+    R: ?Sized + AsyncSeek + Unpin,
+{
+    future::poll_fn(move |cx| Pin::new(&mut *r).poll_seek(cx, pos))
+}
+
+/// Ranges of a stream fetched into memory, which are read as if they were the stream itself.
+///
+/// Reading outside of the fetched ranges fails with an unexpected end of file error, and the
+/// first offset at which this happened is remembered.
+pub(crate) struct Fetched {
+    // non-overlapping and non-adjacent ranges by their offsets
+    ranges: BTreeMap<u64, Vec<u8>>,
+    len: u64,
+    position: u64,
+    missing: Option<u64>,
+}
+
+impl Fetched {
+    // `offset` must not be covered by the fetched ranges
+    fn insert(&mut self, offset: u64, mut data: Vec<u8>) {
+        let mut end = offset + data.len() as u64;
+        let following: Vec<u64> = self.ranges.range(offset..=end).map(|(&o, _)| o).collect();
+        for o in following {
+            let range = self.ranges.remove(&o).unwrap();
+            let range_end = o + range.len() as u64;
+            if range_end > end {
+                data.extend_from_slice(&range[(end - o) as usize..]);
+                end = range_end;
+            }
+        }
+
+        if let Some((&o, range)) = self.ranges.range_mut(..offset).next_back() {
+            if o + range.len() as u64 == offset {
+                range.append(&mut data);
+                return;
+            }
+        }
+        if !data.is_empty() {
+            self.ranges.insert(offset, data);
+        }
+    }
+}
+
+impl Read for Fetched {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let available = self.fill_buf()?;
+        let n = available.len().min(buf.len());
+        buf[..n].copy_from_slice(&available[..n]);
+        self.consume(n);
+        Ok(n)
+    }
+}
+
+impl BufRead for Fetched {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        if self.position >= self.len {
+            return Ok(&[]);
+        }
+        if let Some((&o, range)) = self.ranges.range(..=self.position).next_back() {
+            if let Some(available) = range.get((self.position - o) as usize..) {
+                if !available.is_empty() {
+                    return Ok(available);
+                }
+            }
+        }
+        self.missing.get_or_insert(self.position);
+        Err(io::ErrorKind::UnexpectedEof.into())
+    }
+
+    fn consume(&mut self, amt: usize) {
+        self.position += amt as u64;
+    }
+}
+
+impl Seek for Fetched {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let position = match pos {
+            SeekFrom::Start(offset) => Some(offset),
+            SeekFrom::End(offset) => self.len.checked_add_signed(offset),
+            SeekFrom::Current(offset) => self.position.checked_add_signed(offset),
+        };
+        self.position = position.ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "invalid seek to a negative or overflowing position",
+            )
+        })?;
+        Ok(self.position)
+    }
+}
+
+fn is_eof(e: &Error) -> bool {
+    match *e {
+        Error::UnexpectedEndOfFile(_) => true,
+        Error::Io(ref e) => e.kind() == io::ErrorKind::UnexpectedEof,
+        Error::InvalidFormat(_) => false,
+    }
+}
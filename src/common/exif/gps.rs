@@ -0,0 +1,242 @@
+//! Decoding of GPS information stored in the GPS sub-IFD.
+
+use std::fmt;
+
+use super::Directory;
+use crate::common::tiff::Value;
+
+const LATITUDE_REF: u16 = 0x0001;
+const LATITUDE: u16 = 0x0002;
+const LONGITUDE_REF: u16 = 0x0003;
+const LONGITUDE: u16 = 0x0004;
+const ALTITUDE_REF: u16 = 0x0005;
+const ALTITUDE: u16 = 0x0006;
+const TIME_STAMP: u16 = 0x0007;
+const DATE_STAMP: u16 = 0x001d;
+
+/// A point in time in UTC, as recorded by a GPS receiver.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct GpsDateTime {
+    pub year: u16,
+    pub month: u8,
+    pub day: u8,
+    pub hour: u8,
+    pub minute: u8,
+    pub second: u8,
+    /// Fractional part of the second, in nanoseconds.
+    pub nanosecond: u32,
+}
+
+impl fmt::Display for GpsDateTime {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}",
+            self.year, self.month, self.day, self.hour, self.minute, self.second
+        )?;
+        if self.nanosecond != 0 {
+            write!(f, ".{:09}", self.nanosecond)?;
+        }
+        f.write_str("Z")
+    }
+}
+
+impl GpsDateTime {
+    /// Returns the number of whole seconds elapsed since the Unix epoch.
+    pub fn unix_timestamp(&self) -> i64 {
+        // days from civil algorithm by Howard Hinnant
+        let (y, m) = if self.month <= 2 {
+            (self.year as i64 - 1, self.month as i64 + 9)
+        } else {
+            (self.year as i64, self.month as i64 - 3)
+        };
+        let era = y.div_euclid(400);
+        let yoe = y - era * 400;
+        let doy = (153 * m + 2) / 5 + self.day as i64 - 1;
+        let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+        let days = era * 146097 + doe - 719468;
+
+        days * 86400 + self.hour as i64 * 3600 + self.minute as i64 * 60 + self.second as i64
+    }
+
+    fn parse(date: &str, time: &[(u32, u32)]) -> Option<GpsDateTime> {
+        // date is in YYYY:MM:DD format
+        let mut parts = date.trim_end().splitn(3, ':');
+        let year = parts.next()?.parse().ok()?;
+        let month = parts.next()?.parse().ok()?;
+        let day = parts.next()?.parse().ok()?;
+        if !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+            return None;
+        }
+
+        // time consists of three rationals: hour, minute and second; they are converted to
+        // nanoseconds exactly so that fractions of any of them end up in the lower units
+        let (hour, minute, second) = match *time {
+            [h, m, s] => (
+                nanoseconds(h, 3600 * NANOS_PER_SECOND)?,
+                nanoseconds(m, 60 * NANOS_PER_SECOND)?,
+                nanoseconds(s, NANOS_PER_SECOND)?,
+            ),
+            _ => return None,
+        };
+        if hour >= 24 * 3600 * NANOS_PER_SECOND
+            || minute >= 3600 * NANOS_PER_SECOND
+            || second >= 61 * NANOS_PER_SECOND
+        {
+            return None;
+        }
+
+        // a leap second must not be carried over into the minute
+        let (minutes, second) = if second >= 60 * NANOS_PER_SECOND {
+            if (hour + minute) % (60 * NANOS_PER_SECOND) != 0 {
+                return None;
+            }
+            ((hour + minute) / (60 * NANOS_PER_SECOND), second)
+        } else {
+            let total = hour + minute + second;
+            (
+                total / (60 * NANOS_PER_SECOND),
+                total % (60 * NANOS_PER_SECOND),
+            )
+        };
+        if minutes >= 24 * 60 {
+            return None;
+        }
+
+        Some(GpsDateTime {
+            year,
+            month,
+            day,
+            hour: (minutes / 60) as u8,
+            minute: (minutes % 60) as u8,
+            second: (second / NANOS_PER_SECOND) as u8,
+            nanosecond: (second % NANOS_PER_SECOND) as u32,
+        })
+    }
+}
+
+/// GPS information decoded from the GPS sub-IFD.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct Gps {
+    /// Latitude in decimal degrees, positive for the northern hemisphere.
+    pub latitude: Option<f64>,
+    /// Longitude in decimal degrees, positive for the eastern hemisphere.
+    pub longitude: Option<f64>,
+    /// Altitude in meters, negative if below the sea level.
+    pub altitude: Option<f64>,
+    /// Date and time in UTC, if both date and time stamps are present.
+    pub date_time: Option<GpsDateTime>,
+}
+
+impl Gps {
+    pub(super) fn load(d: &Directory) -> Gps {
+        let rationals = |tag| match d.get(tag) {
+            Some(Value::Rational(v)) => Some(&v[..]),
+            _ => None,
+        };
+
+        let latitude = rationals(LATITUDE).and_then(degrees).map(|v| {
+            match d.get(LATITUDE_REF).and_then(Value::as_str) {
+                Some("S") => -v,
+                _ => v,
+            }
+        });
+        let longitude = rationals(LONGITUDE).and_then(degrees).map(|v| {
+            match d.get(LONGITUDE_REF).and_then(Value::as_str) {
+                Some("W") => -v,
+                _ => v,
+            }
+        });
+        let altitude = d
+            .get(ALTITUDE)
+            .and_then(Value::as_rational)
+            .and_then(ratio)
+            .map(|v| match d.get(ALTITUDE_REF).and_then(Value::as_u32) {
+                Some(1) => -v,
+                _ => v,
+            });
+        let date_time = match (
+            d.get(DATE_STAMP).and_then(Value::as_str),
+            rationals(TIME_STAMP),
+        ) {
+            (Some(date), Some(time)) => GpsDateTime::parse(date, time),
+            _ => None,
+        };
+
+        Gps {
+            latitude,
+            longitude,
+            altitude,
+            date_time,
+        }
+    }
+}
+
+fn ratio((n, d): (u32, u32)) -> Option<f64> {
+    if d == 0 {
+        None
+    } else {
+        Some(n as f64 / d as f64)
+    }
+}
+
+const NANOS_PER_SECOND: u64 = 1_000_000_000;
+
+// converts a rational amount of the given unit to whole nanoseconds, truncating the rest
+fn nanoseconds((n, d): (u32, u32), unit: u64) -> Option<u64> {
+    if d == 0 {
+        None
+    } else {
+        u64::try_from(n as u128 * unit as u128 / d as u128).ok()
+    }
+}
+
+// converts degrees, minutes and seconds to decimal degrees
+fn degrees(dms: &[(u32, u32)]) -> Option<f64> {
+    match *dms {
+        [d, m, s] => Some(ratio(d)? + ratio(m)? / 60.0 + ratio(s)? / 3600.0),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::GpsDateTime;
+
+    fn time(h: (u32, u32), m: (u32, u32), s: (u32, u32)) -> Option<GpsDateTime> {
+        GpsDateTime::parse("2024:02:29", &[h, m, s])
+    }
+
+    #[test]
+    fn test_fractional_second_is_truncated() {
+        let dt = time((23, 1), (59, 1), (u32::MAX - 1, u32::MAX)).unwrap();
+        assert_eq!((dt.hour, dt.minute, dt.second), (23, 59, 0));
+        assert_eq!(dt.nanosecond, 999_999_999);
+        assert_eq!(dt.to_string(), "2024-02-29T23:59:00.999999999Z");
+    }
+
+    #[test]
+    fn test_fractional_hour_and_minute_are_folded() {
+        let dt = time((25, 2), (3, 4), (0, 1)).unwrap();
+        assert_eq!((dt.hour, dt.minute, dt.second), (12, 30, 45));
+        assert_eq!(dt.nanosecond, 0);
+
+        let dt = time((10, 1), (119, 2), (45, 1)).unwrap();
+        assert_eq!((dt.hour, dt.minute, dt.second), (11, 0, 15));
+    }
+
+    #[test]
+    fn test_leap_second() {
+        let dt = time((23, 1), (59, 1), (121, 2)).unwrap();
+        assert_eq!((dt.hour, dt.minute, dt.second), (23, 59, 60));
+        assert_eq!(dt.nanosecond, 500_000_000);
+    }
+
+    #[test]
+    fn test_out_of_range_time() {
+        assert_eq!(time((24, 1), (0, 1), (0, 1)), None);
+        assert_eq!(time((23, 1), (119, 2), (45, 1)), None);
+        assert_eq!(time((0, 1), (0, 1), (61, 1)), None);
+        assert_eq!(time((0, 0), (0, 1), (0, 1)), None);
+    }
+}
@@ -0,0 +1,799 @@
+//! EXIF metadata support.
+//!
+//! EXIF metadata is stored as a TIFF document, usually embedded into a container format
+//! like JPEG or WebP. This module provides an owned representation of EXIF directories
+//! which is loaded with `TiffReader`, as well as decoders for enumerated values and
+//! a registry of known tags in the `tags` module.
+
+use std::fmt;
+use std::io::{Cursor, Read, Seek};
+
+use crate::common::tiff::{Ifd, LazyIfds, TiffReader, Value};
+use crate::types::{Dimensions, Orientation, Result};
+
+pub use self::gps::{Gps, GpsDateTime};
+pub use self::tags::IfdKind;
+
+mod gps;
+pub mod tags;
+
+/// Tag of IFD0 entry which points to the EXIF sub-IFD.
+pub const EXIF_IFD_POINTER: u16 = 0x8769;
+/// Tag of IFD0 entry which points to the GPS sub-IFD.
+pub const GPS_IFD_POINTER: u16 = 0x8825;
+/// Tag of EXIF sub-IFD entry which points to the Interoperability sub-IFD.
+pub const INTEROPERABILITY_IFD_POINTER: u16 = 0xa005;
+
+// IFD0 tags
+const ORIENTATION: u16 = 0x0112;
+const MAKE: u16 = 0x010f;
+const MODEL: u16 = 0x0110;
+const SOFTWARE: u16 = 0x0131;
+const DATE_TIME: u16 = 0x0132;
+const ARTIST: u16 = 0x013b;
+const COPYRIGHT: u16 = 0x8298;
+
+// EXIF sub-IFD tags
+const EXPOSURE_TIME: u16 = 0x829a;
+const F_NUMBER: u16 = 0x829d;
+const EXPOSURE_PROGRAM: u16 = 0x8822;
+const PHOTOGRAPHIC_SENSITIVITY: u16 = 0x8827;
+const EXIF_VERSION: u16 = 0x9000;
+const DATE_TIME_ORIGINAL: u16 = 0x9003;
+const DATE_TIME_DIGITIZED: u16 = 0x9004;
+const EXPOSURE_BIAS_VALUE: u16 = 0x9204;
+const METERING_MODE: u16 = 0x9207;
+const FLASH: u16 = 0x9209;
+const FOCAL_LENGTH: u16 = 0x920a;
+const FLASHPIX_VERSION: u16 = 0xa000;
+const COLOR_SPACE: u16 = 0xa001;
+const PIXEL_X_DIMENSION: u16 = 0xa002;
+const PIXEL_Y_DIMENSION: u16 = 0xa003;
+const EXPOSURE_MODE: u16 = 0xa402;
+const WHITE_BALANCE: u16 = 0xa403;
+const FOCAL_LENGTH_IN_35MM_FILM: u16 = 0xa405;
+const SCENE_CAPTURE_TYPE: u16 = 0xa406;
+const LENS_MAKE: u16 = 0xa433;
+const LENS_MODEL: u16 = 0xa434;
+
+// Interoperability sub-IFD tags
+const INTEROPERABILITY_INDEX: u16 = 0x0001;
+const INTEROPERABILITY_VERSION: u16 = 0x0002;
+
+// IFD1 tags
+const JPEG_INTERCHANGE_FORMAT: u16 = 0x0201;
+const JPEG_INTERCHANGE_FORMAT_LENGTH: u16 = 0x0202;
+
+/// Exposure program used by the camera when the image was taken.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum ExposureProgram {
+    NotDefined,
+    Manual,
+    Normal,
+    AperturePriority,
+    ShutterPriority,
+    Creative,
+    Action,
+    Portrait,
+    Landscape,
+    Unknown(u16),
+}
+
+impl fmt::Display for ExposureProgram {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ExposureProgram::NotDefined => f.write_str("Not defined"),
+            ExposureProgram::Manual => f.write_str("Manual"),
+            ExposureProgram::Normal => f.write_str("Normal program"),
+            ExposureProgram::AperturePriority => f.write_str("Aperture priority"),
+            ExposureProgram::ShutterPriority => f.write_str("Shutter priority"),
+            ExposureProgram::Creative => f.write_str("Creative program"),
+            ExposureProgram::Action => f.write_str("Action program"),
+            ExposureProgram::Portrait => f.write_str("Portrait mode"),
+            ExposureProgram::Landscape => f.write_str("Landscape mode"),
+            ExposureProgram::Unknown(n) => write!(f, "Unknown ({})", n),
+        }
+    }
+}
+
+impl ExposureProgram {
+    fn from_u16(n: u16) -> ExposureProgram {
+        match n {
+            0 => ExposureProgram::NotDefined,
+            1 => ExposureProgram::Manual,
+            2 => ExposureProgram::Normal,
+            3 => ExposureProgram::AperturePriority,
+            4 => ExposureProgram::ShutterPriority,
+            5 => ExposureProgram::Creative,
+            6 => ExposureProgram::Action,
+            7 => ExposureProgram::Portrait,
+            8 => ExposureProgram::Landscape,
+            n => ExposureProgram::Unknown(n),
+        }
+    }
+}
+
+/// Metering mode used by the camera when the image was taken.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum MeteringMode {
+    NotDefined,
+    Average,
+    CenterWeightedAverage,
+    Spot,
+    MultiSpot,
+    Pattern,
+    Partial,
+    Other,
+    Unknown(u16),
+}
+
+impl fmt::Display for MeteringMode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            MeteringMode::NotDefined => f.write_str("Unknown"),
+            MeteringMode::Average => f.write_str("Average"),
+            MeteringMode::CenterWeightedAverage => f.write_str("Center-weighted average"),
+            MeteringMode::Spot => f.write_str("Spot"),
+            MeteringMode::MultiSpot => f.write_str("Multi-spot"),
+            MeteringMode::Pattern => f.write_str("Pattern"),
+            MeteringMode::Partial => f.write_str("Partial"),
+            MeteringMode::Other => f.write_str("Other"),
+            MeteringMode::Unknown(n) => write!(f, "Unknown ({})", n),
+        }
+    }
+}
+
+impl MeteringMode {
+    fn from_u16(n: u16) -> MeteringMode {
+        match n {
+            0 => MeteringMode::NotDefined,
+            1 => MeteringMode::Average,
+            2 => MeteringMode::CenterWeightedAverage,
+            3 => MeteringMode::Spot,
+            4 => MeteringMode::MultiSpot,
+            5 => MeteringMode::Pattern,
+            6 => MeteringMode::Partial,
+            255 => MeteringMode::Other,
+            n => MeteringMode::Unknown(n),
+        }
+    }
+}
+
+/// Status of the flash return light detection.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum FlashReturn {
+    /// The camera has no strobe return detection function.
+    NoDetectionFunction,
+    /// Reserved value.
+    Reserved,
+    /// Strobe return light was not detected.
+    NotDetected,
+    /// Strobe return light was detected.
+    Detected,
+}
+
+/// Flash firing mode.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum FlashMode {
+    Unknown,
+    CompulsoryFiring,
+    CompulsorySuppression,
+    Auto,
+}
+
+/// Status of the flash when the image was taken.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct Flash {
+    /// Whether the flash fired.
+    pub fired: bool,
+    /// Status of the return light detection.
+    pub return_status: FlashReturn,
+    /// Flash firing mode.
+    pub mode: FlashMode,
+    /// Whether the camera has a flash function.
+    pub function_present: bool,
+    /// Whether the red-eye reduction mode was used.
+    pub red_eye_reduction: bool,
+}
+
+impl fmt::Display for Flash {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if !self.function_present {
+            return f.write_str("No flash function");
+        }
+
+        f.write_str(if self.fired { "Fired" } else { "Did not fire" })?;
+        match self.mode {
+            FlashMode::Unknown => {}
+            FlashMode::CompulsoryFiring => f.write_str(", compulsory flash firing")?,
+            FlashMode::CompulsorySuppression => f.write_str(", compulsory flash suppression")?,
+            FlashMode::Auto => f.write_str(", auto mode")?,
+        }
+        match self.return_status {
+            FlashReturn::NoDetectionFunction | FlashReturn::Reserved => {}
+            FlashReturn::NotDetected => f.write_str(", return light not detected")?,
+            FlashReturn::Detected => f.write_str(", return light detected")?,
+        }
+        if self.red_eye_reduction {
+            f.write_str(", red-eye reduction")?;
+        }
+        Ok(())
+    }
+}
+
+impl Flash {
+    fn from_u16(n: u16) -> Flash {
+        Flash {
+            fired: n & 0x01 != 0,
+            return_status: match (n >> 1) & 0x03 {
+                0 => FlashReturn::NoDetectionFunction,
+                1 => FlashReturn::Reserved,
+                2 => FlashReturn::NotDetected,
+                _ => FlashReturn::Detected,
+            },
+            mode: match (n >> 3) & 0x03 {
+                0 => FlashMode::Unknown,
+                1 => FlashMode::CompulsoryFiring,
+                2 => FlashMode::CompulsorySuppression,
+                _ => FlashMode::Auto,
+            },
+            function_present: n & 0x20 == 0,
+            red_eye_reduction: n & 0x40 != 0,
+        }
+    }
+}
+
+/// Color space of the image data.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum ColorSpace {
+    Srgb,
+    Uncalibrated,
+    Unknown(u16),
+}
+
+impl fmt::Display for ColorSpace {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ColorSpace::Srgb => f.write_str("sRGB"),
+            ColorSpace::Uncalibrated => f.write_str("Uncalibrated"),
+            ColorSpace::Unknown(n) => write!(f, "Unknown ({})", n),
+        }
+    }
+}
+
+impl ColorSpace {
+    fn from_u16(n: u16) -> ColorSpace {
+        match n {
+            1 => ColorSpace::Srgb,
+            0xffff => ColorSpace::Uncalibrated,
+            n => ColorSpace::Unknown(n),
+        }
+    }
+}
+
+/// Exposure mode set when the image was taken.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum ExposureMode {
+    Auto,
+    Manual,
+    AutoBracket,
+    Unknown(u16),
+}
+
+impl fmt::Display for ExposureMode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ExposureMode::Auto => f.write_str("Auto exposure"),
+            ExposureMode::Manual => f.write_str("Manual exposure"),
+            ExposureMode::AutoBracket => f.write_str("Auto bracket"),
+            ExposureMode::Unknown(n) => write!(f, "Unknown ({})", n),
+        }
+    }
+}
+
+impl ExposureMode {
+    fn from_u16(n: u16) -> ExposureMode {
+        match n {
+            0 => ExposureMode::Auto,
+            1 => ExposureMode::Manual,
+            2 => ExposureMode::AutoBracket,
+            n => ExposureMode::Unknown(n),
+        }
+    }
+}
+
+/// White balance mode set when the image was taken.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum WhiteBalance {
+    Auto,
+    Manual,
+    Unknown(u16),
+}
+
+impl fmt::Display for WhiteBalance {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            WhiteBalance::Auto => f.write_str("Auto white balance"),
+            WhiteBalance::Manual => f.write_str("Manual white balance"),
+            WhiteBalance::Unknown(n) => write!(f, "Unknown ({})", n),
+        }
+    }
+}
+
+impl WhiteBalance {
+    fn from_u16(n: u16) -> WhiteBalance {
+        match n {
+            0 => WhiteBalance::Auto,
+            1 => WhiteBalance::Manual,
+            n => WhiteBalance::Unknown(n),
+        }
+    }
+}
+
+/// Type of the scene that was shot.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum SceneCaptureType {
+    Standard,
+    Landscape,
+    Portrait,
+    NightScene,
+    Unknown(u16),
+}
+
+impl fmt::Display for SceneCaptureType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            SceneCaptureType::Standard => f.write_str("Standard"),
+            SceneCaptureType::Landscape => f.write_str("Landscape"),
+            SceneCaptureType::Portrait => f.write_str("Portrait"),
+            SceneCaptureType::NightScene => f.write_str("Night scene"),
+            SceneCaptureType::Unknown(n) => write!(f, "Unknown ({})", n),
+        }
+    }
+}
+
+impl SceneCaptureType {
+    fn from_u16(n: u16) -> SceneCaptureType {
+        match n {
+            0 => SceneCaptureType::Standard,
+            1 => SceneCaptureType::Landscape,
+            2 => SceneCaptureType::Portrait,
+            3 => SceneCaptureType::NightScene,
+            n => SceneCaptureType::Unknown(n),
+        }
+    }
+}
+
+#[inline]
+fn as_u16(value: &Value) -> Option<u16> {
+    match *value {
+        Value::Short(ref v) => v.first().cloned(),
+        _ => None,
+    }
+}
+
+/// A single EXIF field, i.e. a tag with its value.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Field {
+    /// Tag number of the field.
+    pub tag: u16,
+    /// Value of the field.
+    pub value: Value,
+}
+
+impl Field {
+    /// Returns the definition of this field tag, if the tag is known.
+    ///
+    /// The same tag number has different meaning in different directories, so the kind of
+    /// the directory containing this field must be provided.
+    pub fn info(&self, ifd: IfdKind) -> Option<&'static tags::TagInfo> {
+        tags::lookup(ifd, self.tag)
+    }
+
+    /// Returns a wrapper which formats this field in a human-readable way, e.g.
+    /// `FNumber: f/2.8`.
+    ///
+    /// Tag names are taken from the registry, and enumerated values are decoded. Fields
+    /// with unknown tags are formatted with their hexadecimal tag number and raw value.
+    pub fn display(&self, ifd: IfdKind) -> DisplayField<'_> {
+        DisplayField { ifd, field: self }
+    }
+}
+
+/// Formats an EXIF field in a human-readable way.
+///
+/// Values of this type are obtained with `Field::display()` method.
+pub struct DisplayField<'a> {
+    ifd: IfdKind,
+    field: &'a Field,
+}
+
+impl<'a> fmt::Display for DisplayField<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.field.info(self.ifd) {
+            Some(info) => write!(f, "{}: ", info.name)?,
+            None => write!(f, "{:#06x}: ", self.field.tag)?,
+        }
+
+        let value = &self.field.value;
+        let decoded = match (self.ifd, self.field.tag) {
+            (IfdKind::Tiff, ORIENTATION) => as_u16(value)
+                .and_then(Orientation::from_exif)
+                .map(|o| write!(f, "{}", o)),
+            (IfdKind::Exif, EXPOSURE_TIME) => value.as_rational().map(|(n, d)| {
+                if n != 0 && n < d && d % n == 0 {
+                    write!(f, "1/{} s", d / n)
+                } else {
+                    write!(f, "{} s", n as f64 / d as f64)
+                }
+            }),
+            (IfdKind::Exif, F_NUMBER) => value
+                .as_rational()
+                .map(|(n, d)| write!(f, "f/{:.1}", n as f64 / d as f64)),
+            (IfdKind::Exif, FOCAL_LENGTH) => value
+                .as_rational()
+                .map(|(n, d)| write!(f, "{} mm", n as f64 / d as f64)),
+            (IfdKind::Exif, FOCAL_LENGTH_IN_35MM_FILM) => {
+                value.as_u32().map(|n| write!(f, "{} mm", n))
+            }
+            (IfdKind::Exif, EXPOSURE_BIAS_VALUE) => value
+                .as_signed_rational()
+                .map(|(n, d)| write!(f, "{:.2} EV", n as f64 / d as f64)),
+            (IfdKind::Exif, EXIF_VERSION)
+            | (IfdKind::Exif, FLASHPIX_VERSION)
+            | (IfdKind::Interoperability, INTEROPERABILITY_VERSION) => value
+                .as_bytes()
+                .and_then(|b| std::str::from_utf8(b).ok())
+                .map(|s| f.write_str(s)),
+            (IfdKind::Exif, EXPOSURE_PROGRAM) => {
+                as_u16(value).map(|n| write!(f, "{}", ExposureProgram::from_u16(n)))
+            }
+            (IfdKind::Exif, METERING_MODE) => {
+                as_u16(value).map(|n| write!(f, "{}", MeteringMode::from_u16(n)))
+            }
+            (IfdKind::Exif, FLASH) => as_u16(value).map(|n| write!(f, "{}", Flash::from_u16(n))),
+            (IfdKind::Exif, COLOR_SPACE) => {
+                as_u16(value).map(|n| write!(f, "{}", ColorSpace::from_u16(n)))
+            }
+            (IfdKind::Exif, EXPOSURE_MODE) => {
+                as_u16(value).map(|n| write!(f, "{}", ExposureMode::from_u16(n)))
+            }
+            (IfdKind::Exif, WHITE_BALANCE) => {
+                as_u16(value).map(|n| write!(f, "{}", WhiteBalance::from_u16(n)))
+            }
+            (IfdKind::Exif, SCENE_CAPTURE_TYPE) => {
+                as_u16(value).map(|n| write!(f, "{}", SceneCaptureType::from_u16(n)))
+            }
+            _ => None,
+        };
+
+        match decoded {
+            Some(result) => result,
+            None => write!(f, "{}", value),
+        }
+    }
+}
+
+/// A loaded EXIF directory (IFD), consisting of fields.
+///
+/// Fields are stored in the same order they are present in the file. Entries with unknown
+/// types are skipped.
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct Directory {
+    /// Fields of the directory.
+    pub fields: Vec<Field>,
+}
+
+impl Directory {
+    /// Returns the value of a field with the given tag, if it is present.
+    pub fn get(&self, tag: u16) -> Option<&Value> {
+        self.fields.iter().find(|f| f.tag == tag).map(|f| &f.value)
+    }
+
+    fn load<R: Read + Seek>(ifd: Ifd<R>) -> Result<Directory> {
+        let mut fields = Vec::with_capacity(ifd.len() as usize);
+        for entry in ifd {
+            let entry = entry?;
+            if let Some(value) = entry.value() {
+                fields.push(Field {
+                    tag: entry.tag(),
+                    value: value?,
+                });
+            }
+        }
+        Ok(Directory { fields })
+    }
+
+    // loads a sub-IFD pointed to by the field with the given tag, if it is present
+    fn load_sub_ifd<R: Read + Seek>(
+        &self,
+        ifds: &LazyIfds<R>,
+        tag: u16,
+    ) -> Result<Option<Directory>> {
+        match self.get(tag).and_then(Value::as_u32) {
+            Some(offset) => Directory::load(ifds.ifd_at(offset as u64)?).map(Some),
+            None => Ok(None),
+        }
+    }
+}
+
+/// Contains EXIF metadata.
+///
+/// Apart from the raw directories, this structure provides typed accessors for the most
+/// commonly used EXIF 2.3 fields.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Exif {
+    /// The main directory, describing the primary image.
+    pub ifd0: Directory,
+    /// The EXIF sub-IFD, containing camera and capture-related fields, if it is present.
+    pub exif_ifd: Option<Directory>,
+    /// The GPS sub-IFD, containing location information, if it is present.
+    pub gps_ifd: Option<Directory>,
+    /// The Interoperability sub-IFD, if it is present.
+    pub interoperability_ifd: Option<Directory>,
+    /// The second directory, describing the thumbnail image, if it is present.
+    pub ifd1: Option<Directory>,
+}
+
+impl Exif {
+    /// Loads EXIF metadata from the given buffer.
+    ///
+    /// The buffer should contain a TIFF document, optionally prefixed with the `Exif\0\0`
+    /// header which is used in JPEG files and by some WebP encoders.
+    pub fn load(data: &[u8]) -> Result<Exif> {
+        let data = data.strip_prefix(b"Exif\0\0").unwrap_or(data);
+
+        let ifds = TiffReader::new(Cursor::new(data)).ifds()?;
+        Exif::from_ifds(&ifds)
+    }
+
+    /// Loads EXIF metadata from an already opened TIFF document.
+    pub(crate) fn from_ifds<R: Read + Seek>(ifds: &LazyIfds<R>) -> Result<Exif> {
+        let mut ifds_iter = ifds.into_iter();
+        let ifd0 = match ifds_iter.next() {
+            Some(ifd) => Directory::load(ifd?)?,
+            None => return Err(invalid_format!("EXIF data does not contain any IFDs")),
+        };
+        // the thumbnail directory is optional, so an invalid one is ignored like sub-IFDs
+        let ifd1 = ifds_iter
+            .next()
+            .and_then(|ifd| Directory::load(ifd.ok()?).ok());
+
+        Ok(Exif::with_sub_ifds(ifds, ifd0, ifd1))
+    }
+
+    /// Loads EXIF metadata whose IFD0 is located at the specified offset of a TIFF document.
+    ///
+    /// IFD1 is not loaded; this is used for TIFF images, where the next IFD in the chain
+    /// describes the next page rather than a thumbnail.
+    pub(crate) fn from_ifd_at<R: Read + Seek>(ifds: &LazyIfds<R>, offset: u64) -> Result<Exif> {
+        let ifd0 = Directory::load(ifds.ifd_at(offset)?)?;
+        Ok(Exif::with_sub_ifds(ifds, ifd0, None))
+    }
+
+    // sub-IFDs are loaded independently of each other; dangling pointers are common in
+    // edited images, and an invalid sub-IFD should not prevent loading the rest of them
+    fn with_sub_ifds<R: Read + Seek>(
+        ifds: &LazyIfds<R>,
+        ifd0: Directory,
+        ifd1: Option<Directory>,
+    ) -> Exif {
+        let exif_ifd = ifd0.load_sub_ifd(ifds, EXIF_IFD_POINTER).ok().flatten();
+        let gps_ifd = ifd0.load_sub_ifd(ifds, GPS_IFD_POINTER).ok().flatten();
+        let interoperability_ifd = exif_ifd.as_ref().and_then(|d| {
+            d.load_sub_ifd(ifds, INTEROPERABILITY_IFD_POINTER)
+                .ok()
+                .flatten()
+        });
+
+        Exif {
+            ifd0,
+            exif_ifd,
+            gps_ifd,
+            interoperability_ifd,
+            ifd1,
+        }
+    }
+
+    #[inline]
+    fn exif_field(&self, tag: u16) -> Option<&Value> {
+        self.exif_ifd.as_ref().and_then(|d| d.get(tag))
+    }
+
+    /// Returns all present directories together with their kinds, in the following order:
+    /// IFD0, EXIF, GPS, Interoperability, IFD1.
+    pub fn directories(&self) -> Vec<(IfdKind, &Directory)> {
+        let mut result = vec![(IfdKind::Tiff, &self.ifd0)];
+        result.extend(self.exif_ifd.as_ref().map(|d| (IfdKind::Exif, d)));
+        result.extend(self.gps_ifd.as_ref().map(|d| (IfdKind::Gps, d)));
+        result.extend(
+            self.interoperability_ifd
+                .as_ref()
+                .map(|d| (IfdKind::Interoperability, d)),
+        );
+        result.extend(self.ifd1.as_ref().map(|d| (IfdKind::Tiff, d)));
+        result
+    }
+
+    /// Returns the orientation of the image, if it is present and valid.
+    pub fn orientation(&self) -> Option<Orientation> {
+        self.ifd0
+            .get(ORIENTATION)
+            .and_then(as_u16)
+            .and_then(Orientation::from_exif)
+    }
+
+    /// Returns the manufacturer of the recording equipment, if it is present.
+    pub fn make(&self) -> Option<&str> {
+        self.ifd0.get(MAKE).and_then(Value::as_str)
+    }
+
+    /// Returns the model name of the recording equipment, if it is present.
+    pub fn model(&self) -> Option<&str> {
+        self.ifd0.get(MODEL).and_then(Value::as_str)
+    }
+
+    /// Returns the name of the software used to create the image, if it is present.
+    pub fn software(&self) -> Option<&str> {
+        self.ifd0.get(SOFTWARE).and_then(Value::as_str)
+    }
+
+    /// Returns the name of the image creator, if it is present.
+    pub fn artist(&self) -> Option<&str> {
+        self.ifd0.get(ARTIST).and_then(Value::as_str)
+    }
+
+    /// Returns the copyright notice, if it is present.
+    pub fn copyright(&self) -> Option<&str> {
+        self.ifd0.get(COPYRIGHT).and_then(Value::as_str)
+    }
+
+    /// Returns the date and time of file modification, in `YYYY:MM:DD HH:MM:SS` format,
+    /// if it is present.
+    pub fn date_time(&self) -> Option<&str> {
+        self.ifd0.get(DATE_TIME).and_then(Value::as_str)
+    }
+
+    /// Returns the date and time when the original image was captured, in
+    /// `YYYY:MM:DD HH:MM:SS` format, if it is present.
+    pub fn date_time_original(&self) -> Option<&str> {
+        self.exif_field(DATE_TIME_ORIGINAL).and_then(Value::as_str)
+    }
+
+    /// Returns the date and time when the image was stored as digital data, in
+    /// `YYYY:MM:DD HH:MM:SS` format, if it is present.
+    pub fn date_time_digitized(&self) -> Option<&str> {
+        self.exif_field(DATE_TIME_DIGITIZED).and_then(Value::as_str)
+    }
+
+    /// Returns the EXIF version as a four-character string, e.g. `0230`, if it is present.
+    pub fn exif_version(&self) -> Option<&str> {
+        self.exif_field(EXIF_VERSION)
+            .and_then(Value::as_bytes)
+            .and_then(|b| std::str::from_utf8(b).ok())
+    }
+
+    /// Returns the exposure time in seconds as a fraction, if it is present.
+    pub fn exposure_time(&self) -> Option<(u32, u32)> {
+        self.exif_field(EXPOSURE_TIME).and_then(Value::as_rational)
+    }
+
+    /// Returns the F number as a fraction, if it is present.
+    pub fn f_number(&self) -> Option<(u32, u32)> {
+        self.exif_field(F_NUMBER).and_then(Value::as_rational)
+    }
+
+    /// Returns the exposure program, if it is present.
+    pub fn exposure_program(&self) -> Option<ExposureProgram> {
+        self.exif_field(EXPOSURE_PROGRAM)
+            .and_then(as_u16)
+            .map(ExposureProgram::from_u16)
+    }
+
+    /// Returns the metering mode, if it is present.
+    pub fn metering_mode(&self) -> Option<MeteringMode> {
+        self.exif_field(METERING_MODE)
+            .and_then(as_u16)
+            .map(MeteringMode::from_u16)
+    }
+
+    /// Returns the flash status, if it is present.
+    pub fn flash(&self) -> Option<Flash> {
+        self.exif_field(FLASH).and_then(as_u16).map(Flash::from_u16)
+    }
+
+    /// Returns the color space, if it is present.
+    pub fn color_space(&self) -> Option<ColorSpace> {
+        self.exif_field(COLOR_SPACE)
+            .and_then(as_u16)
+            .map(ColorSpace::from_u16)
+    }
+
+    /// Returns the exposure mode, if it is present.
+    pub fn exposure_mode(&self) -> Option<ExposureMode> {
+        self.exif_field(EXPOSURE_MODE)
+            .and_then(as_u16)
+            .map(ExposureMode::from_u16)
+    }
+
+    /// Returns the white balance mode, if it is present.
+    pub fn white_balance(&self) -> Option<WhiteBalance> {
+        self.exif_field(WHITE_BALANCE)
+            .and_then(as_u16)
+            .map(WhiteBalance::from_u16)
+    }
+
+    /// Returns the scene capture type, if it is present.
+    pub fn scene_capture_type(&self) -> Option<SceneCaptureType> {
+        self.exif_field(SCENE_CAPTURE_TYPE)
+            .and_then(as_u16)
+            .map(SceneCaptureType::from_u16)
+    }
+
+    /// Returns the sensitivity of the camera, usually the ISO speed, if it is present.
+    pub fn photographic_sensitivity(&self) -> Option<u32> {
+        self.exif_field(PHOTOGRAPHIC_SENSITIVITY)
+            .and_then(Value::as_u32)
+    }
+
+    /// Returns the exposure bias in APEX units as a fraction, if it is present.
+    pub fn exposure_bias(&self) -> Option<(i32, i32)> {
+        self.exif_field(EXPOSURE_BIAS_VALUE)
+            .and_then(Value::as_signed_rational)
+    }
+
+    /// Returns the focal length of the lens in millimeters as a fraction, if it is present.
+    pub fn focal_length(&self) -> Option<(u32, u32)> {
+        self.exif_field(FOCAL_LENGTH).and_then(Value::as_rational)
+    }
+
+    /// Returns the equivalent focal length for a 35 mm film camera in millimeters, if it
+    /// is present.
+    pub fn focal_length_in_35mm_film(&self) -> Option<u32> {
+        self.exif_field(FOCAL_LENGTH_IN_35MM_FILM)
+            .and_then(Value::as_u32)
+    }
+
+    /// Returns the manufacturer of the lens, if it is present.
+    pub fn lens_make(&self) -> Option<&str> {
+        self.exif_field(LENS_MAKE).and_then(Value::as_str)
+    }
+
+    /// Returns the model name of the lens, if it is present.
+    pub fn lens_model(&self) -> Option<&str> {
+        self.exif_field(LENS_MODEL).and_then(Value::as_str)
+    }
+
+    /// Returns the dimensions of the compressed image, if they are present.
+    pub fn pixel_dimensions(&self) -> Option<Dimensions> {
+        let width = self.exif_field(PIXEL_X_DIMENSION).and_then(Value::as_u32)?;
+        let height = self.exif_field(PIXEL_Y_DIMENSION).and_then(Value::as_u32)?;
+        Some((width, height).into())
+    }
+
+    /// Returns GPS information, if the GPS sub-IFD is present.
+    pub fn gps(&self) -> Option<Gps> {
+        self.gps_ifd.as_ref().map(Gps::load)
+    }
+
+    /// Returns the interoperability rule identifier, e.g. `R98`, if it is present.
+    pub fn interoperability_index(&self) -> Option<&str> {
+        self.interoperability_ifd
+            .as_ref()
+            .and_then(|d| d.get(INTEROPERABILITY_INDEX))
+            .and_then(Value::as_str)
+    }
+
+    /// Returns the offset and the length of the JPEG thumbnail, if it is present.
+    ///
+    /// The offset is relative to the beginning of the TIFF header, i.e. it does not include
+    /// the `Exif\0\0` prefix.
+    pub fn thumbnail_location(&self) -> Option<(u32, u32)> {
+        let ifd1 = self.ifd1.as_ref()?;
+        let offset = ifd1.get(JPEG_INTERCHANGE_FORMAT).and_then(Value::as_u32)?;
+        let length = ifd1
+            .get(JPEG_INTERCHANGE_FORMAT_LENGTH)
+            .and_then(Value::as_u32)?;
+        Some((offset, length))
+    }
+}
@@ -0,0 +1,230 @@
+//! Registry of tags defined by the EXIF 2.3 spec.
+//!
+//! The registry maps tag numbers to their names, the directory they belong to and their
+//! expected type and number of items.
+
+use std::fmt;
+
+use crate::common::tiff::EntryType;
+use crate::common::tiff::EntryType::*;
+
+/// Kind of an EXIF directory.
+///
+/// The same tag number may have different meanings depending on the directory it is
+/// stored in, so tags are looked up by both the directory kind and the number.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum IfdKind {
+    /// IFD0 or IFD1, containing TIFF tags describing the primary image or the thumbnail.
+    Tiff,
+    /// EXIF sub-IFD.
+    Exif,
+    /// GPS sub-IFD.
+    Gps,
+    /// Interoperability sub-IFD.
+    Interoperability,
+}
+
+impl fmt::Display for IfdKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match *self {
+            IfdKind::Tiff => "TIFF",
+            IfdKind::Exif => "EXIF",
+            IfdKind::Gps => "GPS",
+            IfdKind::Interoperability => "Interoperability",
+        })
+    }
+}
+
+/// Describes a known EXIF tag.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct TagInfo {
+    /// Tag number.
+    pub tag: u16,
+    /// Tag name, as it is written in the spec, e.g. `FNumber`.
+    pub name: &'static str,
+    /// Kind of the directory this tag belongs to.
+    pub ifd: IfdKind,
+    /// Types the tag value is allowed to have.
+    pub types: &'static [EntryType],
+    /// Expected number of items in the tag value, or `None` if any number is allowed.
+    ///
+    /// For ASCII values this is the number of bytes including the terminating zero byte.
+    pub count: Option<u64>,
+}
+
+impl TagInfo {
+    /// Checks whether an entry with the given type and number of items conforms to
+    /// this tag definition.
+    pub fn accepts(&self, entry_type: EntryType, count: u64) -> bool {
+        self.types.contains(&entry_type) && self.count.is_none_or(|c| c == count)
+    }
+}
+
+/// Looks up a tag with the given number in the given directory kind.
+pub fn lookup(ifd: IfdKind, tag: u16) -> Option<&'static TagInfo> {
+    TAGS.iter().find(|t| t.ifd == ifd && t.tag == tag)
+}
+
+/// Looks up a tag by its name.
+pub fn lookup_by_name(name: &str) -> Option<&'static TagInfo> {
+    TAGS.iter().find(|t| t.name == name)
+}
+
+macro_rules! tags {
+    ($($ifd:ident, $tag:expr, $name:expr, [$($t:ident),+], $count:tt);+ $(;)*) => {
+        /// All known tags.
+        pub static TAGS: &[TagInfo] = &[
+            $(TagInfo {
+                tag: $tag,
+                name: $name,
+                ifd: IfdKind::$ifd,
+                types: &[$($t),+],
+                count: tags!(@count $count),
+            }),+
+        ];
+    };
+    (@count any) => { None };
+    (@count $n:expr) => { Some($n) };
+}
+
+tags! {
+    Tiff, 0x0100, "ImageWidth", [Short, Long], 1;
+    Tiff, 0x0101, "ImageLength", [Short, Long], 1;
+    Tiff, 0x0102, "BitsPerSample", [Short], 3;
+    Tiff, 0x0103, "Compression", [Short], 1;
+    Tiff, 0x0106, "PhotometricInterpretation", [Short], 1;
+    Tiff, 0x010e, "ImageDescription", [Ascii], any;
+    Tiff, 0x010f, "Make", [Ascii], any;
+    Tiff, 0x0110, "Model", [Ascii], any;
+    Tiff, 0x0111, "StripOffsets", [Short, Long], any;
+    Tiff, 0x0112, "Orientation", [Short], 1;
+    Tiff, 0x0115, "SamplesPerPixel", [Short], 1;
+    Tiff, 0x0116, "RowsPerStrip", [Short, Long], 1;
+    Tiff, 0x0117, "StripByteCounts", [Short, Long], any;
+    Tiff, 0x011a, "XResolution", [Rational], 1;
+    Tiff, 0x011b, "YResolution", [Rational], 1;
+    Tiff, 0x011c, "PlanarConfiguration", [Short], 1;
+    Tiff, 0x0128, "ResolutionUnit", [Short], 1;
+    Tiff, 0x012d, "TransferFunction", [Short], 768;
+    Tiff, 0x0131, "Software", [Ascii], any;
+    Tiff, 0x0132, "DateTime", [Ascii], 20;
+    Tiff, 0x013b, "Artist", [Ascii], any;
+    Tiff, 0x013e, "WhitePoint", [Rational], 2;
+    Tiff, 0x013f, "PrimaryChromaticities", [Rational], 6;
+    Tiff, 0x0201, "JPEGInterchangeFormat", [Long], 1;
+    Tiff, 0x0202, "JPEGInterchangeFormatLength", [Long], 1;
+    Tiff, 0x0211, "YCbCrCoefficients", [Rational], 3;
+    Tiff, 0x0212, "YCbCrSubSampling", [Short], 2;
+    Tiff, 0x0213, "YCbCrPositioning", [Short], 1;
+    Tiff, 0x0214, "ReferenceBlackWhite", [Rational], 6;
+    Tiff, 0x8298, "Copyright", [Ascii], any;
+    Tiff, 0x8769, "ExifIFDPointer", [Long, Ifd], 1;
+    Tiff, 0x8825, "GPSInfoIFDPointer", [Long, Ifd], 1;
+
+    Exif, 0x829a, "ExposureTime", [Rational], 1;
+    Exif, 0x829d, "FNumber", [Rational], 1;
+    Exif, 0x8822, "ExposureProgram", [Short], 1;
+    Exif, 0x8824, "SpectralSensitivity", [Ascii], any;
+    Exif, 0x8827, "PhotographicSensitivity", [Short], any;
+    Exif, 0x8828, "OECF", [Undefined], any;
+    Exif, 0x8830, "SensitivityType", [Short], 1;
+    Exif, 0x8831, "StandardOutputSensitivity", [Long], 1;
+    Exif, 0x8832, "RecommendedExposureIndex", [Long], 1;
+    Exif, 0x8833, "ISOSpeed", [Long], 1;
+    Exif, 0x8834, "ISOSpeedLatitudeyyy", [Long], 1;
+    Exif, 0x8835, "ISOSpeedLatitudezzz", [Long], 1;
+    Exif, 0x9000, "ExifVersion", [Undefined], 4;
+    Exif, 0x9003, "DateTimeOriginal", [Ascii], 20;
+    Exif, 0x9004, "DateTimeDigitized", [Ascii], 20;
+    Exif, 0x9101, "ComponentsConfiguration", [Undefined], 4;
+    Exif, 0x9102, "CompressedBitsPerPixel", [Rational], 1;
+    Exif, 0x9201, "ShutterSpeedValue", [SignedRational], 1;
+    Exif, 0x9202, "ApertureValue", [Rational], 1;
+    Exif, 0x9203, "BrightnessValue", [SignedRational], 1;
+    Exif, 0x9204, "ExposureBiasValue", [SignedRational], 1;
+    Exif, 0x9205, "MaxApertureValue", [Rational], 1;
+    Exif, 0x9206, "SubjectDistance", [Rational], 1;
+    Exif, 0x9207, "MeteringMode", [Short], 1;
+    Exif, 0x9208, "LightSource", [Short], 1;
+    Exif, 0x9209, "Flash", [Short], 1;
+    Exif, 0x920a, "FocalLength", [Rational], 1;
+    Exif, 0x9214, "SubjectArea", [Short], any;
+    Exif, 0x927c, "MakerNote", [Undefined], any;
+    Exif, 0x9286, "UserComment", [Undefined], any;
+    Exif, 0x9290, "SubSecTime", [Ascii], any;
+    Exif, 0x9291, "SubSecTimeOriginal", [Ascii], any;
+    Exif, 0x9292, "SubSecTimeDigitized", [Ascii], any;
+    Exif, 0xa000, "FlashpixVersion", [Undefined], 4;
+    Exif, 0xa001, "ColorSpace", [Short], 1;
+    Exif, 0xa002, "PixelXDimension", [Short, Long], 1;
+    Exif, 0xa003, "PixelYDimension", [Short, Long], 1;
+    Exif, 0xa004, "RelatedSoundFile", [Ascii], 13;
+    Exif, 0xa005, "InteroperabilityIFDPointer", [Long, Ifd], 1;
+    Exif, 0xa20b, "FlashEnergy", [Rational], 1;
+    Exif, 0xa20c, "SpatialFrequencyResponse", [Undefined], any;
+    Exif, 0xa20e, "FocalPlaneXResolution", [Rational], 1;
+    Exif, 0xa20f, "FocalPlaneYResolution", [Rational], 1;
+    Exif, 0xa210, "FocalPlaneResolutionUnit", [Short], 1;
+    Exif, 0xa214, "SubjectLocation", [Short], 2;
+    Exif, 0xa215, "ExposureIndex", [Rational], 1;
+    Exif, 0xa217, "SensingMethod", [Short], 1;
+    Exif, 0xa300, "FileSource", [Undefined], 1;
+    Exif, 0xa301, "SceneType", [Undefined], 1;
+    Exif, 0xa302, "CFAPattern", [Undefined], any;
+    Exif, 0xa401, "CustomRendered", [Short], 1;
+    Exif, 0xa402, "ExposureMode", [Short], 1;
+    Exif, 0xa403, "WhiteBalance", [Short], 1;
+    Exif, 0xa404, "DigitalZoomRatio", [Rational], 1;
+    Exif, 0xa405, "FocalLengthIn35mmFilm", [Short], 1;
+    Exif, 0xa406, "SceneCaptureType", [Short], 1;
+    Exif, 0xa407, "GainControl", [Short], 1;
+    Exif, 0xa408, "Contrast", [Short], 1;
+    Exif, 0xa409, "Saturation", [Short], 1;
+    Exif, 0xa40a, "Sharpness", [Short], 1;
+    Exif, 0xa40b, "DeviceSettingDescription", [Undefined], any;
+    Exif, 0xa40c, "SubjectDistanceRange", [Short], 1;
+    Exif, 0xa420, "ImageUniqueID", [Ascii], 33;
+    Exif, 0xa430, "CameraOwnerName", [Ascii], any;
+    Exif, 0xa431, "BodySerialNumber", [Ascii], any;
+    Exif, 0xa432, "LensSpecification", [Rational], 4;
+    Exif, 0xa433, "LensMake", [Ascii], any;
+    Exif, 0xa434, "LensModel", [Ascii], any;
+    Exif, 0xa435, "LensSerialNumber", [Ascii], any;
+    Exif, 0xa500, "Gamma", [Rational], 1;
+
+    Gps, 0x0000, "GPSVersionID", [Byte], 4;
+    Gps, 0x0001, "GPSLatitudeRef", [Ascii], 2;
+    Gps, 0x0002, "GPSLatitude", [Rational], 3;
+    Gps, 0x0003, "GPSLongitudeRef", [Ascii], 2;
+    Gps, 0x0004, "GPSLongitude", [Rational], 3;
+    Gps, 0x0005, "GPSAltitudeRef", [Byte], 1;
+    Gps, 0x0006, "GPSAltitude", [Rational], 1;
+    Gps, 0x0007, "GPSTimeStamp", [Rational], 3;
+    Gps, 0x0008, "GPSSatellites", [Ascii], any;
+    Gps, 0x0009, "GPSStatus", [Ascii], 2;
+    Gps, 0x000a, "GPSMeasureMode", [Ascii], 2;
+    Gps, 0x000b, "GPSDOP", [Rational], 1;
+    Gps, 0x000c, "GPSSpeedRef", [Ascii], 2;
+    Gps, 0x000d, "GPSSpeed", [Rational], 1;
+    Gps, 0x000e, "GPSTrackRef", [Ascii], 2;
+    Gps, 0x000f, "GPSTrack", [Rational], 1;
+    Gps, 0x0010, "GPSImgDirectionRef", [Ascii], 2;
+    Gps, 0x0011, "GPSImgDirection", [Rational], 1;
+    Gps, 0x0012, "GPSMapDatum", [Ascii], any;
+    Gps, 0x0013, "GPSDestLatitudeRef", [Ascii], 2;
+    Gps, 0x0014, "GPSDestLatitude", [Rational], 3;
+    Gps, 0x0015, "GPSDestLongitudeRef", [Ascii], 2;
+    Gps, 0x0016, "GPSDestLongitude", [Rational], 3;
+    Gps, 0x0017, "GPSDestBearingRef", [Ascii], 2;
+    Gps, 0x0018, "GPSDestBearing", [Rational], 1;
+    Gps, 0x0019, "GPSDestDistanceRef", [Ascii], 2;
+    Gps, 0x001a, "GPSDestDistance", [Rational], 1;
+    Gps, 0x001b, "GPSProcessingMethod", [Undefined], any;
+    Gps, 0x001c, "GPSAreaInformation", [Undefined], any;
+    Gps, 0x001d, "GPSDateStamp", [Ascii], 11;
+    Gps, 0x001e, "GPSDifferential", [Short], 1;
+    Gps, 0x001f, "GPSHPositioningError", [Rational], 1;
+
+    Interoperability, 0x0001, "InteroperabilityIndex", [Ascii], any;
+    Interoperability, 0x0002, "InteroperabilityVersion", [Undefined], 4;
+}
@@ -0,0 +1,320 @@
+//! ICC color profile support.
+//!
+//! ICC profiles are embedded into images to describe their color spaces. This module parses
+//! the profile header and the description and copyright tags, which is enough to identify
+//! a profile; the color transformation data is not interpreted.
+
+use std::fmt;
+
+use byteorder::{BigEndian, ByteOrder};
+
+use crate::types::Result;
+
+const HEADER_SIZE: usize = 128;
+
+const DESCRIPTION_TAG: &[u8] = b"desc";
+const COPYRIGHT_TAG: &[u8] = b"cprt";
+
+/// Profile format version.
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub struct Version {
+    pub major: u8,
+    pub minor: u8,
+    pub bugfix: u8,
+}
+
+impl fmt::Display for Version {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.bugfix)
+    }
+}
+
+/// Profile/device class.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum DeviceClass {
+    Input,
+    Display,
+    Output,
+    DeviceLink,
+    ColorSpace,
+    Abstract,
+    NamedColor,
+    Unknown([u8; 4]),
+}
+
+impl fmt::Display for DeviceClass {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            DeviceClass::Input => f.write_str("Input device"),
+            DeviceClass::Display => f.write_str("Display device"),
+            DeviceClass::Output => f.write_str("Output device"),
+            DeviceClass::DeviceLink => f.write_str("Device link"),
+            DeviceClass::ColorSpace => f.write_str("Color space"),
+            DeviceClass::Abstract => f.write_str("Abstract"),
+            DeviceClass::NamedColor => f.write_str("Named color"),
+            DeviceClass::Unknown(s) => write!(f, "Unknown ({})", signature_str(&s)),
+        }
+    }
+}
+
+impl DeviceClass {
+    fn from_signature(s: [u8; 4]) -> DeviceClass {
+        match &s {
+            b"scnr" => DeviceClass::Input,
+            b"mntr" => DeviceClass::Display,
+            b"prtr" => DeviceClass::Output,
+            b"link" => DeviceClass::DeviceLink,
+            b"spac" => DeviceClass::ColorSpace,
+            b"abst" => DeviceClass::Abstract,
+            b"nmcl" => DeviceClass::NamedColor,
+            _ => DeviceClass::Unknown(s),
+        }
+    }
+}
+
+/// Color space of data or profile connection space.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum ColorSpace {
+    Xyz,
+    Lab,
+    Luv,
+    YCbCr,
+    Yxy,
+    Rgb,
+    Gray,
+    Hsv,
+    Hls,
+    Cmyk,
+    Cmy,
+    /// A generic color space with the given number of channels, from 2 to 15.
+    Color(u8),
+    Unknown([u8; 4]),
+}
+
+impl fmt::Display for ColorSpace {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ColorSpace::Xyz => f.write_str("XYZ"),
+            ColorSpace::Lab => f.write_str("Lab"),
+            ColorSpace::Luv => f.write_str("Luv"),
+            ColorSpace::YCbCr => f.write_str("YCbCr"),
+            ColorSpace::Yxy => f.write_str("Yxy"),
+            ColorSpace::Rgb => f.write_str("RGB"),
+            ColorSpace::Gray => f.write_str("Gray"),
+            ColorSpace::Hsv => f.write_str("HSV"),
+            ColorSpace::Hls => f.write_str("HLS"),
+            ColorSpace::Cmyk => f.write_str("CMYK"),
+            ColorSpace::Cmy => f.write_str("CMY"),
+            ColorSpace::Color(n) => write!(f, "{n} color"),
+            ColorSpace::Unknown(s) => write!(f, "Unknown ({})", signature_str(&s)),
+        }
+    }
+}
+
+impl ColorSpace {
+    fn from_signature(s: [u8; 4]) -> ColorSpace {
+        match &s {
+            b"XYZ " => ColorSpace::Xyz,
+            b"Lab " => ColorSpace::Lab,
+            b"Luv " => ColorSpace::Luv,
+            b"YCbr" => ColorSpace::YCbCr,
+            b"Yxy " => ColorSpace::Yxy,
+            b"RGB " => ColorSpace::Rgb,
+            b"GRAY" => ColorSpace::Gray,
+            b"HSV " => ColorSpace::Hsv,
+            b"HLS " => ColorSpace::Hls,
+            b"CMYK" => ColorSpace::Cmyk,
+            b"CMY " => ColorSpace::Cmy,
+            [n, b'C', b'L', b'R'] => match (*n as char).to_digit(16) {
+                Some(n @ 2..=15) => ColorSpace::Color(n as u8),
+                _ => ColorSpace::Unknown(s),
+            },
+            _ => ColorSpace::Unknown(s),
+        }
+    }
+
+    /// Returns the number of channels in this color space, if it is known.
+    pub fn channels(&self) -> Option<u8> {
+        match *self {
+            ColorSpace::Gray => Some(1),
+            ColorSpace::Cmyk => Some(4),
+            ColorSpace::Color(n) => Some(n),
+            ColorSpace::Unknown(_) => None,
+            _ => Some(3),
+        }
+    }
+}
+
+/// Rendering intent of a profile.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum RenderingIntent {
+    Perceptual,
+    MediaRelativeColorimetric,
+    Saturation,
+    IccAbsoluteColorimetric,
+    Unknown(u32),
+}
+
+impl fmt::Display for RenderingIntent {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            RenderingIntent::Perceptual => f.write_str("Perceptual"),
+            RenderingIntent::MediaRelativeColorimetric => {
+                f.write_str("Media-relative colorimetric")
+            }
+            RenderingIntent::Saturation => f.write_str("Saturation"),
+            RenderingIntent::IccAbsoluteColorimetric => f.write_str("ICC-absolute colorimetric"),
+            RenderingIntent::Unknown(n) => write!(f, "Unknown ({n})"),
+        }
+    }
+}
+
+impl RenderingIntent {
+    pub(crate) fn from_u32(n: u32) -> RenderingIntent {
+        match n {
+            0 => RenderingIntent::Perceptual,
+            1 => RenderingIntent::MediaRelativeColorimetric,
+            2 => RenderingIntent::Saturation,
+            3 => RenderingIntent::IccAbsoluteColorimetric,
+            n => RenderingIntent::Unknown(n),
+        }
+    }
+}
+
+/// Information about an ICC profile.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Profile {
+    /// Profile size declared in the header.
+    pub size: u32,
+    /// Signature of the preferred color management module, if it is set.
+    pub cmm: Option<[u8; 4]>,
+    /// Profile format version.
+    pub version: Version,
+    /// Profile/device class.
+    pub device_class: DeviceClass,
+    /// Color space of the data, e.g. RGB for images.
+    pub color_space: ColorSpace,
+    /// Profile connection space, XYZ or Lab for most profiles.
+    pub connection_space: ColorSpace,
+    /// Rendering intent.
+    pub rendering_intent: RenderingIntent,
+    /// Signature of the profile creator, if it is set.
+    pub creator: Option<[u8; 4]>,
+    /// Profile description, e.g. `sRGB IEC61966-2.1` or `Display P3`.
+    ///
+    /// For multi-localized descriptions, the US English one is preferred.
+    pub description: Option<String>,
+    /// Copyright notice.
+    pub copyright: Option<String>,
+}
+
+impl Profile {
+    /// Parses the header and the tag table of the given profile.
+    pub fn parse(data: &[u8]) -> Result<Profile> {
+        if data.len() < HEADER_SIZE + 4 {
+            return Err(unexpected_eof!("when reading ICC profile header"));
+        }
+        if &data[36..40] != b"acsp" {
+            return Err(invalid_format!("invalid ICC profile signature"));
+        }
+
+        let signature = |offset: usize| {
+            let mut s = [0u8; 4];
+            s.copy_from_slice(&data[offset..offset + 4]);
+            s
+        };
+        let optional_signature = |offset: usize| Some(signature(offset)).filter(|s| s != &[0; 4]);
+
+        let mut profile = Profile {
+            size: BigEndian::read_u32(&data[0..4]),
+            cmm: optional_signature(4),
+            version: Version {
+                major: data[8],
+                minor: data[9] >> 4,
+                bugfix: data[9] & 0x0f,
+            },
+            device_class: DeviceClass::from_signature(signature(12)),
+            color_space: ColorSpace::from_signature(signature(16)),
+            connection_space: ColorSpace::from_signature(signature(20)),
+            rendering_intent: RenderingIntent::from_u32(BigEndian::read_u32(&data[64..68])),
+            creator: optional_signature(80),
+            description: None,
+            copyright: None,
+        };
+
+        // tag table follows the header; invalid tags are ignored
+        let count = BigEndian::read_u32(&data[HEADER_SIZE..HEADER_SIZE + 4]) as usize;
+        let table = &data[HEADER_SIZE + 4..];
+        for entry in table.chunks_exact(12).take(count) {
+            let offset = BigEndian::read_u32(&entry[4..8]) as usize;
+            let size = BigEndian::read_u32(&entry[8..12]) as usize;
+            let tag = match offset
+                .checked_add(size)
+                .and_then(|end| data.get(offset..end))
+            {
+                Some(tag) => tag,
+                None => continue,
+            };
+            match &entry[..4] {
+                DESCRIPTION_TAG => profile.description = read_text(tag),
+                COPYRIGHT_TAG => profile.copyright = read_text(tag),
+                _ => {}
+            }
+        }
+
+        Ok(profile)
+    }
+}
+
+fn signature_str(s: &[u8; 4]) -> String {
+    s.iter().map(|&b| b as char).collect()
+}
+
+// reads a textual tag of any of the types used for descriptions and copyrights
+fn read_text(tag: &[u8]) -> Option<String> {
+    if tag.len() < 8 {
+        return None;
+    }
+    let text = match &tag[..4] {
+        // textDescriptionType (ICC v2): ASCII length, ASCII string and localized variants
+        b"desc" => {
+            let len = BigEndian::read_u32(tag.get(8..12)?) as usize;
+            ascii(tag.get(12..12usize.checked_add(len)?)?)
+        }
+        // textType (ICC v2)
+        b"text" => ascii(&tag[8..]),
+        // multiLocalizedUnicodeType (ICC v4): records with UTF-16 strings
+        b"mluc" => {
+            let count = BigEndian::read_u32(tag.get(8..12)?) as usize;
+            let record_size = BigEndian::read_u32(tag.get(12..16)?) as usize;
+            if record_size < 12 {
+                return None;
+            }
+            // the declared count is not trusted beyond the records which fit into the tag
+            let count = count.min((tag.len() - 16) / record_size);
+            let mut records = (0..count).map(|i| &tag[16 + i * record_size..][..12]);
+            let record = records
+                .clone()
+                .find(|r| &r[..4] == b"enUS")
+                .or_else(|| records.next())?;
+            let len = BigEndian::read_u32(&record[4..8]) as usize;
+            let offset = BigEndian::read_u32(&record[8..12]) as usize;
+            let units: Vec<u16> = tag
+                .get(offset..offset.checked_add(len)?)?
+                .chunks_exact(2)
+                .map(BigEndian::read_u16)
+                .collect();
+            String::from_utf16_lossy(&units)
+        }
+        _ => return None,
+    };
+    Some(text.trim_end_matches('\0').to_owned())
+}
+
+// reads a NUL-terminated string
+fn ascii(data: &[u8]) -> String {
+    data.iter()
+        .take_while(|&&b| b != 0)
+        .map(|&b| b as char)
+        .collect()
+}
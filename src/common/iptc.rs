@@ -0,0 +1,210 @@
+//! IPTC-IIM (Information Interchange Model) metadata support.
+//!
+//! IIM metadata consists of datasets, each identified by a record number and a dataset
+//! number. In images it is usually stored in the IPTC-NAA Photoshop image resource.
+//! This module decodes the most commonly used datasets of the application record into
+//! the `Iptc` structure, while keeping all datasets available in their raw form.
+
+use byteorder::{BigEndian, ByteOrder};
+
+use crate::types::Result;
+
+/// Envelope record number.
+pub const ENVELOPE_RECORD: u8 = 1;
+/// Application record number.
+pub const APPLICATION_RECORD: u8 = 2;
+
+// envelope record datasets
+const CODED_CHARACTER_SET: u8 = 90;
+
+// application record datasets
+const OBJECT_NAME: u8 = 5;
+const KEYWORDS: u8 = 25;
+const DATE_CREATED: u8 = 55;
+const TIME_CREATED: u8 = 60;
+const BYLINE: u8 = 80;
+const BYLINE_TITLE: u8 = 85;
+const CITY: u8 = 90;
+const SUBLOCATION: u8 = 92;
+const PROVINCE_STATE: u8 = 95;
+const COUNTRY_CODE: u8 = 100;
+const COUNTRY_NAME: u8 = 101;
+const HEADLINE: u8 = 105;
+const CREDIT: u8 = 110;
+const SOURCE: u8 = 115;
+const COPYRIGHT_NOTICE: u8 = 116;
+const CAPTION: u8 = 120;
+const WRITER: u8 = 122;
+
+// ISO 2022 escape sequence designating UTF-8
+const UTF8_ESCAPE: &[u8] = b"\x1b%G";
+
+/// A single IIM dataset.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct DataSet {
+    /// Record number, e.g. `APPLICATION_RECORD`.
+    pub record: u8,
+    /// Dataset number within the record.
+    pub number: u8,
+    /// Raw contents of the dataset.
+    pub data: Vec<u8>,
+}
+
+/// IPTC-IIM metadata.
+///
+/// Text fields are decoded according to the coded character set dataset of the envelope
+/// record. If the character set is not declared, text is decoded as UTF-8 when it is
+/// valid and as ISO 8859-1 otherwise, because many writers omit the declaration.
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct Iptc {
+    /// Whether the coded character set is declared to be UTF-8.
+    pub utf8: bool,
+    /// Object name (2:05), a shorthand reference to the object.
+    pub object_name: Option<String>,
+    /// Keywords (2:25).
+    pub keywords: Vec<String>,
+    /// Date the intellectual content was created (2:55), in `CCYYMMDD` format.
+    pub date_created: Option<String>,
+    /// Time the intellectual content was created (2:60), in `HHMMSS±HHMM` format.
+    pub time_created: Option<String>,
+    /// Names of the creators (2:80).
+    pub by_line: Vec<String>,
+    /// Titles of the creators (2:85).
+    pub by_line_title: Vec<String>,
+    /// City (2:90).
+    pub city: Option<String>,
+    /// Sublocation within the city (2:92).
+    pub sublocation: Option<String>,
+    /// Province or state (2:95).
+    pub province_state: Option<String>,
+    /// Country code (2:100).
+    pub country_code: Option<String>,
+    /// Country name (2:101).
+    pub country_name: Option<String>,
+    /// Headline (2:105).
+    pub headline: Option<String>,
+    /// Credit line (2:110).
+    pub credit: Option<String>,
+    /// Original owner of the intellectual content (2:115).
+    pub source: Option<String>,
+    /// Copyright notice (2:116).
+    pub copyright_notice: Option<String>,
+    /// Caption or abstract (2:120).
+    pub caption: Option<String>,
+    /// Writer or editor of the caption (2:122).
+    pub writer: Option<String>,
+    /// All datasets in the order they are stored.
+    pub datasets: Vec<DataSet>,
+}
+
+impl Iptc {
+    /// Loads IIM metadata from the given data.
+    pub fn load(data: &[u8]) -> Result<Iptc> {
+        let datasets = read_datasets(data)?;
+
+        let mut iptc = Iptc {
+            utf8: datasets.iter().any(|d| {
+                d.record == ENVELOPE_RECORD
+                    && d.number == CODED_CHARACTER_SET
+                    && d.data.starts_with(UTF8_ESCAPE)
+            }),
+            ..Iptc::default()
+        };
+
+        for dataset in &datasets {
+            if dataset.record != APPLICATION_RECORD {
+                continue;
+            }
+            let text = decode_text(&dataset.data, iptc.utf8);
+            match dataset.number {
+                OBJECT_NAME => iptc.object_name = Some(text),
+                KEYWORDS => iptc.keywords.push(text),
+                DATE_CREATED => iptc.date_created = Some(text),
+                TIME_CREATED => iptc.time_created = Some(text),
+                BYLINE => iptc.by_line.push(text),
+                BYLINE_TITLE => iptc.by_line_title.push(text),
+                CITY => iptc.city = Some(text),
+                SUBLOCATION => iptc.sublocation = Some(text),
+                PROVINCE_STATE => iptc.province_state = Some(text),
+                COUNTRY_CODE => iptc.country_code = Some(text),
+                COUNTRY_NAME => iptc.country_name = Some(text),
+                HEADLINE => iptc.headline = Some(text),
+                CREDIT => iptc.credit = Some(text),
+                SOURCE => iptc.source = Some(text),
+                COPYRIGHT_NOTICE => iptc.copyright_notice = Some(text),
+                CAPTION => iptc.caption = Some(text),
+                WRITER => iptc.writer = Some(text),
+                _ => {}
+            }
+        }
+
+        iptc.datasets = datasets;
+        Ok(iptc)
+    }
+
+    /// Returns datasets with the given record and dataset numbers.
+    pub fn get(&self, record: u8, number: u8) -> impl Iterator<Item = &DataSet> {
+        self.datasets
+            .iter()
+            .filter(move |d| d.record == record && d.number == number)
+    }
+}
+
+fn read_datasets(mut data: &[u8]) -> Result<Vec<DataSet>> {
+    let mut datasets = Vec::new();
+    // some writers pad the data with zeros
+    while !data.is_empty() && data[0] != 0 {
+        if data[0] != 0x1c {
+            return Err(invalid_format!(
+                "invalid IPTC dataset tag marker: {:#x}",
+                data[0]
+            ));
+        }
+        if data.len() < 5 {
+            return Err(unexpected_eof!("when reading IPTC dataset header"));
+        }
+        let record = data[1];
+        let number = data[2];
+        let mut size = BigEndian::read_u16(&data[3..5]) as usize;
+        data = &data[5..];
+
+        // extended datasets store the number of bytes of their actual length instead
+        if size & 0x8000 != 0 {
+            let n = size & 0x7fff;
+            if n > 4 || data.len() < n {
+                return Err(invalid_format!(
+                    "invalid length of IPTC dataset {}:{}",
+                    record,
+                    number
+                ));
+            }
+            size = data[..n].iter().fold(0, |s, &b| (s << 8) | b as usize);
+            data = &data[n..];
+        }
+
+        if data.len() < size {
+            return Err(unexpected_eof!(
+                "when reading IPTC dataset {}:{}",
+                record,
+                number
+            ));
+        }
+        datasets.push(DataSet {
+            record,
+            number,
+            data: data[..size].to_vec(),
+        });
+        data = &data[size..];
+    }
+    Ok(datasets)
+}
+
+fn decode_text(data: &[u8], utf8: bool) -> String {
+    let data = data.strip_suffix(b"\0").unwrap_or(data);
+    match std::str::from_utf8(data) {
+        Ok(s) => s.to_owned(),
+        Err(_) if utf8 => String::from_utf8_lossy(data).into_owned(),
+        // ISO 8859-1 maps directly to the first 256 code points
+        Err(_) => data.iter().map(|&b| b as char).collect(),
+    }
+}
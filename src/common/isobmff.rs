@@ -0,0 +1,221 @@
+//! ISO base media file format (ISO/IEC 14496-12) support.
+//!
+//! Files in this format, e.g. HEIF and AVIF images, consist of boxes. Each box starts with
+//! its size and four-character type and may contain other boxes. This module provides
+//! a reader of top-level boxes of a stream and an iterator over boxes contained in
+//! the payload of another box.
+
+use std::fmt;
+use std::io::Read;
+use std::str;
+
+use byteorder::{BigEndian, ByteOrder, ReadBytesExt};
+
+use crate::types::Result;
+use crate::utils::ReadExt;
+
+/// A four-character code, used as box types and brands.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub struct FourCc(pub [u8; 4]);
+
+impl FourCc {
+    #[inline]
+    pub fn as_str(&self) -> Option<&str> {
+        str::from_utf8(&self.0).ok()
+    }
+
+    #[inline]
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+
+    fn read(data: &[u8]) -> FourCc {
+        let mut code = [0u8; 4];
+        code.copy_from_slice(&data[..4]);
+        FourCc(code)
+    }
+}
+
+impl fmt::Display for FourCc {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.as_str() {
+            Some(s) => f.write_str(s),
+            None => write!(f, "{:?}", self.as_bytes()),
+        }
+    }
+}
+
+/// Header of a box.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct BoxHeader {
+    /// Box type.
+    pub box_type: FourCc,
+    /// Size of the header, including the extended type of `uuid` boxes.
+    pub header_size: u64,
+    /// Size of the box payload, or `None` if the box extends to the end of the file.
+    pub data_size: Option<u64>,
+}
+
+/// Reads the header of the next box from the stream.
+///
+/// Returns `None` if the stream has ended before the box.
+pub fn read_box_header<R: ?Sized + Read>(r: &mut R) -> Result<Option<BoxHeader>> {
+    let mut header = [0u8; 8];
+    match r.read_exact_0(&mut header)? {
+        0 => return Ok(None),
+        8 => {}
+        _ => return Err(unexpected_eof!("when reading box header")),
+    }
+    let box_type = FourCc::read(&header[4..]);
+
+    let mut header_size = 8;
+    let size = match BigEndian::read_u32(&header[..4]) {
+        0 => None,
+        1 => {
+            header_size += 8;
+            let size = try_if_eof!(
+                r.read_u64::<BigEndian>(),
+                "when reading size of box {}",
+                box_type
+            );
+            Some(size)
+        }
+        size => Some(size as u64),
+    };
+    if box_type.as_bytes() == b"uuid" {
+        let mut user_type = [0u8; 16];
+        r.read_exact(&mut user_type).map_err(if_eof!(
+            std,
+            "when reading extended type of box {}",
+            box_type
+        ))?;
+        header_size += 16;
+    }
+
+    let data_size = match size {
+        Some(size) => Some(
+            size.checked_sub(header_size)
+                .ok_or_else(|| invalid_format!("invalid size of box {}: {}", box_type, size))?,
+        ),
+        None => None,
+    };
+
+    Ok(Some(BoxHeader {
+        box_type,
+        header_size,
+        data_size,
+    }))
+}
+
+/// An iterator over boxes contained in a buffer, yielding their types and payloads.
+pub struct Boxes<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Boxes<'a> {
+    /// Creates an iterator over boxes in the given buffer.
+    pub fn new(data: &'a [u8]) -> Boxes<'a> {
+        Boxes { data }
+    }
+}
+
+impl<'a> Iterator for Boxes<'a> {
+    type Item = Result<(FourCc, &'a [u8])>;
+
+    fn next(&mut self) -> Option<Result<(FourCc, &'a [u8])>> {
+        if self.data.is_empty() {
+            return None;
+        }
+
+        let mut r = self.data;
+        let header = match read_box_header(&mut r) {
+            Ok(header) => header?,
+            Err(e) => {
+                self.data = &[];
+                return Some(Err(e));
+            }
+        };
+        let size = header.data_size.unwrap_or(r.len() as u64);
+        if size > r.len() as u64 {
+            self.data = &[];
+            return Some(Err(unexpected_eof!(
+                "when reading payload of box {}",
+                header.box_type
+            )));
+        }
+
+        let (data, rest) = r.split_at(size as usize);
+        self.data = rest;
+        Some(Ok((header.box_type, data)))
+    }
+}
+
+/// Splits the payload of a full box into its version, flags and the rest of the payload.
+pub fn full_box(data: &[u8]) -> Result<(u8, u32, &[u8])> {
+    if data.len() < 4 {
+        return Err(unexpected_eof!("when reading full box header"));
+    }
+    let flags = BigEndian::read_u32(&data[..4]) & 0xffffff;
+    Ok((data[0], flags, &data[4..]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{full_box, read_box_header, BoxHeader, Boxes, FourCc};
+
+    #[test]
+    fn test_box_header() {
+        let mut data: &[u8] = b"\0\0\0\x0cftypabcd";
+        assert_eq!(
+            read_box_header(&mut data).unwrap(),
+            Some(BoxHeader {
+                box_type: FourCc(*b"ftyp"),
+                header_size: 8,
+                data_size: Some(4),
+            })
+        );
+        assert_eq!(data, b"abcd");
+
+        let mut data: &[u8] = b"\0\0\0\x01mdat\0\0\0\0\0\0\0\x12xy";
+        let header = read_box_header(&mut data).unwrap().unwrap();
+        assert_eq!(header.header_size, 16);
+        assert_eq!(header.data_size, Some(2));
+
+        let mut data: &[u8] = b"\0\0\0\0mdat";
+        let header = read_box_header(&mut data).unwrap().unwrap();
+        assert_eq!(header.data_size, None);
+
+        let mut data: &[u8] = b"\0\0\0\x04free";
+        assert!(read_box_header(&mut data).is_err());
+
+        let mut data: &[u8] = b"";
+        assert_eq!(read_box_header(&mut data).unwrap(), None);
+    }
+
+    #[test]
+    fn test_boxes() {
+        let data = b"\0\0\0\x09abcd1\0\0\0\x08efgh\0\0\0\0ijkl234";
+        let boxes: Vec<_> = Boxes::new(data).map(|b| b.unwrap()).collect();
+        assert_eq!(
+            boxes,
+            vec![
+                (FourCc(*b"abcd"), &b"1"[..]),
+                (FourCc(*b"efgh"), &b""[..]),
+                (FourCc(*b"ijkl"), &b"234"[..]),
+            ]
+        );
+
+        let data = b"\0\0\0\x09abcd1\0\0\0\x10efgh";
+        let mut boxes = Boxes::new(data);
+        assert!(boxes.next().unwrap().is_ok());
+        assert!(boxes.next().unwrap().is_err());
+        assert!(boxes.next().is_none());
+    }
+
+    #[test]
+    fn test_full_box() {
+        let (version, flags, rest) = full_box(b"\x01\0\0\x03xyz").unwrap();
+        assert_eq!((version, flags, rest), (1, 3, &b"xyz"[..]));
+        assert!(full_box(b"\x01\0").is_err());
+    }
+}
@@ -1,2 +1,8 @@
+pub mod exif;
+pub mod icc;
+pub mod iptc;
+pub mod isobmff;
+pub mod photoshop;
 pub mod riff;
 pub mod tiff;
+pub mod xmp;
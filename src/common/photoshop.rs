@@ -0,0 +1,210 @@
+//! Photoshop image resources support.
+//!
+//! Image resources are stored by Photoshop and other applications in JPEG APP13 segments
+//! and in TIFF files. Each resource is identified by a numeric id; this module provides
+//! generic access to all resources and decoders for some of them, including IPTC-IIM
+//! metadata, resolution information and the embedded thumbnail.
+
+use std::fmt;
+
+use byteorder::{BigEndian, ByteOrder};
+
+use crate::common::iptc::Iptc;
+use crate::types::{Dimensions, Result};
+
+/// Signature of Photoshop image resources in JPEG APP13 segments.
+pub const APP13_SIGNATURE: &[u8] = b"Photoshop 3.0\0";
+
+/// Id of the resolution information resource.
+pub const RESOLUTION_INFO: u16 = 0x03ed;
+/// Id of the thumbnail resource written by Photoshop 4.0, with BGR pixel order.
+pub const THUMBNAIL_PS4: u16 = 0x0409;
+/// Id of the IPTC-NAA record resource.
+pub const IPTC_NAA: u16 = 0x0404;
+/// Id of the thumbnail resource written by Photoshop 5.0 and later.
+pub const THUMBNAIL: u16 = 0x040c;
+/// Id of the ICC profile resource.
+pub const ICC_PROFILE: u16 = 0x040f;
+
+/// A single image resource.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Resource {
+    /// Resource id.
+    pub id: u16,
+    /// Resource name, usually empty.
+    pub name: String,
+    /// Raw contents of the resource.
+    pub data: Vec<u8>,
+}
+
+/// Unit of resolution values.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum ResolutionUnit {
+    PixelsPerInch,
+    PixelsPerCentimeter,
+    Unknown(u16),
+}
+
+impl fmt::Display for ResolutionUnit {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ResolutionUnit::PixelsPerInch => f.write_str("pixels per inch"),
+            ResolutionUnit::PixelsPerCentimeter => f.write_str("pixels per centimeter"),
+            ResolutionUnit::Unknown(n) => write!(f, "unknown ({n})"),
+        }
+    }
+}
+
+impl ResolutionUnit {
+    fn from_u16(n: u16) -> ResolutionUnit {
+        match n {
+            1 => ResolutionUnit::PixelsPerInch,
+            2 => ResolutionUnit::PixelsPerCentimeter,
+            n => ResolutionUnit::Unknown(n),
+        }
+    }
+}
+
+/// Resolution information resource.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct ResolutionInfo {
+    /// Horizontal resolution.
+    pub horizontal: f64,
+    /// Unit of the horizontal resolution.
+    pub horizontal_unit: ResolutionUnit,
+    /// Vertical resolution.
+    pub vertical: f64,
+    /// Unit of the vertical resolution.
+    pub vertical_unit: ResolutionUnit,
+}
+
+/// Thumbnail resource.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Thumbnail {
+    /// Thumbnail format: 1 for JPEG, 0 for raw RGB.
+    pub format: u32,
+    /// Thumbnail size.
+    pub dimensions: Dimensions,
+    /// Number of bits per pixel.
+    pub bits_per_pixel: u16,
+    /// Thumbnail image data, a complete JFIF image for the JPEG format.
+    pub data: Vec<u8>,
+}
+
+impl Thumbnail {
+    /// Returns `true` if the thumbnail is a JPEG image.
+    #[inline]
+    pub fn is_jpeg(&self) -> bool {
+        self.format == 1
+    }
+}
+
+/// A collection of image resources.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct ImageResources {
+    /// All resources in the order they are stored.
+    pub resources: Vec<Resource>,
+}
+
+impl ImageResources {
+    /// Loads image resources from the given data.
+    ///
+    /// The data may start with the `Photoshop 3.0` signature used in JPEG APP13 segments.
+    pub fn load(data: &[u8]) -> Result<ImageResources> {
+        let mut data = data.strip_prefix(APP13_SIGNATURE).unwrap_or(data);
+        let mut resources = Vec::new();
+
+        while !data.is_empty() {
+            // some writers pad the data with zeros
+            if data.iter().all(|&b| b == 0) {
+                break;
+            }
+            if data.len() < 7 {
+                return Err(unexpected_eof!("when reading image resource header"));
+            }
+            // "8BIM" is the standard signature, others were used by old applications
+            match &data[..4] {
+                b"8BIM" | b"MeSa" | b"PHUT" | b"AgHg" | b"DCSR" => {}
+                s => return Err(invalid_format!("invalid image resource signature: {:?}", s)),
+            }
+            let id = BigEndian::read_u16(&data[4..6]);
+
+            // name is a Pascal string padded to make its total size even
+            let name_len = data[6] as usize;
+            let name_size = (name_len + 2) & !1;
+            if data.len() < 6 + name_size + 4 {
+                return Err(unexpected_eof!("when reading image resource {:#06x}", id));
+            }
+            let name = data[7..7 + name_len].iter().map(|&b| b as char).collect();
+            data = &data[6 + name_size..];
+
+            // data is also padded to make its size even
+            let size = BigEndian::read_u32(&data[..4]) as usize;
+            data = &data[4..];
+            if data.len() < size {
+                return Err(unexpected_eof!(
+                    "when reading image resource {:#06x} data",
+                    id
+                ));
+            }
+            resources.push(Resource {
+                id,
+                name,
+                data: data[..size].to_vec(),
+            });
+            data = &data[(size + (size & 1)).min(data.len())..];
+        }
+
+        Ok(ImageResources { resources })
+    }
+
+    /// Returns the first resource with the given id.
+    pub fn get(&self, id: u16) -> Option<&Resource> {
+        self.resources.iter().find(|r| r.id == id)
+    }
+
+    /// Decodes IPTC-IIM metadata from the IPTC-NAA resource, if it is present and valid.
+    pub fn iptc(&self) -> Option<Iptc> {
+        self.get(IPTC_NAA).and_then(|r| Iptc::load(&r.data).ok())
+    }
+
+    /// Decodes the resolution information resource, if it is present and valid.
+    pub fn resolution_info(&self) -> Option<ResolutionInfo> {
+        let data = &self.get(RESOLUTION_INFO)?.data;
+        if data.len() < 16 {
+            return None;
+        }
+        // resolutions are 16.16 fixed point numbers, each followed by the unit
+        // of the resolution and the unit of the corresponding dimension
+        let fixed = |d: &[u8]| BigEndian::read_u32(d) as f64 / 65536.0;
+        Some(ResolutionInfo {
+            horizontal: fixed(&data[0..4]),
+            horizontal_unit: ResolutionUnit::from_u16(BigEndian::read_u16(&data[4..6])),
+            vertical: fixed(&data[8..12]),
+            vertical_unit: ResolutionUnit::from_u16(BigEndian::read_u16(&data[12..14])),
+        })
+    }
+
+    /// Decodes the thumbnail resource, if it is present and valid.
+    pub fn thumbnail(&self) -> Option<Thumbnail> {
+        let data = &self
+            .get(THUMBNAIL)
+            .or_else(|| self.get(THUMBNAIL_PS4))?
+            .data;
+        if data.len() < 28 {
+            return None;
+        }
+        // the header also contains row size, total size and compressed size, which
+        // are not needed
+        Some(Thumbnail {
+            format: BigEndian::read_u32(&data[0..4]),
+            dimensions: (
+                BigEndian::read_u32(&data[4..8]),
+                BigEndian::read_u32(&data[8..12]),
+            )
+                .into(),
+            bits_per_pixel: BigEndian::read_u16(&data[24..26]),
+            data: data[28..].to_vec(),
+        })
+    }
+}
@@ -43,7 +43,7 @@ impl<R: Read> RiffReader<R> {
         RiffReader { source }
     }
 
-    pub fn root(&mut self) -> Result<RiffListChunk> {
+    pub fn root(&mut self) -> Result<RiffListChunk<'_>> {
         let (id, len) = match read_id_and_len(&mut self.source)? {
             Some(t) => t,
             None => return Err(unexpected_eof!()),
@@ -73,11 +73,10 @@ struct Counter<'a, R> {
 
 impl<'a, R: Read> Read for Counter<'a, R> {
     fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
-        self.delegate.read(buf).map(|n| {
+        self.delegate.read(buf).inspect(|&n| {
             if let Some(ref mut counter) = self.counter {
                 **counter += n as u32;
             }
-            n
         })
     }
 }
@@ -100,6 +99,11 @@ impl<'a> RiffChunk<'a> {
         self.len
     }
 
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
     #[inline]
     pub fn contents(&mut self) -> &mut dyn Read {
         self.tainted = true;
@@ -161,20 +165,31 @@ impl<'a> RiffListChunk<'a> {
         self.len
     }
 
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
     #[inline]
     pub fn chunk_type(&self) -> ChunkId {
         self.chunk_type
     }
 
     #[inline]
-    pub fn next(&mut self) -> Option<Result<RiffChunk>> {
-        if self.cur_chunk_read < self.cur_chunk_len {
-            let to_skip = (self.cur_chunk_len - self.cur_chunk_read) as u64;
-            match self.data.skip_exact_0(to_skip) {
-                Ok(n) if n == to_skip => {}
+    #[allow(clippy::should_implement_trait)]
+    pub fn next(&mut self) -> Option<Result<RiffChunk<'_>>> {
+        // chunks of odd length are followed by a padding byte, which is sometimes
+        // omitted at the very end of the stream, so it is skipped opportunistically
+        let to_skip = (self.cur_chunk_len - self.cur_chunk_read) as u64;
+        let padding = (self.cur_chunk_len % 2) as u64;
+        if to_skip + padding > 0 {
+            match self.data.skip_exact_0(to_skip + padding) {
+                Ok(n) if n >= to_skip => {}
                 Ok(_) => return Some(Err(unexpected_eof!())),
                 Err(e) => return Some(Err(e.into())),
             }
+            self.cur_chunk_len = 0;
+            self.cur_chunk_read = 0;
         }
 
         let (id, len) = match read_id_and_len(&mut self.data) {
@@ -225,7 +240,7 @@ mod tests {
     macro_rules! build {
         ($($arg:expr),+) => {{
             let mut data = Vec::new();
-            $(data.write($arg).unwrap();)+
+            $(data.write_all($arg).unwrap();)+
             data
         }}
     }
@@ -283,14 +298,14 @@ mod tests {
     #[test]
     fn test_nested_chunks() {
         let data = build! {
-            b"RIFF", &n(77), b"abcd",
-            b"A   ", &n(1), b"z",
-            b"LIST", &n(56), b"wxyz",
-                b" B  ", &n(3), b"123",
+            b"RIFF", &n(80), b"abcd",
+            b"A   ", &n(1), b"z\0",
+            b"LIST", &n(58), b"wxyz",
+                b" B  ", &n(3), b"123\0",
                 b"LIST", &n(22), b"hi  ",
                     b"  C ", &n(0),
                     b"   D", &n(2), b"op",
-                b"E   ", &n(3), b"fuz"
+                b"E   ", &n(3), b"fuz\0"
         };
         let mut data: &[u8] = &data;
 
@@ -299,7 +314,7 @@ mod tests {
         let mut root = r.root().unwrap();
 
         assert_eq!(root.chunk_id(), ChunkId(*b"RIFF"));
-        assert_eq!(root.len(), 77);
+        assert_eq!(root.len(), 80);
         assert_eq!(root.chunk_type(), ChunkId(*b"abcd"));
 
         check_next_chunk(&mut root, ChunkId(*b"A   "), 1, b"z");
@@ -307,7 +322,7 @@ mod tests {
         {
             let chunk = root.next().unwrap().unwrap();
             assert_eq!(chunk.chunk_id(), ChunkId(*b"LIST"));
-            assert_eq!(chunk.len(), 56);
+            assert_eq!(chunk.len(), 58);
             assert!(chunk.can_have_subchunks());
 
             let chunk = chunk.into_list();
@@ -383,4 +398,30 @@ mod tests {
 
         assert!(root.next().is_none());
     }
+
+    #[test]
+    fn test_odd_chunk_padding() {
+        let data = build! {
+            b"RIFF", &n(33), b"abcd",
+            b"A   ", &n(3), b"xyz\0",
+            b" B  ", &n(5), b"12345"
+        };
+        let mut data: &[u8] = &data;
+
+        let mut r = RiffReader::new(&mut data);
+
+        let mut root = r.root().unwrap();
+
+        {
+            let chunk = root.next().unwrap().unwrap();
+            assert_eq!(chunk.chunk_id(), ChunkId(*b"A   "));
+            assert_eq!(chunk.len(), 3);
+        }
+
+        // the padding byte of the last chunk is missing
+        check_next_chunk(&mut root, ChunkId(*b" B  "), 5, b"12345");
+
+        assert!(root.next().is_none());
+        assert!(root.next().is_none());
+    }
 }
@@ -1,4 +1,5 @@
 use std::cell::{Cell, RefCell};
+use std::fmt;
 use std::io::{self, Read, Seek, SeekFrom};
 use std::marker::PhantomData;
 
@@ -8,7 +9,8 @@ use crate::utils::{ByteOrder, ByteOrderReadExt};
 /// A TIFF document reader.
 ///
 /// This structure wraps a `Read` and `Seek` implementation and allows one to read a TIFF
-/// document from it.
+/// document from it. Both classic TIFF and BigTIFF (with 64-bit offsets) documents are
+/// supported.
 pub struct TiffReader<R: Read + Seek> {
     source: R,
 }
@@ -27,8 +29,7 @@ impl<R: Read + Seek> TiffReader<R> {
     /// Note that the returned value does not implement `IntoIterator`, but an immutable
     /// reference to it does. Therefore, it should be used like this:
     ///
-    /// TODO: make it `no_run` instead after tiff module is made public again
-    /// ```ignore
+    /// ```no_run
     /// # use std::io::Cursor;
     /// # use immeta::common::tiff::TiffReader;
     /// # let r = TiffReader::new(Cursor::new(Vec::<u8>::new()));
@@ -54,19 +55,51 @@ impl<R: Read + Seek> TiffReader<R> {
             self.source.read_u16(byte_order),
             "when reading TIFF magic number"
         );
-        if magic != 42 {
-            return Err(invalid_format!("invalid TIFF magic number: {}", magic));
-        }
+        let big = match magic {
+            42 => false,
+            43 => true,
+            _ => return Err(invalid_format!("invalid TIFF magic number: {}", magic)),
+        };
 
-        let next_ifd_offset = try_if_eof!(
-            self.source.read_u32(byte_order),
-            "when reading first TIFF IFD offset"
-        );
+        let next_ifd_offset = if big {
+            // BigTIFF header contains offset size, which is always 8, and a reserved zero
+            let offset_size = try_if_eof!(
+                self.source.read_u16(byte_order),
+                "when reading BigTIFF offset size"
+            );
+            if offset_size != 8 {
+                return Err(invalid_format!(
+                    "invalid BigTIFF offset size: {}",
+                    offset_size
+                ));
+            }
+            let reserved = try_if_eof!(
+                self.source.read_u16(byte_order),
+                "when reading BigTIFF header"
+            );
+            if reserved != 0 {
+                return Err(invalid_format!(
+                    "invalid BigTIFF header reserved value: {}",
+                    reserved
+                ));
+            }
+
+            try_if_eof!(
+                self.source.read_u64(byte_order),
+                "when reading first BigTIFF IFD offset"
+            )
+        } else {
+            try_if_eof!(
+                self.source.read_u32(byte_order),
+                "when reading first TIFF IFD offset"
+            ) as u64
+        };
 
         Ok(LazyIfds {
             source: RefCell::new(self.source),
             byte_order,
-            next_ifd_offset: Cell::new(next_ifd_offset as u64),
+            big,
+            next_ifd_offset: Cell::new(next_ifd_offset),
         })
     }
 }
@@ -76,6 +109,7 @@ impl<R: Read + Seek> TiffReader<R> {
 pub struct LazyIfds<R: Read + Seek> {
     source: RefCell<R>,
     byte_order: ByteOrder,
+    big: bool,
     next_ifd_offset: Cell<u64>,
 }
 
@@ -111,50 +145,130 @@ impl<'a, R: Read + Seek> Ifds<'a, R> {
             return Ok(None);
         }
 
-        // seek to the beginning of the next IFD
+        let (ifd, next_ifd_offset) = self.0.read_ifd_at(next_ifd_offset)?;
+
+        // update the next IFD offset for further calls to `next()`
+        self.0.next_ifd_offset.set(next_ifd_offset);
+
+        Ok(Some(ifd))
+    }
+}
+
+impl<R: Read + Seek> LazyIfds<R> {
+    /// Returns the byte order of the TIFF document.
+    #[inline]
+    pub fn byte_order(&self) -> ByteOrder {
+        self.byte_order
+    }
+
+    /// Returns `true` if the document is a BigTIFF document, `false` otherwise.
+    #[inline]
+    pub fn is_big_tiff(&self) -> bool {
+        self.big
+    }
+
+    // size of offsets and of the entry value field, in bytes
+    #[inline]
+    fn offset_size(&self) -> u64 {
+        if self.big {
+            8
+        } else {
+            4
+        }
+    }
+
+    // size of a single IFD entry, in bytes
+    #[inline]
+    fn entry_size(&self) -> u64 {
+        if self.big {
+            20
+        } else {
+            12
+        }
+    }
+
+    // size of the IFD entries count, in bytes
+    #[inline]
+    fn entries_count_size(&self) -> u64 {
+        if self.big {
+            8
+        } else {
+            2
+        }
+    }
+
+    fn read_offset(&self, source: &mut R) -> io::Result<u64> {
+        if self.big {
+            source.read_u64(self.byte_order)
+        } else {
+            source.read_u32(self.byte_order).map(|n| n as u64)
+        }
+    }
+
+    /// Reads an IFD located at the specified offset.
+    ///
+    /// This method is needed to access IFDs which are not a part of the main IFD chain, for
+    /// example, EXIF sub-IFDs referenced from entries of other IFDs. It does not affect
+    /// the iteration over the main IFD chain.
+    pub fn ifd_at(&self, offset: u64) -> Result<Ifd<'_, R>> {
+        self.read_ifd_at(offset).map(|(ifd, _)| ifd)
+    }
+
+    fn read_ifd_at(&self, current_ifd_offset: u64) -> Result<(Ifd<'_, R>, u64)> {
+        // seek to the beginning of the IFD
         try_if_eof!(
             std,
-            self.0
-                .source
+            self.source
                 .borrow_mut()
-                .seek(SeekFrom::Start(next_ifd_offset)),
+                .seek(SeekFrom::Start(current_ifd_offset)),
             "when seeking to the beginning of the next IFD"
         );
-        let current_ifd_offset = next_ifd_offset;
 
         // read the length of this IFD
-        let current_ifd_size = try_if_eof!(
-            self.0.source.borrow_mut().read_u16(self.0.byte_order),
-            "when reading number of entries in an IFD"
-        );
+        let current_ifd_size = if self.big {
+            try_if_eof!(
+                self.source.borrow_mut().read_u64(self.byte_order),
+                "when reading number of entries in an IFD"
+            )
+        } else {
+            try_if_eof!(
+                self.source.borrow_mut().read_u16(self.byte_order),
+                "when reading number of entries in an IFD"
+            ) as u64
+        };
         // it is an error for an IFD to be empty
         if current_ifd_size == 0 {
             return Err(invalid_format!("number of entries in an IFD is zero"));
         }
 
         // compute the offset of the next IFD offset and seek to it
-        let next_ifd_offset_offset = current_ifd_offset + 2 + current_ifd_size as u64 * 12;
+        let next_ifd_offset_offset = current_ifd_size
+            .checked_mul(self.entry_size())
+            .and_then(|n| n.checked_add(current_ifd_offset + self.entries_count_size()))
+            .ok_or_else(|| invalid_format!("number of entries in an IFD is too large"))?;
         try_if_eof!(
             std,
-            self.0
-                .source
+            self.source
                 .borrow_mut()
                 .seek(SeekFrom::Start(next_ifd_offset_offset)),
             "when seeking to the next IFD offset"
         );
 
-        // read and update the next IFD offset for further calls to `next()`
-        self.0.next_ifd_offset.set(try_if_eof!(
-            self.0.source.borrow_mut().read_u32(self.0.byte_order),
+        // read the next IFD offset
+        let next_ifd_offset = try_if_eof!(
+            self.read_offset(&mut self.source.borrow_mut()),
             "when reading the next IFD offset"
-        ) as u64);
-
-        Ok(Some(Ifd {
-            ifds: self.0,
-            ifd_offset: current_ifd_offset,
-            current_entry: 0,
-            total_entries: current_ifd_size,
-        }))
+        );
+
+        Ok((
+            Ifd {
+                ifds: self,
+                ifd_offset: current_ifd_offset,
+                current_entry: 0,
+                total_entries: current_ifd_size,
+            },
+            next_ifd_offset,
+        ))
     }
 }
 
@@ -164,8 +278,8 @@ impl<'a, R: Read + Seek> Ifds<'a, R> {
 pub struct Ifd<'a, R: Read + Seek + 'a> {
     ifds: &'a LazyIfds<R>,
     ifd_offset: u64,
-    current_entry: u16,
-    total_entries: u16,
+    current_entry: u64,
+    total_entries: u64,
 }
 
 impl<'a, R: Read + Seek + 'a> Iterator for Ifd<'a, R> {
@@ -182,16 +296,30 @@ impl<'a, R: Read + Seek + 'a> Iterator for Ifd<'a, R> {
 
 impl<'a, R: Read + Seek + 'a> Ifd<'a, R> {
     #[inline]
-    pub fn len(&self) -> u16 {
+    pub fn len(&self) -> u64 {
         self.total_entries
     }
 
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.total_entries == 0
+    }
+
+    /// Returns the offset of this IFD from the beginning of the TIFF document.
+    #[inline]
+    pub fn offset(&self) -> u64 {
+        self.ifd_offset
+    }
+
     fn read_entry(&mut self) -> Result<Entry<'a, R>> {
         let mut source = self.ifds.source.borrow_mut();
 
-        // seek to the beginning of the next entry (ifd offset + 2 + next_entry * 12)
+        // seek to the beginning of the next entry (ifd offset + 2 + next_entry * 12 for
+        // classic TIFF, ifd offset + 8 + next_entry * 20 for BigTIFF)
         source.seek(SeekFrom::Start(
-            self.ifd_offset + 2 + self.current_entry as u64 * 12,
+            self.ifd_offset
+                + self.ifds.entries_count_size()
+                + self.current_entry * self.ifds.entry_size(),
         ))?;
 
         // read the tag
@@ -208,23 +336,16 @@ impl<'a, R: Read + Seek + 'a> Ifd<'a, R> {
 
         // read the count
         let count = try_if_eof!(
-            source.read_u32(self.ifds.byte_order),
+            self.ifds.read_offset(&mut source),
             "when reading TIFF IFD entry data count"
         );
 
         // read the offset/value
         let offset = try_if_eof!(
-            source.read_u32(self.ifds.byte_order),
+            self.ifds.read_offset(&mut source),
             "when reading TIFF IFD entry data offset"
         );
 
-        println!("---------------------------------");
-        println!("Entry tag:               {tag:04X}, {tag}");
-        println!("Entry type:              {entry_type:04X}, {entry_type}");
-        println!("Entry items count:       {count:08X}, {count}");
-        println!("Entry data offset/value: {offset:08X}, {offset}");
-        println!("---------------------------------");
-
         self.current_entry += 1;
 
         Ok(Entry {
@@ -252,6 +373,10 @@ pub enum EntryType {
     SignedRational,
     Float,
     Double,
+    Ifd,
+    Long8,
+    SignedLong8,
+    Ifd8,
     Unknown(u16),
 }
 
@@ -270,6 +395,10 @@ impl From<u16> for EntryType {
             10 => EntryType::SignedRational,
             11 => EntryType::Float,
             12 => EntryType::Double,
+            13 => EntryType::Ifd,
+            16 => EntryType::Long8,
+            17 => EntryType::SignedLong8,
+            18 => EntryType::Ifd8,
             n => EntryType::Unknown(n),
         }
     }
@@ -290,6 +419,10 @@ impl EntryType {
             EntryType::SignedRational => Some(8),
             EntryType::Float => Some(4),
             EntryType::Double => Some(8),
+            EntryType::Ifd => Some(4),
+            EntryType::Long8 => Some(8),
+            EntryType::SignedLong8 => Some(8),
+            EntryType::Ifd8 => Some(8),
             EntryType::Unknown(_) => None,
         }
     }
@@ -300,8 +433,8 @@ pub struct Entry<'a, R: Read + Seek + 'a> {
     ifds: &'a LazyIfds<R>,
     tag: u16,
     entry_type: EntryType,
-    count: u32,
-    offset: u32,
+    count: u64,
+    offset: u64,
 }
 
 impl<'a, R: Read + Seek + 'a> Entry<'a, R> {
@@ -319,10 +452,59 @@ impl<'a, R: Read + Seek + 'a> Entry<'a, R> {
 
     /// Returns the number of items this entry contains.
     #[inline]
-    pub fn count(&self) -> u32 {
+    pub fn count(&self) -> u64 {
         self.count
     }
 
+    // checks whether the entry data fits into the offset field
+    fn is_embedded(&self, entry_type_size: u8) -> bool {
+        (entry_type_size as u64)
+            .checked_mul(self.count)
+            .is_some_and(|n| n <= self.ifds.offset_size())
+    }
+
+    // seeks to the entry data stored outside of the entry, checking that the data fits
+    // into the rest of the stream
+    fn seek_to_data(&self, entry_type_size: u8) -> Result<()> {
+        let size = (entry_type_size as u64)
+            .checked_mul(self.count)
+            .ok_or_else(|| invalid_format!("TIFF IFD entry count is too large: {}", self.count))?;
+
+        let mut source = self.ifds.source.borrow_mut();
+        let len = try_if_eof!(
+            std,
+            source.seek(SeekFrom::End(0)),
+            "when seeking to the end of TIFF data"
+        );
+        if self.offset.checked_add(size).is_none_or(|end| end > len) {
+            return Err(unexpected_eof!(
+                "when reading TIFF IFD entry values: {} bytes at offset {}",
+                size,
+                self.offset
+            ));
+        }
+
+        try_if_eof!(
+            std,
+            source.seek(SeekFrom::Start(self.offset)),
+            "when seeking to the beginning of IFD entry data"
+        );
+        Ok(())
+    }
+
+    // returns the offset field as raw bytes, this is where embedded data is stored
+    fn embedded_data(&self) -> [u8; 8] {
+        let mut data = [0u8; 8];
+        if self.ifds.big {
+            self.ifds.byte_order.write_u64(&mut data, self.offset);
+        } else {
+            self.ifds
+                .byte_order
+                .write_u32(&mut data[..4], self.offset as u32);
+        }
+        data
+    }
+
     /// Returns an iterator for elements of the specified representation type.
     ///
     /// This method returns `None` if the requested representation type does not correspond
@@ -334,15 +516,14 @@ impl<'a, R: Read + Seek + 'a> Entry<'a, R> {
         if self.entry_type == T::entry_type() {
             // then try to get the size and ignore the data in the entry if it is unknown
             if let Some(entry_type_size) = T::entry_type().size() {
-                // if the total entry data size is smaller than 4 bytes (u32 value length)
-                // the the data is embedded into the offset u32
-                if entry_type_size as u32 * self.count <= 4 {
-                    let mut data = [0u8; 4];
-                    self.ifds.byte_order.write_u32(&mut data, self.offset);
+                // if the total entry data size is smaller than 4 bytes (u32 value length,
+                // or 8 bytes for BigTIFF) the the data is embedded into the offset field
+                if self.is_embedded(entry_type_size) {
                     Some(EntryValues::Embedded(EmbeddedValues {
                         current: 0,
                         count: self.count,
-                        data,
+                        data: self.embedded_data(),
+                        len: self.ifds.offset_size() as usize,
                         byte_order: self.ifds.byte_order,
                         _entry_type_repr: PhantomData,
                     }))
@@ -376,22 +557,14 @@ impl<'a, R: Read + Seek + 'a> Entry<'a, R> {
         if self.entry_type == T::entry_type() {
             // then try to get the size and ignore the data in the entry if it is unknown
             if let Some(entry_type_size) = T::entry_type().size() {
-                // if the total entry data size is smaller than 4 bytes (u32 value length)
-                // the the data is embedded into the offset u32, and we just delegate to the
-                // iterator
-                if entry_type_size as u32 * self.count <= 4 {
+                // if the total entry data size is smaller than 4 bytes (u32 value length,
+                // or 8 bytes for BigTIFF) the the data is embedded into the offset field,
+                // and we just delegate to the iterator
+                if self.is_embedded(entry_type_size) {
                     Some(self.values::<T>().unwrap().collect())
                 // othewise the data is stored at that offset, load it all at once
                 } else {
-                    match self
-                        .ifds
-                        .source
-                        .borrow_mut()
-                        .seek(SeekFrom::Start(self.offset as u64))
-                        .map_err(if_eof!(
-                            std,
-                            "when seeking to the beginning of IFD entry data"
-                        )) {
+                    match self.seek_to_data(entry_type_size) {
                         Ok(_) => {}
                         Err(e) => return Some(Err(e)),
                     }
@@ -416,6 +589,210 @@ impl<'a, R: Read + Seek + 'a> Entry<'a, R> {
             None
         }
     }
+
+    /// Loads all of the items of this entry into an owned value of the corresponding type.
+    ///
+    /// This method returns `None` if the entry type is unknown.
+    pub fn value(&self) -> Option<Result<Value>> {
+        use self::entry_types as et;
+
+        macro_rules! load {
+            ($($tpe:ident),+) => {
+                match self.entry_type {
+                    $(EntryType::$tpe => self.all_values::<et::$tpe>().map(|r| r.map(Value::$tpe)),)+
+                    EntryType::Unknown(_) => None,
+                }
+            }
+        }
+
+        load!(
+            Byte,
+            Ascii,
+            Short,
+            Long,
+            Rational,
+            SignedByte,
+            Undefined,
+            SignedShort,
+            SignedLong,
+            SignedRational,
+            Float,
+            Double,
+            Ifd,
+            Long8,
+            SignedLong8,
+            Ifd8
+        )
+    }
+}
+
+/// An owned representation of all items of a TIFF IFD entry.
+///
+/// Values of this type are obtained with `Entry::value()` method. Each variant corresponds
+/// to one of the known entry types.
+#[derive(Clone, Debug)]
+pub enum Value {
+    Byte(Vec<u8>),
+    Ascii(Vec<String>),
+    Short(Vec<u16>),
+    Long(Vec<u32>),
+    Rational(Vec<(u32, u32)>),
+    SignedByte(Vec<i8>),
+    Undefined(Vec<u8>),
+    SignedShort(Vec<i16>),
+    SignedLong(Vec<i32>),
+    SignedRational(Vec<(i32, i32)>),
+    Float(Vec<f32>),
+    Double(Vec<f64>),
+    Ifd(Vec<u32>),
+    Long8(Vec<u64>),
+    SignedLong8(Vec<i64>),
+    Ifd8(Vec<u64>),
+}
+
+// floating point values are compared bitwise, so the equality is reflexive
+impl PartialEq for Value {
+    fn eq(&self, other: &Value) -> bool {
+        match (self, other) {
+            (Value::Byte(a), Value::Byte(b)) => a == b,
+            (Value::Ascii(a), Value::Ascii(b)) => a == b,
+            (Value::Short(a), Value::Short(b)) => a == b,
+            (Value::Long(a), Value::Long(b)) => a == b,
+            (Value::Rational(a), Value::Rational(b)) => a == b,
+            (Value::SignedByte(a), Value::SignedByte(b)) => a == b,
+            (Value::Undefined(a), Value::Undefined(b)) => a == b,
+            (Value::SignedShort(a), Value::SignedShort(b)) => a == b,
+            (Value::SignedLong(a), Value::SignedLong(b)) => a == b,
+            (Value::SignedRational(a), Value::SignedRational(b)) => a == b,
+            (Value::Float(a), Value::Float(b)) => {
+                a.len() == b.len() && a.iter().zip(b).all(|(x, y)| x.to_bits() == y.to_bits())
+            }
+            (Value::Double(a), Value::Double(b)) => {
+                a.len() == b.len() && a.iter().zip(b).all(|(x, y)| x.to_bits() == y.to_bits())
+            }
+            (Value::Ifd(a), Value::Ifd(b)) => a == b,
+            (Value::Long8(a), Value::Long8(b)) => a == b,
+            (Value::SignedLong8(a), Value::SignedLong8(b)) => a == b,
+            (Value::Ifd8(a), Value::Ifd8(b)) => a == b,
+            _ => false,
+        }
+    }
+}
+
+impl Eq for Value {}
+
+// writes items separated with commas
+fn write_items<T, F>(f: &mut fmt::Formatter, items: &[T], mut write_item: F) -> fmt::Result
+where
+    F: FnMut(&mut fmt::Formatter, &T) -> fmt::Result,
+{
+    for (i, item) in items.iter().enumerate() {
+        if i > 0 {
+            f.write_str(", ")?;
+        }
+        write_item(f, item)?;
+    }
+    Ok(())
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Value::Byte(ref v) => write_items(f, v, |f, n| write!(f, "{}", n)),
+            Value::Ascii(ref v) => write_items(f, v, |f, s| f.write_str(s)),
+            Value::Short(ref v) => write_items(f, v, |f, n| write!(f, "{}", n)),
+            Value::Long(ref v) | Value::Ifd(ref v) => write_items(f, v, |f, n| write!(f, "{}", n)),
+            Value::Rational(ref v) => write_items(f, v, |f, &(n, d)| write!(f, "{}/{}", n, d)),
+            Value::SignedByte(ref v) => write_items(f, v, |f, n| write!(f, "{}", n)),
+            Value::Undefined(ref v) => write!(f, "({} bytes)", v.len()),
+            Value::SignedShort(ref v) => write_items(f, v, |f, n| write!(f, "{}", n)),
+            Value::SignedLong(ref v) => write_items(f, v, |f, n| write!(f, "{}", n)),
+            Value::SignedRational(ref v) => {
+                write_items(f, v, |f, &(n, d)| write!(f, "{}/{}", n, d))
+            }
+            Value::Float(ref v) => write_items(f, v, |f, n| write!(f, "{}", n)),
+            Value::Double(ref v) => write_items(f, v, |f, n| write!(f, "{}", n)),
+            Value::Long8(ref v) | Value::Ifd8(ref v) => {
+                write_items(f, v, |f, n| write!(f, "{}", n))
+            }
+            Value::SignedLong8(ref v) => write_items(f, v, |f, n| write!(f, "{}", n)),
+        }
+    }
+}
+
+impl Value {
+    /// Returns the type of this value.
+    pub fn entry_type(&self) -> EntryType {
+        match *self {
+            Value::Byte(_) => EntryType::Byte,
+            Value::Ascii(_) => EntryType::Ascii,
+            Value::Short(_) => EntryType::Short,
+            Value::Long(_) => EntryType::Long,
+            Value::Rational(_) => EntryType::Rational,
+            Value::SignedByte(_) => EntryType::SignedByte,
+            Value::Undefined(_) => EntryType::Undefined,
+            Value::SignedShort(_) => EntryType::SignedShort,
+            Value::SignedLong(_) => EntryType::SignedLong,
+            Value::SignedRational(_) => EntryType::SignedRational,
+            Value::Float(_) => EntryType::Float,
+            Value::Double(_) => EntryType::Double,
+            Value::Ifd(_) => EntryType::Ifd,
+            Value::Long8(_) => EntryType::Long8,
+            Value::SignedLong8(_) => EntryType::SignedLong8,
+            Value::Ifd8(_) => EntryType::Ifd8,
+        }
+    }
+
+    /// Returns the first item of this value as an unsigned integer, if this value has
+    /// an unsigned integer or an offset type.
+    pub fn as_u64(&self) -> Option<u64> {
+        match *self {
+            Value::Byte(ref v) => v.first().map(|&n| n as u64),
+            Value::Short(ref v) => v.first().map(|&n| n as u64),
+            Value::Long(ref v) | Value::Ifd(ref v) => v.first().map(|&n| n as u64),
+            Value::Long8(ref v) | Value::Ifd8(ref v) => v.first().cloned(),
+            _ => None,
+        }
+    }
+
+    /// Returns the first item of this value as an unsigned integer, if this value has
+    /// an unsigned integer or an offset type and the item fits into `u32`.
+    pub fn as_u32(&self) -> Option<u32> {
+        self.as_u64().and_then(|n| u32::try_from(n).ok())
+    }
+
+    /// Returns the first string of this value, if this value has ASCII type.
+    pub fn as_str(&self) -> Option<&str> {
+        match *self {
+            Value::Ascii(ref v) => v.first().map(|s| &**s),
+            _ => None,
+        }
+    }
+
+    /// Returns the first item of this value as a fraction, if this value has rational type.
+    pub fn as_rational(&self) -> Option<(u32, u32)> {
+        match *self {
+            Value::Rational(ref v) => v.first().cloned(),
+            _ => None,
+        }
+    }
+
+    /// Returns the first item of this value as a fraction, if this value has signed
+    /// rational type.
+    pub fn as_signed_rational(&self) -> Option<(i32, i32)> {
+        match *self {
+            Value::SignedRational(ref v) => v.first().cloned(),
+            _ => None,
+        }
+    }
+
+    /// Returns the raw bytes of this value, if this value has byte or undefined type.
+    pub fn as_bytes(&self) -> Option<&[u8]> {
+        match *self {
+            Value::Byte(ref v) | Value::Undefined(ref v) => Some(v),
+            _ => None,
+        }
+    }
 }
 
 /// Designates a marker type which represent one of TIFF directory entry types.
@@ -439,16 +816,17 @@ pub trait EntryTypeRepr {
     fn read_many_from<R: Read>(
         source: &mut R,
         byte_order: ByteOrder,
-        n: u32,
+        n: u64,
         target: &mut Vec<Self::Repr>,
     ) -> io::Result<()>;
 
-    /// Reads the `n`th represented value inside `source`.
+    /// Reads the `n`th represented value inside `source`, which is the contents of an entry
+    /// offset field (4 bytes for classic TIFF, 8 bytes for BigTIFF).
     ///
-    /// If the value can be read successfully (`n` < `count`, the represented type is smaller
-    /// than or equal to u32, etc.), returns `Some(value)`, otherwise returns `None`.
-    fn read_from_u32(
-        source: [u8; 4],
+    /// If the value can be read successfully (`n` < `count`, the value fits into `source`,
+    /// etc.), returns `Some(value)`, otherwise returns `None`.
+    fn read_from_embedded(
+        source: &[u8],
         byte_order: ByteOrder,
         n: usize,
         count: usize,
@@ -458,7 +836,6 @@ pub trait EntryTypeRepr {
 /// Contains representation types for all of defined TIFF entry types.
 pub mod entry_types {
     use std::io::{self, Read};
-    use std::str;
 
     use arrayvec::ArrayVec;
     use byteorder;
@@ -466,12 +843,29 @@ pub mod entry_types {
     use super::{EntryType, EntryTypeRepr};
     use crate::utils::{ByteOrder, ByteOrderReadExt};
 
+    // reads the `n`th fixed-size value embedded into the entry offset field
+    fn read_embedded<T: EntryTypeRepr>(
+        source: &[u8],
+        byte_order: ByteOrder,
+        n: usize,
+        count: usize,
+    ) -> Option<T::Repr> {
+        let size = T::entry_type().size()? as usize;
+        if n >= count || (n + 1) * size > source.len() {
+            None
+        } else {
+            T::read_from(&mut &source[n * size..], byte_order)
+                .ok()
+                .map(|(_, v)| v)
+        }
+    }
+
     macro_rules! gen_entry_types {
         (
             $(
                 $tpe:ident, $repr:ty,
-                |$source:pat, $byte_order:pat_param| $read:expr,
-                |$u32_source:pat, $u32_byte_order:pat, $n:pat, $count:pat_param| $u32_read:expr
+                |$source:pat, $byte_order:pat_param| $read:expr
+                $(, |$emb_source:pat, $emb_byte_order:pat, $n:pat, $count:pat_param| $emb_read:expr)?
             );+
         ) => {
             $(
@@ -490,33 +884,42 @@ pub mod entry_types {
                     }
 
                     fn read_many_from<R: Read>(source: &mut R, byte_order: ByteOrder,
-                                               n: u32, target: &mut Vec<Self::Repr>) -> io::Result<()> {
+                                               n: u64, target: &mut Vec<Self::Repr>) -> io::Result<()> {
                         // This logic is necessary to handle variable-size items (Ascii strings)
                         // We read item by item, increasing the read bytes counter until we read
                         // all expected items (whose size can be calculated)
                         let item_size = EntryType::$tpe.size().expect("reading unknown data type");
-                        let max_bytes = n * item_size as u32;
+                        let max_bytes = n.checked_mul(item_size as u64).ok_or_else(|| {
+                            io::Error::new(io::ErrorKind::InvalidData, "too many TIFF entry values")
+                        })?;
                         let mut bytes_read = 0;
                         while bytes_read < max_bytes {
                             let (c, v) = Self::read_from(source, byte_order)?;
-                            bytes_read += c;
+                            bytes_read += c as u64;
                             target.push(v);
                         }
                         Ok(())
                     }
 
-                    fn read_from_u32($u32_source: [u8; 4], $u32_byte_order: ByteOrder, $n: usize, $count: usize) -> Option<$repr> {
-                        $u32_read
-                    }
+                    gen_entry_types!(@embedded $repr $(, |$emb_source, $emb_byte_order, $n, $count| $emb_read)?);
                 }
             )+
-        }
+        };
+        (@embedded $repr:ty) => {
+            fn read_from_embedded(source: &[u8], byte_order: ByteOrder, n: usize, count: usize) -> Option<$repr> {
+                read_embedded::<Self>(source, byte_order, n, count)
+            }
+        };
+        (@embedded $repr:ty, |$source:pat, $byte_order:pat, $n:pat, $count:pat_param| $read:expr) => {
+            fn read_from_embedded($source: &[u8], $byte_order: ByteOrder, $n: usize, $count: usize) -> Option<$repr> {
+                $read
+            }
+        };
     }
 
     gen_entry_types! {
         Byte, u8,
-            |source, _| byteorder::ReadBytesExt::read_u8(source).map(|v| (1, v)),
-            |source, _, n, count| if n >= count || n >= 4 { None } else { Some(source[n]) };
+            |source, _| byteorder::ReadBytesExt::read_u8(source).map(|v| (1, v));
         Ascii, String,
             |source, _| {
                 let mut s = String::new();
@@ -527,7 +930,7 @@ pub mod entry_types {
                 }
                 Ok((s.len() as u32 + 1, s))
             },
-            |source, _, n, count| if n >= count || n >= 4 { None } else {
+            |source, _, n, count| if n >= count || count > source.len() { None } else {
                 // w x y z
                 // +-----0   4
                 // 0 +---0   4
@@ -549,49 +952,43 @@ pub mod entry_types {
                         i += 1;
                     }
                 }
-                let mut substrings = ArrayVec::<_, 4>::new();
+                let mut substrings = ArrayVec::<_, 8>::new();
                 find_substrings(&bs[..count], &mut substrings);
                 substrings.get(n)
-                    .map(|&(s, e)| unsafe { str::from_utf8_unchecked(&bs[s..e]).to_owned() })
+                    .map(|&(s, e)| bs[s..e].iter().map(|&b| b as char).collect())
             };
         Short, u16,
-            |source, byte_order| source.read_u16(byte_order).map(|v| (2, v)),
-            |source, byte_order, n, count| if n >= count || n >= 2 { None } else {
-                Some(byte_order.read_u16(&source[2*n..]))
-            };
+            |source, byte_order| source.read_u16(byte_order).map(|v| (2, v));
         Long, u32,
-            |source, byte_order| source.read_u32(byte_order).map(|v| (4, v)),
-            |source, byte_order, n, _| if n >= 1 { None } else { Some(byte_order.read_u32(&source)) };
+            |source, byte_order| source.read_u32(byte_order).map(|v| (4, v));
         Rational, (u32, u32),
             |source, byte_order| source.read_u32(byte_order)
                 .and_then(|n| source.read_u32(byte_order).map(|d| (n, d)))
-                .map(|v| (4 * 2, v)),
-            |_, _, _, _| None;
+                .map(|v| (4 * 2, v));
         SignedByte, i8,
-            |source, _| byteorder::ReadBytesExt::read_i8(source).map(|v| (1, v)),
-            |source, _, n, count| if n >= count || n >= 4 { None } else { Some(source[n] as i8) };
+            |source, _| byteorder::ReadBytesExt::read_i8(source).map(|v| (1, v));
         Undefined, u8,
-            |source, _| byteorder::ReadBytesExt::read_u8(source).map(|v| (1, v)),
-            |source, _, n, count| if n >= count || n >= 4 { None } else { Some(source[n]) };
+            |source, _| byteorder::ReadBytesExt::read_u8(source).map(|v| (1, v));
         SignedShort, i16,
-            |source, byte_order| source.read_i16(byte_order).map(|v| (2, v)),
-            |source, byte_order, n, count| if n >= count || n >= 2 { None } else {
-                Some(byte_order.read_i16(&source[2*n..]))
-            };
+            |source, byte_order| source.read_i16(byte_order).map(|v| (2, v));
         SignedLong, i32,
-            |source, byte_order| source.read_i32(byte_order).map(|v| (4, v)),
-            |source, byte_order, n, _| if n >= 1 { None } else { Some(byte_order.read_i32(&source)) };
+            |source, byte_order| source.read_i32(byte_order).map(|v| (4, v));
         SignedRational, (i32, i32),
             |source, byte_order| source.read_i32(byte_order)
                 .and_then(|n| source.read_i32(byte_order).map(|d| (n, d)))
-                .map(|v| (4 * 2, v)),
-            |_, _, _, _| None;
+                .map(|v| (4 * 2, v));
         Float, f32,
-            |source, byte_order| source.read_f32(byte_order).map(|v| (4, v)),
-            |source, byte_order, n, _| if n >= 1 { None } else { Some(byte_order.read_f32(&source)) };
+            |source, byte_order| source.read_f32(byte_order).map(|v| (4, v));
         Double, f64,
-            |source, byte_order| source.read_f64(byte_order).map(|v| (8, v)),
-            |_, _, _, _| None
+            |source, byte_order| source.read_f64(byte_order).map(|v| (8, v));
+        Ifd, u32,
+            |source, byte_order| source.read_u32(byte_order).map(|v| (4, v));
+        Long8, u64,
+            |source, byte_order| source.read_u64(byte_order).map(|v| (8, v));
+        SignedLong8, i64,
+            |source, byte_order| source.read_i64(byte_order).map(|v| (8, v));
+        Ifd8, u64,
+            |source, byte_order| source.read_u64(byte_order).map(|v| (8, v))
     }
 }
 
@@ -625,9 +1022,10 @@ impl<'a, T: EntryTypeRepr, R: Read + Seek + 'a> EntryValues<'a, T, R> {
 
 #[doc(hidden)]
 pub struct EmbeddedValues<T: EntryTypeRepr> {
-    current: u32,
-    count: u32,
-    data: [u8; 4],
+    current: u64,
+    count: u64,
+    data: [u8; 8],
+    len: usize,
     byte_order: ByteOrder,
     _entry_type_repr: PhantomData<T>,
 }
@@ -637,8 +1035,8 @@ impl<T: EntryTypeRepr> EmbeddedValues<T> {
         if self.current >= self.count {
             None
         } else {
-            let result = T::read_from_u32(
-                self.data,
+            let result = T::read_from_embedded(
+                &self.data[..self.len],
                 self.byte_order,
                 self.current as usize,
                 self.count as usize,
@@ -652,29 +1050,33 @@ impl<T: EntryTypeRepr> EmbeddedValues<T> {
 #[doc(hidden)]
 pub struct ReferencedValues<'a, T: EntryTypeRepr, R: Read + Seek + 'a> {
     ifds: &'a LazyIfds<R>,
-    count: u32,
-    bytes_read: u32,
-    next_offset: u32,
+    count: u64,
+    bytes_read: u64,
+    next_offset: u64,
     _entry_type_repr: PhantomData<T>,
 }
 
 impl<'a, T: EntryTypeRepr, R: Read + Seek + 'a> ReferencedValues<'a, T, R> {
     fn read_value(&mut self) -> Result<Option<T::Repr>> {
-        if self.bytes_read >= self.count * T::entry_type().size().unwrap() as u32 {
+        let size = self
+            .count
+            .checked_mul(T::entry_type().size().unwrap() as u64)
+            .ok_or_else(|| invalid_format!("TIFF IFD entry count is too large: {}", self.count))?;
+        if self.bytes_read >= size {
             return Ok(None);
         }
 
         self.ifds
             .source
             .borrow_mut()
-            .seek(SeekFrom::Start(self.next_offset as u64))?;
+            .seek(SeekFrom::Start(self.next_offset))?;
 
         let (bytes_read, value) = try_if_eof!(
             T::read_from(&mut *self.ifds.source.borrow_mut(), self.ifds.byte_order),
             "when reading TIFF entry value"
         );
-        self.next_offset += bytes_read;
-        self.bytes_read += bytes_read;
+        self.next_offset += bytes_read as u64;
+        self.bytes_read += bytes_read as u64;
 
         Ok(Some(value))
     }
@@ -686,7 +1088,7 @@ mod tests {
 
     use byteorder::{self, BigEndian, ByteOrder, LittleEndian};
 
-    use crate::common::tiff::{entry_types, EntryType, TiffReader};
+    use crate::common::tiff::{entry_types, EntryType, TiffReader, Value};
 
     trait Writable {
         fn write_to<W: Write + ?Sized, T: ByteOrder>(&self, target: &mut W);
@@ -847,7 +1249,7 @@ mod tests {
             -333i32, -106i32,
 
             // @252, Double x1, 8 bytes
-            3.14f64
+            2.75f64
         };
 
         let reader = TiffReader::new(Cursor::new(data));
@@ -1028,9 +1430,9 @@ mod tests {
                         assert_eq!(e.count(), 1);
                         assert_eq!(
                             e.all_values::<entry_types::Double>().unwrap().unwrap(),
-                            vec![3.14]
+                            vec![2.75]
                         );
-                        assert_items!(e.values::<entry_types::Double>().unwrap(), 3.14);
+                        assert_items!(e.values::<entry_types::Double>().unwrap(), 2.75);
                     }
                     12 => {
                         assert_eq!(e.tag(), 4);
@@ -1045,6 +1447,108 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_big_tiff() {
+        let data = build! { LittleEndian,
+            b"II", 43u16, 8u16, 0u16, 16u64,  // 1st IFD starts from 16th offset
+
+            // first IFD has 4 entries
+            4u64,
+
+            // first entry, Short
+            256u16, 3u16, 1u64, 640u16, 0u16, 0u32,
+
+            // second entry, Ascii, embedded into 8 bytes
+            2u16, 2u16, 8u64, b"abc\x00def\x00",
+
+            // third entry, Long8
+            273u16, 16u16, 1u64, 1234567890123u64,
+
+            // fourth entry, Long
+            279u16, 4u16, 3u64, 112u64,
+
+            // next IFD offset, zero means no more IFDs
+            0u64,
+
+            // @112, Long x3, 12 bytes
+            12u32, 34u32, 56u32
+        };
+
+        let reader = TiffReader::new(Cursor::new(data));
+        let ifds = reader.ifds().unwrap();
+        assert!(ifds.is_big_tiff());
+
+        let mut ifds_iter = (&ifds).into_iter();
+        let ifd = ifds_iter.next().unwrap().unwrap();
+        assert!(ifds_iter.next().is_none());
+
+        assert_eq!(ifd.len(), 4);
+        let values: Vec<_> = ifd
+            .map(|e| {
+                let e = e.unwrap();
+                (e.tag(), e.value().unwrap().unwrap())
+            })
+            .collect();
+        assert_eq!(
+            values,
+            vec![
+                (256, Value::Short(vec![640])),
+                (2, Value::Ascii(vec!["abc".to_owned(), "def".to_owned()])),
+                (273, Value::Long8(vec![1234567890123])),
+                (279, Value::Long(vec![12, 34, 56])),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_big_tiff_invalid_offset_size() {
+        let data = build! { BigEndian,
+            b"MM", 43u16, 4u16, 0u16, 0u64
+        };
+
+        let reader = TiffReader::new(Cursor::new(data));
+        assert!(reader.ifds().is_err());
+    }
+
+    #[test]
+    fn test_big_tiff_invalid_count() {
+        // the byte size of the Long entry overflows u64
+        let data = build! { LittleEndian,
+            b"II", 43u16, 8u16, 0u16, 16u64,
+            1u64,
+            279u16, 4u16, (1u64 << 62) + 1, 64u64,
+            0u64,
+            0u64
+        };
+
+        let reader = TiffReader::new(Cursor::new(data));
+        let ifds = reader.ifds().unwrap();
+        let mut ifd = (&ifds).into_iter().next().unwrap().unwrap();
+        let e = ifd.next().unwrap().unwrap();
+        assert!(e.value().unwrap().is_err());
+        assert!(e
+            .values::<entry_types::Long>()
+            .unwrap()
+            .next()
+            .unwrap()
+            .is_err());
+
+        // the entry data does not fit into the stream
+        let data = build! { LittleEndian,
+            b"II", 43u16, 8u16, 0u16, 16u64,
+            1u64,
+            279u16, 4u16, 1u64 << 40, 64u64,
+            0u64,
+            0u64
+        };
+
+        let reader = TiffReader::new(Cursor::new(data));
+        let ifds = reader.ifds().unwrap();
+        let mut ifd = (&ifds).into_iter().next().unwrap().unwrap();
+        let e = ifd.next().unwrap().unwrap();
+        assert!(e.value().unwrap().is_err());
+    }
+
     //#[test]
     //fn test_two_ifds() {
     //let data = build! { LittleEndian,
@@ -0,0 +1,502 @@
+//! XMP (Extensible Metadata Platform) packets.
+//!
+//! XMP packets are stored as serialized RDF/XML documents in various container formats.
+//! This module provides a lightweight reader which turns such a packet into a map of
+//! properties without a full RDF implementation. It understands simple properties written
+//! both as attributes and as elements, arrays (`rdf:Bag`, `rdf:Seq`), language
+//! alternatives (`rdf:Alt`) and structures.
+//!
+//! Properties are identified by their namespace URI and local name, so they are found
+//! regardless of the prefixes used in a particular packet:
+//!
+//! ```no_run
+//! use immeta::common::xmp::{ns, Xmp};
+//!
+//! let md = immeta::load_from_file("kitty.jpg").unwrap();
+//! let xmp = Xmp::parse(md.xmp().unwrap()).unwrap();
+//! println!("Title: {:?}", xmp.title());
+//! println!("Location: {:?}", xmp.get(ns::IPTC_CORE, "Location"));
+//! ```
+
+use std::collections::BTreeMap;
+use std::str::FromStr;
+
+use self::xml::{Element, XML_NAMESPACE};
+use crate::types::{Orientation, Result};
+
+mod xml;
+
+/// Namespace URIs of commonly used XMP schemas.
+pub mod ns {
+    /// RDF syntax namespace.
+    pub const RDF: &str = "http://www.w3.org/1999/02/22-rdf-syntax-ns#";
+    /// Dublin Core schema (`dc`).
+    pub const DC: &str = "http://purl.org/dc/elements/1.1/";
+    /// XMP basic schema (`xmp`).
+    pub const XMP: &str = "http://ns.adobe.com/xap/1.0/";
+    /// XMP rights management schema (`xmpRights`).
+    pub const XMP_RIGHTS: &str = "http://ns.adobe.com/xap/1.0/rights/";
+    /// XMP media management schema (`xmpMM`).
+    pub const XMP_MM: &str = "http://ns.adobe.com/xap/1.0/mm/";
+    /// XMP note schema (`xmpNote`), used for extended XMP in JPEG images.
+    pub const XMP_NOTE: &str = "http://ns.adobe.com/xmp/note/";
+    /// Photoshop schema (`photoshop`).
+    pub const PHOTOSHOP: &str = "http://ns.adobe.com/photoshop/1.0/";
+    /// IPTC Core schema (`Iptc4xmpCore`).
+    pub const IPTC_CORE: &str = "http://iptc.org/std/Iptc4xmpCore/1.0/xmlns/";
+    /// IPTC Extension schema (`Iptc4xmpExt`).
+    pub const IPTC_EXT: &str = "http://iptc.org/std/Iptc4xmpExt/2008-02-29/";
+    /// TIFF properties mirrored from IFD0 (`tiff`).
+    pub const TIFF: &str = "http://ns.adobe.com/tiff/1.0/";
+    /// EXIF properties mirrored from the EXIF sub-IFD (`exif`).
+    pub const EXIF: &str = "http://ns.adobe.com/exif/1.0/";
+    /// EXIF 2.21 and later properties (`exifEX`).
+    pub const EXIF_EX: &str = "http://cipa.jp/exif/1.0/";
+}
+
+/// Default language of language alternatives.
+pub const DEFAULT_LANGUAGE: &str = "x-default";
+
+/// A property name qualified with its namespace.
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub struct Name {
+    /// Namespace URI.
+    pub namespace: String,
+    /// Local name of the property.
+    pub name: String,
+}
+
+impl Name {
+    /// Creates a new property name.
+    pub fn new(namespace: &str, name: &str) -> Name {
+        Name {
+            namespace: namespace.to_owned(),
+            name: name.to_owned(),
+        }
+    }
+}
+
+/// A map of properties, e.g. top-level properties of a packet or fields of a structure.
+pub type Properties = BTreeMap<Name, Value>;
+
+/// An item of a language alternative array.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Alternative {
+    /// Language of the item from its `xml:lang` qualifier, if it is present.
+    pub language: Option<String>,
+    /// Value of the item.
+    pub value: Value,
+}
+
+/// A value of an XMP property.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum Value {
+    /// A simple text value; numbers, dates and URIs are also stored as text.
+    Text(String),
+    /// An unordered array (`rdf:Bag`).
+    Bag(Vec<Value>),
+    /// An ordered array (`rdf:Seq`).
+    Seq(Vec<Value>),
+    /// An alternative array (`rdf:Alt`), usually containing the same text in different
+    /// languages.
+    Alt(Vec<Alternative>),
+    /// A structure with named fields.
+    Struct(Properties),
+}
+
+impl Value {
+    /// Returns the text of a simple value.
+    pub fn as_text(&self) -> Option<&str> {
+        match *self {
+            Value::Text(ref s) => Some(s),
+            _ => None,
+        }
+    }
+
+    /// Returns items of an ordered or unordered array.
+    pub fn items(&self) -> Option<&[Value]> {
+        match *self {
+            Value::Bag(ref v) | Value::Seq(ref v) => Some(v),
+            _ => None,
+        }
+    }
+
+    /// Returns items of an alternative array.
+    pub fn alternatives(&self) -> Option<&[Alternative]> {
+        match *self {
+            Value::Alt(ref v) => Some(v),
+            _ => None,
+        }
+    }
+
+    /// Returns fields of a structure.
+    pub fn as_struct(&self) -> Option<&Properties> {
+        match *self {
+            Value::Struct(ref p) => Some(p),
+            _ => None,
+        }
+    }
+
+    /// Returns a field of a structure.
+    pub fn field(&self, namespace: &str, name: &str) -> Option<&Value> {
+        self.as_struct()
+            .and_then(|p| p.get(&Name::new(namespace, name)))
+    }
+
+    /// Returns the text in the given language from a language alternative.
+    ///
+    /// Languages are compared case-insensitively, as required by RFC 3066.
+    pub fn text_in(&self, language: &str) -> Option<&str> {
+        self.alternatives()?
+            .iter()
+            .find(|a| {
+                a.language
+                    .as_ref()
+                    .is_some_and(|l| l.eq_ignore_ascii_case(language))
+            })
+            .and_then(|a| a.value.as_text())
+    }
+
+    /// Returns the default text of this value.
+    ///
+    /// For simple values this is the value itself. For language alternatives this is the
+    /// `x-default` item, or the first item if there is no default one.
+    pub fn default_text(&self) -> Option<&str> {
+        match *self {
+            Value::Text(ref s) => Some(s),
+            Value::Alt(ref v) => self
+                .text_in(DEFAULT_LANGUAGE)
+                .or_else(|| v.first().and_then(|a| a.value.as_text())),
+            _ => None,
+        }
+    }
+
+    /// Returns all texts of an array, or the default text of any other value.
+    pub fn texts(&self) -> Vec<&str> {
+        match self.items() {
+            Some(items) => items.iter().filter_map(Value::default_text).collect(),
+            None => self.default_text().into_iter().collect(),
+        }
+    }
+}
+
+/// Properties read from an XMP packet.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Xmp {
+    /// Top-level properties of all `rdf:Description` elements in the packet.
+    pub properties: Properties,
+}
+
+impl Xmp {
+    /// Parses the given XMP packet.
+    ///
+    /// The packet may be wrapped into `<?xpacket?>` processing instructions and an
+    /// `x:xmpmeta` element. An error is returned if the packet is not well-formed XML or
+    /// if it does not contain an `rdf:RDF` element.
+    pub fn parse(packet: &str) -> Result<Xmp> {
+        let root = xml::parse(packet)?;
+        let rdf = root
+            .find(ns::RDF, "RDF")
+            .ok_or_else(|| invalid_format!("XMP packet has no rdf:RDF element"))?;
+
+        let mut properties = Properties::new();
+        for description in rdf.elements() {
+            if description.name.is(ns::RDF, "Description") {
+                properties.append(&mut read_properties(description));
+            }
+        }
+        Ok(Xmp { properties })
+    }
+
+    /// Returns the value of the given property, if it is present.
+    pub fn get(&self, namespace: &str, name: &str) -> Option<&Value> {
+        self.properties.get(&Name::new(namespace, name))
+    }
+
+    fn text(&self, namespace: &str, name: &str) -> Option<&str> {
+        self.get(namespace, name).and_then(Value::default_text)
+    }
+
+    fn texts(&self, namespace: &str, name: &str) -> Vec<&str> {
+        self.get(namespace, name)
+            .map_or_else(Vec::new, Value::texts)
+    }
+
+    /// Returns the default title (`dc:title`).
+    #[inline]
+    pub fn title(&self) -> Option<&str> {
+        self.text(ns::DC, "title")
+    }
+
+    /// Returns the default description (`dc:description`).
+    #[inline]
+    pub fn description(&self) -> Option<&str> {
+        self.text(ns::DC, "description")
+    }
+
+    /// Returns the creators in their original order (`dc:creator`).
+    #[inline]
+    pub fn creators(&self) -> Vec<&str> {
+        self.texts(ns::DC, "creator")
+    }
+
+    /// Returns the keywords (`dc:subject`).
+    #[inline]
+    pub fn subjects(&self) -> Vec<&str> {
+        self.texts(ns::DC, "subject")
+    }
+
+    /// Returns the default copyright notice (`dc:rights`).
+    #[inline]
+    pub fn rights(&self) -> Option<&str> {
+        self.text(ns::DC, "rights")
+    }
+
+    /// Returns the date and time the resource was created (`xmp:CreateDate`).
+    ///
+    /// The value is returned as is, which is usually an ISO 8601 date.
+    #[inline]
+    pub fn create_date(&self) -> Option<&str> {
+        self.text(ns::XMP, "CreateDate")
+    }
+
+    /// Returns the date and time the resource was last modified (`xmp:ModifyDate`).
+    #[inline]
+    pub fn modify_date(&self) -> Option<&str> {
+        self.text(ns::XMP, "ModifyDate")
+    }
+
+    /// Returns the name of the tool which created the resource (`xmp:CreatorTool`).
+    #[inline]
+    pub fn creator_tool(&self) -> Option<&str> {
+        self.text(ns::XMP, "CreatorTool")
+    }
+
+    /// Returns the user-assigned rating (`xmp:Rating`).
+    ///
+    /// Valid ratings are between 0 and 5; -1 means the resource is rejected.
+    pub fn rating(&self) -> Option<f64> {
+        self.text(ns::XMP, "Rating")
+            .and_then(|s| f64::from_str(s.trim()).ok())
+    }
+
+    /// Returns the credit line (`photoshop:Credit`).
+    #[inline]
+    pub fn credit(&self) -> Option<&str> {
+        self.text(ns::PHOTOSHOP, "Credit")
+    }
+
+    /// Returns the value of the given IPTC Core property (`Iptc4xmpCore`), e.g.
+    /// `Location`, `CountryCode` or `CreatorContactInfo`.
+    #[inline]
+    pub fn iptc_core(&self, name: &str) -> Option<&Value> {
+        self.get(ns::IPTC_CORE, name)
+    }
+
+    /// Returns the orientation mirrored from TIFF metadata (`tiff:Orientation`).
+    pub fn orientation(&self) -> Option<Orientation> {
+        self.text(ns::TIFF, "Orientation")
+            .and_then(|s| s.trim().parse().ok())
+            .and_then(Orientation::from_exif)
+    }
+}
+
+// reads properties from the attributes and child elements of a node element
+fn read_properties(e: &Element) -> Properties {
+    let mut properties = Properties::new();
+    for (name, value) in &e.attributes {
+        if is_property_name(&name.namespace) {
+            properties.insert(
+                Name::new(&name.namespace, &name.local),
+                Value::Text(value.clone()),
+            );
+        }
+    }
+    for child in e.elements() {
+        if is_property_name(&child.name.namespace) {
+            properties.insert(
+                Name::new(&child.name.namespace, &child.name.local),
+                read_value(child),
+            );
+        }
+    }
+    properties
+}
+
+// names in the RDF and XML namespaces are syntax, and names without a namespace are
+// not valid in RDF
+fn is_property_name(namespace: &str) -> bool {
+    !namespace.is_empty() && namespace != ns::RDF && namespace != XML_NAMESPACE
+}
+
+// reads the value of a property element
+fn read_value(e: &Element) -> Value {
+    if let Some(resource) = e.attribute(ns::RDF, "resource") {
+        return Value::Text(resource.to_owned());
+    }
+    if e.attribute(ns::RDF, "parseType") == Some("Resource") {
+        return Value::Struct(read_properties(e));
+    }
+
+    if let Some(child) = e.elements().next() {
+        if child.name.namespace != ns::RDF {
+            return Value::Struct(read_properties(e));
+        }
+        let items = || child.elements().filter(|li| li.name.is(ns::RDF, "li"));
+        return match &child.name.local[..] {
+            "Bag" => Value::Bag(items().map(read_value).collect()),
+            "Seq" => Value::Seq(items().map(read_value).collect()),
+            "Alt" => Value::Alt(
+                items()
+                    .map(|li| Alternative {
+                        language: li.attribute(XML_NAMESPACE, "lang").map(str::to_owned),
+                        value: read_value(li),
+                    })
+                    .collect(),
+            ),
+            _ => Value::Struct(read_properties(child)),
+        };
+    }
+
+    // a structure may also be written with its fields as attributes
+    if e.attributes
+        .iter()
+        .any(|(n, _)| is_property_name(&n.namespace))
+    {
+        return Value::Struct(read_properties(e));
+    }
+    Value::Text(e.text())
+}
+
+/// Decodes raw XMP packet data into a string.
+///
+/// XMP packets must be encoded in UTF-8 when embedded into image files. Some writers
+/// add a byte order mark or terminate the packet with NUL bytes, which are removed.
+/// Returns `None` if the data is not valid UTF-8.
+pub(crate) fn decode(data: &[u8]) -> Option<String> {
+    let data = data.strip_prefix(b"\xef\xbb\xbf").unwrap_or(data);
+    let end = data.iter().rposition(|&b| b != 0).map_or(0, |i| i + 1);
+    String::from_utf8(data[..end].to_vec()).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ns, Alternative, Name, Value, Xmp};
+    use crate::types::Orientation;
+
+    const PACKET: &str = r#"<?xpacket begin="" id="W5M0MpCehiHzreSzNTczkc9d"?>
+<x:xmpmeta xmlns:x="adobe:ns:meta/">
+ <rdf:RDF xmlns:rdf="http://www.w3.org/1999/02/22-rdf-syntax-ns#">
+  <rdf:Description rdf:about=""
+    xmlns:xmp="http://ns.adobe.com/xap/1.0/"
+    xmlns:tiff="http://ns.adobe.com/tiff/1.0/"
+    xmp:CreateDate="2023-02-26T12:00:00+01:00"
+    xmp:Rating="4"
+    tiff:Orientation="6"/>
+  <rdf:Description rdf:about="" xmlns:d="http://purl.org/dc/elements/1.1/">
+   <d:title>
+    <rdf:Alt>
+     <rdf:li xml:lang="x-default">Kitty &amp; friends</rdf:li>
+     <rdf:li xml:lang="de-DE">K&#228;tzchen</rdf:li>
+    </rdf:Alt>
+   </d:title>
+   <d:creator><rdf:Seq><rdf:li>Jane Doe</rdf:li><rdf:li>John Doe</rdf:li></rdf:Seq></d:creator>
+   <d:subject><rdf:Bag><rdf:li>cat</rdf:li><rdf:li><![CDATA[<pets>]]></rdf:li></rdf:Bag></d:subject>
+  </rdf:Description>
+  <rdf:Description rdf:about=""
+    xmlns:photoshop="http://ns.adobe.com/photoshop/1.0/"
+    xmlns:Iptc4xmpCore="http://iptc.org/std/Iptc4xmpCore/1.0/xmlns/"
+    photoshop:Credit="immeta">
+   <Iptc4xmpCore:Location>Backyard</Iptc4xmpCore:Location>
+   <Iptc4xmpCore:CreatorContactInfo rdf:parseType="Resource">
+    <Iptc4xmpCore:CiAdrCity>Springfield</Iptc4xmpCore:CiAdrCity>
+   </Iptc4xmpCore:CreatorContactInfo>
+   <Iptc4xmpCore:Scene rdf:resource="http://cv.iptc.org/newscodes/scene/011100"/>
+  </rdf:Description>
+ </rdf:RDF>
+</x:xmpmeta>
+<?xpacket end="w"?>"#;
+
+    #[test]
+    fn test_parse() {
+        let xmp = Xmp::parse(PACKET).unwrap();
+
+        assert_eq!(xmp.title(), Some("Kitty & friends"));
+        assert_eq!(
+            xmp.get(ns::DC, "title").unwrap().text_in("de-de"),
+            Some("Kätzchen")
+        );
+        assert_eq!(
+            xmp.get(ns::DC, "title").unwrap().alternatives().unwrap()[1],
+            Alternative {
+                language: Some("de-DE".into()),
+                value: Value::Text("Kätzchen".into()),
+            }
+        );
+        assert_eq!(xmp.creators(), vec!["Jane Doe", "John Doe"]);
+        assert!(matches!(xmp.get(ns::DC, "creator"), Some(Value::Seq(_))));
+        assert_eq!(xmp.subjects(), vec!["cat", "<pets>"]);
+        assert!(matches!(xmp.get(ns::DC, "subject"), Some(Value::Bag(_))));
+        assert_eq!(xmp.description(), None);
+
+        assert_eq!(xmp.create_date(), Some("2023-02-26T12:00:00+01:00"));
+        assert_eq!(xmp.rating(), Some(4.0));
+        assert_eq!(xmp.orientation(), Some(Orientation::Rotate90));
+        assert_eq!(xmp.credit(), Some("immeta"));
+
+        assert_eq!(
+            xmp.iptc_core("Location").and_then(Value::as_text),
+            Some("Backyard")
+        );
+        let contact = xmp.iptc_core("CreatorContactInfo").unwrap();
+        assert_eq!(
+            contact
+                .field(ns::IPTC_CORE, "CiAdrCity")
+                .and_then(Value::as_text),
+            Some("Springfield")
+        );
+        assert_eq!(
+            xmp.iptc_core("Scene").and_then(Value::as_text),
+            Some("http://cv.iptc.org/newscodes/scene/011100")
+        );
+
+        // rdf:about is syntax rather than a property
+        assert!(!xmp.properties.contains_key(&Name::new(ns::RDF, "about")));
+    }
+
+    #[test]
+    fn test_parse_struct_attributes() {
+        let xmp = Xmp::parse(
+            r#"<rdf:RDF xmlns:rdf="http://www.w3.org/1999/02/22-rdf-syntax-ns#"
+                xmlns:exif="http://ns.adobe.com/exif/1.0/">
+              <rdf:Description><exif:Flash exif:Fired="False" exif:Mode="2"/></rdf:Description>
+            </rdf:RDF>"#,
+        )
+        .unwrap();
+
+        let flash = xmp.get(ns::EXIF, "Flash").unwrap();
+        assert_eq!(
+            flash.field(ns::EXIF, "Fired").and_then(Value::as_text),
+            Some("False")
+        );
+        assert_eq!(
+            flash.field(ns::EXIF, "Mode").and_then(Value::as_text),
+            Some("2")
+        );
+    }
+
+    #[test]
+    fn test_parse_invalid() {
+        assert!(Xmp::parse("<x:xmpmeta xmlns:x=\"adobe:ns:meta/\"/>").is_err());
+        assert!(Xmp::parse("<rdf:RDF>").is_err());
+        assert!(Xmp::parse("<a><b></a></b>").is_err());
+        assert!(Xmp::parse("<unknown:RDF/>").is_err());
+    }
+
+    #[test]
+    fn test_parse_too_deep() {
+        let depth = 100_000;
+        let packet = format!("{}{}", "<a>".repeat(depth), "</a>".repeat(depth));
+        assert!(Xmp::parse(&packet).is_err());
+    }
+}
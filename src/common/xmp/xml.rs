@@ -0,0 +1,377 @@
+//! A minimal namespace-aware XML reader sufficient for XMP packets.
+//!
+//! It builds a tree of elements with resolved names; DTDs, processing instructions and
+//! comments are skipped.
+
+use std::collections::HashMap;
+
+use crate::types::Result;
+
+pub(super) const XML_NAMESPACE: &str = "http://www.w3.org/XML/1998/namespace";
+
+// the tree is processed recursively, so its depth is limited to keep the stack bounded;
+// real XMP packets are only a few levels deep
+const MAX_DEPTH: usize = 256;
+
+/// An element or attribute name with a resolved namespace.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub(super) struct QName {
+    /// Namespace URI, empty if the name is not in a namespace.
+    pub namespace: String,
+    pub local: String,
+}
+
+impl QName {
+    #[inline]
+    pub fn is(&self, namespace: &str, local: &str) -> bool {
+        self.namespace == namespace && self.local == local
+    }
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub(super) enum Node {
+    Element(Element),
+    Text(String),
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub(super) struct Element {
+    pub name: QName,
+    /// Attributes except namespace declarations.
+    pub attributes: Vec<(QName, String)>,
+    pub children: Vec<Node>,
+}
+
+impl Element {
+    pub fn attribute(&self, namespace: &str, local: &str) -> Option<&str> {
+        self.attributes
+            .iter()
+            .find(|(n, _)| n.is(namespace, local))
+            .map(|(_, v)| &v[..])
+    }
+
+    pub fn elements(&self) -> impl Iterator<Item = &Element> {
+        self.children.iter().filter_map(|c| match *c {
+            Node::Element(ref e) => Some(e),
+            Node::Text(_) => None,
+        })
+    }
+
+    pub fn text(&self) -> String {
+        let mut result = String::new();
+        for c in &self.children {
+            if let Node::Text(ref t) = *c {
+                result.push_str(t);
+            }
+        }
+        result
+    }
+
+    /// Finds the first element with the given name in this subtree, including this element.
+    pub fn find(&self, namespace: &str, local: &str) -> Option<&Element> {
+        if self.name.is(namespace, local) {
+            return Some(self);
+        }
+        self.elements().find_map(|e| e.find(namespace, local))
+    }
+}
+
+// An element which is not closed yet, together with its namespace declarations.
+struct OpenElement<'a> {
+    raw_name: &'a str,
+    raw_attributes: Vec<(&'a str, String)>,
+    namespaces: HashMap<&'a str, String>,
+    children: Vec<Node>,
+}
+
+/// Parses the given document and returns its root element.
+pub(super) fn parse(document: &str) -> Result<Element> {
+    let mut reader = Reader { rest: document };
+    let mut stack: Vec<OpenElement> = Vec::new();
+    let mut root = None;
+
+    while !reader.rest.is_empty() {
+        if reader.skip_prefixed("<?", "?>")?
+            || reader.skip_prefixed("<!--", "-->")?
+            || reader.skip_doctype()?
+        {
+            continue;
+        }
+
+        if let Some(rest) = reader.rest.strip_prefix("<![CDATA[") {
+            let end = rest
+                .find("]]>")
+                .ok_or_else(|| invalid_format!("unterminated CDATA section in XML"))?;
+            if let Some(parent) = stack.last_mut() {
+                parent.children.push(Node::Text(rest[..end].to_owned()));
+            }
+            reader.rest = &rest[end + 3..];
+        } else if let Some(rest) = reader.rest.strip_prefix("</") {
+            let end = rest
+                .find('>')
+                .ok_or_else(|| invalid_format!("unterminated XML end tag"))?;
+            let raw_name = rest[..end].trim_end();
+            reader.rest = &rest[end + 1..];
+
+            let open = stack
+                .pop()
+                .ok_or_else(|| invalid_format!("unexpected XML end tag: {}", raw_name))?;
+            if open.raw_name != raw_name {
+                return Err(invalid_format!(
+                    "mismatched XML end tag: expected {}, got {}",
+                    open.raw_name,
+                    raw_name
+                ));
+            }
+            let element = close(&stack, open)?;
+            attach(&mut stack, &mut root, element);
+        } else if reader.rest.starts_with('<') {
+            if stack.len() >= MAX_DEPTH {
+                return Err(invalid_format!(
+                    "XML elements are nested deeper than {} levels",
+                    MAX_DEPTH
+                ));
+            }
+            let (open, empty) = reader.read_start_tag()?;
+            if empty {
+                let element = close(&stack, open)?;
+                attach(&mut stack, &mut root, element);
+            } else {
+                stack.push(open);
+            }
+        } else {
+            let end = reader.rest.find('<').unwrap_or(reader.rest.len());
+            let text = &reader.rest[..end];
+            reader.rest = &reader.rest[end..];
+            if let Some(parent) = stack.last_mut() {
+                parent.children.push(Node::Text(unescape(text)?));
+            }
+        }
+    }
+
+    if let Some(open) = stack.last() {
+        return Err(invalid_format!("unclosed XML element: {}", open.raw_name));
+    }
+    root.ok_or_else(|| invalid_format!("XML document has no root element"))
+}
+
+// adds a closed element to its parent, or makes it the root element
+fn attach(stack: &mut [OpenElement], root: &mut Option<Element>, element: Element) {
+    match stack.last_mut() {
+        Some(parent) => parent.children.push(Node::Element(element)),
+        None => {
+            // anything after the root element is ignored
+            if root.is_none() {
+                *root = Some(element);
+            }
+        }
+    }
+}
+
+struct Reader<'a> {
+    rest: &'a str,
+}
+
+impl<'a> Reader<'a> {
+    fn skip_prefixed(&mut self, start: &str, end: &str) -> Result<bool> {
+        match self.rest.strip_prefix(start) {
+            Some(rest) => {
+                let i = rest
+                    .find(end)
+                    .ok_or_else(|| invalid_format!("unterminated {} in XML", start))?;
+                self.rest = &rest[i + end.len()..];
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    fn skip_doctype(&mut self) -> Result<bool> {
+        let rest = match self.rest.strip_prefix("<!DOCTYPE") {
+            Some(rest) => rest,
+            None => return Ok(false),
+        };
+        // internal subset may contain '>' characters inside brackets
+        let mut depth = 0;
+        for (i, c) in rest.char_indices() {
+            match c {
+                '[' => depth += 1,
+                ']' => depth -= 1,
+                '>' if depth <= 0 => {
+                    self.rest = &rest[i + 1..];
+                    return Ok(true);
+                }
+                _ => {}
+            }
+        }
+        Err(invalid_format!("unterminated DOCTYPE in XML"))
+    }
+
+    fn skip_whitespace(&mut self) {
+        self.rest = self.rest.trim_start();
+    }
+
+    fn read_name(&mut self) -> Result<&'a str> {
+        let end = self
+            .rest
+            .find(|c: char| c.is_whitespace() || matches!(c, '=' | '/' | '>'))
+            .unwrap_or(self.rest.len());
+        if end == 0 {
+            return Err(invalid_format!("missing name in XML tag"));
+        }
+        let name = &self.rest[..end];
+        self.rest = &self.rest[end..];
+        Ok(name)
+    }
+
+    // reads a start or an empty element tag, returning whether it is empty
+    fn read_start_tag(&mut self) -> Result<(OpenElement<'a>, bool)> {
+        self.rest = &self.rest[1..];
+        let raw_name = self.read_name()?;
+        let mut raw_attributes = Vec::new();
+        let mut namespaces = HashMap::new();
+
+        let empty = loop {
+            self.skip_whitespace();
+            if let Some(rest) = self.rest.strip_prefix("/>") {
+                self.rest = rest;
+                break true;
+            }
+            if let Some(rest) = self.rest.strip_prefix('>') {
+                self.rest = rest;
+                break false;
+            }
+            if self.rest.is_empty() {
+                return Err(invalid_format!("unterminated XML tag: {}", raw_name));
+            }
+
+            let name = self.read_name()?;
+            self.skip_whitespace();
+            self.rest = self
+                .rest
+                .strip_prefix('=')
+                .ok_or_else(|| invalid_format!("missing value of XML attribute {}", name))?;
+            self.skip_whitespace();
+            let quote = match self.rest.chars().next() {
+                Some(q @ ('"' | '\'')) => q,
+                _ => return Err(invalid_format!("unquoted value of XML attribute {}", name)),
+            };
+            let rest = &self.rest[1..];
+            let end = rest
+                .find(quote)
+                .ok_or_else(|| invalid_format!("unterminated value of XML attribute {}", name))?;
+            let value = unescape(&rest[..end])?;
+            self.rest = &rest[end + 1..];
+
+            if name == "xmlns" {
+                namespaces.insert("", value);
+            } else if let Some(prefix) = name.strip_prefix("xmlns:") {
+                namespaces.insert(prefix, value);
+            } else {
+                raw_attributes.push((name, value));
+            }
+        };
+
+        let open = OpenElement {
+            raw_name,
+            raw_attributes,
+            namespaces,
+            children: Vec::new(),
+        };
+        Ok((open, empty))
+    }
+}
+
+// resolves names of the given element and converts it to its final form
+fn close(stack: &[OpenElement], open: OpenElement) -> Result<Element> {
+    let resolve = |raw: &str, is_attribute: bool| -> Result<QName> {
+        let (prefix, local) = match raw.split_once(':') {
+            Some((prefix, local)) => (prefix, local),
+            // unprefixed attributes are not in any namespace
+            None if is_attribute => {
+                return Ok(QName {
+                    namespace: String::new(),
+                    local: raw.to_owned(),
+                })
+            }
+            None => ("", raw),
+        };
+        if prefix == "xml" {
+            return Ok(QName {
+                namespace: XML_NAMESPACE.to_owned(),
+                local: local.to_owned(),
+            });
+        }
+        let namespace = std::iter::once(&open)
+            .chain(stack.iter().rev())
+            .find_map(|e| e.namespaces.get(prefix));
+        match namespace {
+            Some(namespace) => Ok(QName {
+                namespace: namespace.clone(),
+                local: local.to_owned(),
+            }),
+            None if prefix.is_empty() => Ok(QName {
+                namespace: String::new(),
+                local: local.to_owned(),
+            }),
+            None => Err(invalid_format!(
+                "undeclared XML namespace prefix: {}",
+                prefix
+            )),
+        }
+    };
+
+    let name = resolve(open.raw_name, false)?;
+    let attributes = open
+        .raw_attributes
+        .iter()
+        .map(|(n, v)| Ok((resolve(n, true)?, v.clone())))
+        .collect::<Result<Vec<_>>>()?;
+
+    Ok(Element {
+        name,
+        attributes,
+        children: open.children,
+    })
+}
+
+// replaces predefined entities and character references
+fn unescape(s: &str) -> Result<String> {
+    let mut result = String::with_capacity(s.len());
+    let mut rest = s;
+    while let Some(i) = rest.find('&') {
+        result.push_str(&rest[..i]);
+        rest = &rest[i + 1..];
+        let end = rest
+            .find(';')
+            .ok_or_else(|| invalid_format!("unterminated XML entity reference"))?;
+        let entity = &rest[..end];
+        let c = match entity {
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "amp" => Some('&'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            _ => match entity.strip_prefix('#') {
+                Some(n) => match n.strip_prefix('x').or_else(|| n.strip_prefix('X')) {
+                    Some(hex) => u32::from_str_radix(hex, 16).ok(),
+                    None => n.parse().ok(),
+                }
+                .and_then(char::from_u32),
+                None => None,
+            },
+        };
+        match c {
+            Some(c) => result.push(c),
+            None => {
+                return Err(invalid_format!(
+                    "invalid XML entity reference: &{};",
+                    entity
+                ))
+            }
+        }
+        rest = &rest[end + 1..];
+    }
+    result.push_str(rest);
+    Ok(result)
+}
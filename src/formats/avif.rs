@@ -0,0 +1,408 @@
+//! Metadata of AVIF images and image sequences.
+//!
+//! AVIF images are HEIF images coded with AV1, so their structure is described in the `heif`
+//! module. Image sequences additionally contain a movie with a video track, which is used to
+//! find the number of frames and their timing.
+
+use std::fmt;
+use std::io::{BufRead, Seek};
+
+use byteorder::{BigEndian, ReadBytesExt};
+
+use crate::common::exif::{Exif, Gps};
+use crate::common::isobmff::{self, Boxes, FourCc};
+use crate::formats::heif::{self, Container, ImageProperties, Item, Nclx, Transformation};
+use crate::formats::png::MasteringDisplayColorVolume;
+use crate::traits::LoadableMetadata;
+use crate::types::{Dimensions, Orientation, Result};
+
+/// Brands which identify AVIF images and image sequences.
+pub const BRANDS: &[&[u8; 4]] = &[b"avif", b"avis"];
+
+// auxiliary types of alpha planes defined by MPEG and by HEVC
+const ALPHA_AUXILIARY_TYPES: &[&[u8]] = &[
+    b"urn:mpeg:mpegB:cicp:systems:auxiliary:alpha",
+    b"urn:mpeg:hevc:2015:auxid:1",
+];
+
+/// Chroma subsampling of an AV1 image.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum ChromaSubsampling {
+    /// No subsampling.
+    Yuv444,
+    /// Chroma planes have half horizontal resolution.
+    Yuv422,
+    /// Chroma planes have half horizontal and vertical resolution.
+    Yuv420,
+    /// There are no chroma planes.
+    Monochrome,
+}
+
+impl fmt::Display for ChromaSubsampling {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match *self {
+            ChromaSubsampling::Yuv444 => "4:4:4",
+            ChromaSubsampling::Yuv422 => "4:2:2",
+            ChromaSubsampling::Yuv420 => "4:2:0",
+            ChromaSubsampling::Monochrome => "4:0:0",
+        })
+    }
+}
+
+/// AV1 codec configuration from the `av1C` property.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct Av1Config {
+    /// AV1 profile: 0 for Main, 1 for High and 2 for Professional.
+    pub profile: u8,
+    /// Level index of the first operating point, e.g. 8 for level 4.0.
+    pub level: u8,
+    /// Tier of the first operating point: 0 for Main and 1 for High.
+    pub tier: u8,
+    /// Number of bits per sample: 8, 10 or 12.
+    pub bit_depth: u8,
+    /// Whether the image has only the luma plane.
+    pub monochrome: bool,
+    /// Whether chroma planes are subsampled horizontally.
+    pub chroma_subsampling_x: bool,
+    /// Whether chroma planes are subsampled vertically.
+    pub chroma_subsampling_y: bool,
+    /// Position of chroma samples relative to luma samples, as defined in the AV1
+    /// specification.
+    pub chroma_sample_position: u8,
+}
+
+impl Av1Config {
+    /// Returns chroma subsampling of the image.
+    pub fn chroma_subsampling(&self) -> ChromaSubsampling {
+        match (
+            self.monochrome,
+            self.chroma_subsampling_x,
+            self.chroma_subsampling_y,
+        ) {
+            (true, _, _) => ChromaSubsampling::Monochrome,
+            (false, true, true) => ChromaSubsampling::Yuv420,
+            (false, true, false) => ChromaSubsampling::Yuv422,
+            (false, false, _) => ChromaSubsampling::Yuv444,
+        }
+    }
+
+    fn parse(data: &[u8]) -> Result<Av1Config> {
+        if data.len() < 4 {
+            return Err(unexpected_eof!("when reading av1C property"));
+        }
+        // the highest bit is a marker and the rest of the first byte is the version
+        if data[0] != 0x81 {
+            return Err(invalid_format!("invalid av1C version: {}", data[0] & 0x7f));
+        }
+        let high_bitdepth = data[2] & 0x40 != 0;
+        let twelve_bit = data[2] & 0x20 != 0;
+        Ok(Av1Config {
+            profile: data[1] >> 5,
+            level: data[1] & 0x1f,
+            tier: data[2] >> 7,
+            bit_depth: match (high_bitdepth, twelve_bit) {
+                (false, _) => 8,
+                (true, false) => 10,
+                (true, true) => 12,
+            },
+            monochrome: data[2] & 0x10 != 0,
+            chroma_subsampling_x: data[2] & 0x08 != 0,
+            chroma_subsampling_y: data[2] & 0x04 != 0,
+            chroma_sample_position: data[2] & 0x03,
+        })
+    }
+}
+
+/// Content light level information from the `clli` property.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct ContentLightLevel {
+    /// Maximum light level of any pixel, in cd/m².
+    pub max_content_light_level: u16,
+    /// Maximum average light level of any frame, in cd/m².
+    pub max_picture_average_light_level: u16,
+}
+
+/// Information about an image sequence from its video track.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Sequence {
+    /// Number of time units in a second.
+    pub timescale: u32,
+    /// Duration of the track in time units.
+    pub duration: u64,
+    /// Number of frames in the track.
+    pub frames_number: u32,
+    /// Durations of frames in time units, as pairs of a number of consecutive frames and
+    /// their duration.
+    pub frame_durations: Vec<(u32, u32)>,
+}
+
+impl Sequence {
+    /// Returns the duration of the track in milliseconds.
+    pub fn duration_ms(&self) -> f64 {
+        if self.timescale == 0 {
+            0.0
+        } else {
+            self.duration as f64 * 1000.0 / self.timescale as f64
+        }
+    }
+
+    // parses the first track of color images in the moov box
+    fn parse(moov: &[u8]) -> Result<Option<Sequence>> {
+        for b in Boxes::new(moov) {
+            let (box_type, data) = b?;
+            if box_type.as_bytes() == b"trak" {
+                if let Some(sequence) = Sequence::parse_track(data)? {
+                    return Ok(Some(sequence));
+                }
+            }
+        }
+        Ok(None)
+    }
+
+    fn parse_track(trak: &[u8]) -> Result<Option<Sequence>> {
+        let mdia = match find_box(trak, b"mdia")? {
+            Some(mdia) => mdia,
+            None => return Ok(None),
+        };
+        // alpha planes are stored in separate auxiliary tracks
+        match find_box(mdia, b"hdlr")? {
+            Some(hdlr) if isobmff::full_box(hdlr)?.2.get(4..8) == Some(b"pict") => {}
+            _ => return Ok(None),
+        }
+
+        let mdhd =
+            find_box(mdia, b"mdhd")?.ok_or_else(|| invalid_format!("no mdhd box in track"))?;
+        let (version, _, mut r) = isobmff::full_box(mdhd)?;
+        let (timescale, duration) = if version == 1 {
+            // skip creation and modification times
+            r = r.get(16..).unwrap_or(&[]);
+            let timescale = try_if_eof!(r.read_u32::<BigEndian>(), "when reading timescale");
+            let duration = try_if_eof!(r.read_u64::<BigEndian>(), "when reading duration");
+            (timescale, duration)
+        } else {
+            r = r.get(8..).unwrap_or(&[]);
+            let timescale = try_if_eof!(r.read_u32::<BigEndian>(), "when reading timescale");
+            let duration = try_if_eof!(r.read_u32::<BigEndian>(), "when reading duration");
+            (timescale, duration as u64)
+        };
+
+        let stts = match find_box(mdia, b"minf")? {
+            Some(minf) => match find_box(minf, b"stbl")? {
+                Some(stbl) => find_box(stbl, b"stts")?,
+                None => None,
+            },
+            None => None,
+        };
+        let stts = stts.ok_or_else(|| invalid_format!("no stts box in track"))?;
+        let (_, _, mut r) = isobmff::full_box(stts)?;
+        let count = try_if_eof!(r.read_u32::<BigEndian>(), "when reading stts entry count");
+        let mut frame_durations = Vec::new();
+        for _ in 0..count {
+            let frames = try_if_eof!(r.read_u32::<BigEndian>(), "when reading stts entry");
+            let delta = try_if_eof!(r.read_u32::<BigEndian>(), "when reading stts entry");
+            frame_durations.push((frames, delta));
+        }
+
+        Ok(Some(Sequence {
+            timescale,
+            duration,
+            frames_number: frame_durations
+                .iter()
+                .fold(0u32, |n, &(frames, _)| n.saturating_add(frames)),
+            frame_durations,
+        }))
+    }
+}
+
+// returns the payload of the first box of the given type
+fn find_box<'a>(data: &'a [u8], box_type: &[u8; 4]) -> Result<Option<&'a [u8]>> {
+    for b in Boxes::new(data) {
+        let (t, data) = b?;
+        if t.as_bytes() == box_type {
+            return Ok(Some(data));
+        }
+    }
+    Ok(None)
+}
+
+/// Represents metadata of an AVIF image or image sequence.
+///
+/// Information about the image comes from properties of its primary item. Image sequences
+/// usually also have a primary item, which is the first frame.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Metadata {
+    /// Major brand from the `ftyp` box, usually `avif` for images and `avis` for image
+    /// sequences.
+    pub major_brand: FourCc,
+    /// Minor version of the major brand.
+    pub minor_version: u32,
+    /// Brands the file is compatible with.
+    pub compatible_brands: Vec<FourCc>,
+    /// Handler type of the `meta` box, `pict` for images.
+    pub handler_type: FourCc,
+    /// Identifier of the primary item.
+    pub primary_item_id: u32,
+    /// All items of the file, including alpha planes.
+    pub items: Vec<Item>,
+    /// Size of the primary image, before transformations are applied.
+    pub dimensions: Dimensions,
+    /// Transformations of the primary image, in the order they are applied.
+    pub transformations: Vec<Transformation>,
+    /// AV1 configuration of the primary image, if it is present.
+    pub av1_config: Option<Av1Config>,
+    /// Number of bits in each channel of the primary image (`pixi` property).
+    pub bits_per_channel: Option<Vec<u8>>,
+    /// Color information of the primary image from a `colr` property, if it is present.
+    pub nclx: Option<Nclx>,
+    /// ICC profile of the primary image from a `colr` property, if it is present.
+    pub icc_profile: Option<Vec<u8>>,
+    /// Content light level of an HDR image, if it is present.
+    pub content_light_level: Option<ContentLightLevel>,
+    /// Color volume of the mastering display of an HDR image, if it is present.
+    ///
+    /// Primaries are reordered to red, green and blue.
+    pub mastering_display_color_volume: Option<MasteringDisplayColorVolume>,
+    /// Identifier of the auxiliary item which contains the alpha plane of the primary image.
+    pub alpha_item_id: Option<u32>,
+    /// Information from the video track of an image sequence.
+    pub sequence: Option<Sequence>,
+    /// EXIF metadata from the first `Exif` item, if it is present, can be read and is valid.
+    pub exif: Option<Exif>,
+    /// XMP packet from the first XMP item, if it is present, can be read and is a valid
+    /// UTF-8 string.
+    pub xmp: Option<String>,
+}
+
+impl Metadata {
+    /// Returns the primary item.
+    pub fn primary_item(&self) -> Option<&Item> {
+        self.items.iter().find(|i| i.id == self.primary_item_id)
+    }
+
+    /// Returns `true` if the image has an alpha plane.
+    #[inline]
+    pub fn has_alpha(&self) -> bool {
+        self.alpha_item_id.is_some()
+    }
+
+    /// Returns the number of frames, which is 1 for still images.
+    pub fn frames_number(&self) -> u32 {
+        self.sequence.as_ref().map_or(1, |s| s.frames_number)
+    }
+
+    /// Returns `true` if the image is a sequence of more than one frame.
+    #[inline]
+    pub fn is_animated(&self) -> bool {
+        self.frames_number() > 1
+    }
+
+    /// Returns the orientation of the primary image derived from its transformations.
+    ///
+    /// As in HEIF images, the EXIF orientation tag should be ignored.
+    pub fn orientation(&self) -> Orientation {
+        heif::orientation(&self.transformations)
+    }
+
+    /// Returns GPS information from EXIF metadata, if it is present.
+    #[inline]
+    pub fn gps(&self) -> Option<Gps> {
+        self.exif.as_ref().and_then(Exif::gps)
+    }
+}
+
+impl Metadata {
+    fn from_container(container: Container) -> Result<Metadata> {
+        let meta = container.meta;
+        let properties = ImageProperties::parse(&meta, meta.primary_item_id)?;
+        let dimensions = properties
+            .dimensions
+            .ok_or_else(|| invalid_format!("primary item of AVIF image has no size"))?;
+
+        let mut av1_config = None;
+        let mut content_light_level = None;
+        let mut mastering_display_color_volume = None;
+        for (box_type, data) in meta.properties_of(meta.primary_item_id) {
+            match box_type.as_bytes() {
+                b"av1C" => av1_config = Some(Av1Config::parse(data)?),
+                b"clli" if data.len() >= 4 => {
+                    content_light_level = Some(ContentLightLevel {
+                        max_content_light_level: u16::from_be_bytes([data[0], data[1]]),
+                        max_picture_average_light_level: u16::from_be_bytes([data[2], data[3]]),
+                    });
+                }
+                b"mdcv" if data.len() >= 24 => {
+                    let point = |i: usize| {
+                        (
+                            u16::from_be_bytes([data[i], data[i + 1]]),
+                            u16::from_be_bytes([data[i + 2], data[i + 3]]),
+                        )
+                    };
+                    mastering_display_color_volume = Some(MasteringDisplayColorVolume {
+                        // primaries are stored in green, blue, red order
+                        primaries: [point(8), point(0), point(4)],
+                        white_point: point(12),
+                        max_luminance: u32::from_be_bytes([data[16], data[17], data[18], data[19]]),
+                        min_luminance: u32::from_be_bytes([data[20], data[21], data[22], data[23]]),
+                    });
+                }
+                _ => {}
+            }
+        }
+
+        // the alpha plane is an auxiliary item which refers to the primary item
+        let alpha_item_id = meta
+            .references
+            .iter()
+            .filter(|r| {
+                r.reference_type.as_bytes() == b"auxl"
+                    && r.to_item_ids.contains(&meta.primary_item_id)
+            })
+            .map(|r| r.from_item_id)
+            .find(|&id| {
+                meta.properties_of(id).any(|(box_type, data)| {
+                    box_type.as_bytes() == b"auxC"
+                        && isobmff::full_box(data).is_ok_and(|(_, _, aux_type)| {
+                            let end = aux_type.iter().position(|&b| b == 0);
+                            ALPHA_AUXILIARY_TYPES
+                                .contains(&&aux_type[..end.unwrap_or(aux_type.len())])
+                        })
+                })
+            });
+
+        let sequence = match container.movie {
+            Some(ref moov) => Sequence::parse(moov)?,
+            None => None,
+        };
+
+        Ok(Metadata {
+            major_brand: container.major_brand,
+            minor_version: container.minor_version,
+            compatible_brands: container.compatible_brands,
+            handler_type: meta.handler_type,
+            primary_item_id: meta.primary_item_id,
+            items: meta.items,
+            dimensions,
+            transformations: properties.transformations,
+            av1_config,
+            bits_per_channel: properties.bits_per_channel,
+            nclx: properties.nclx,
+            icc_profile: properties.icc_profile,
+            content_light_level,
+            mastering_display_color_volume,
+            alpha_item_id,
+            sequence,
+            exif: container.exif,
+            xmp: container.xmp,
+        })
+    }
+}
+
+impl LoadableMetadata for Metadata {
+    fn load<R: ?Sized + BufRead>(r: &mut R) -> Result<Metadata> {
+        Metadata::from_container(Container::load(r, "AVIF", Some(b"avis"))?)
+    }
+
+    fn load_from_seek<R: ?Sized + BufRead + Seek>(r: &mut R) -> Result<Metadata> {
+        Metadata::from_container(Container::load_from_seek(r, "AVIF", Some(b"avis"))?)
+    }
+}
@@ -0,0 +1,394 @@
+//! Metadata of BMP images.
+//!
+//! A BMP file consists of a small file header followed by a device-independent bitmap (DIB)
+//! header, which comes in several versions. Icon files contain the same DIB headers, so they
+//! are exposed separately as `DibHeader`.
+
+use std::fmt;
+use std::io::{BufRead, Read};
+
+use byteorder::{ByteOrder, LittleEndian, ReadBytesExt};
+
+use crate::traits::LoadableMetadata;
+use crate::types::{Dimensions, Result};
+use crate::utils::BufReadExt;
+
+// larger headers are reserved for future versions, but are very unlikely to appear
+const MAX_HEADER_SIZE: u32 = 1024;
+
+// embedded color profiles are usually a few kilobytes
+const MAX_PROFILE_SIZE: u32 = 16 << 20;
+
+/// Version of the DIB header.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum HeaderKind {
+    /// `BITMAPCOREHEADER` from Windows 2.0 and OS/2 1.x, with 16-bit dimensions.
+    Core,
+    /// `OS22XBITMAPHEADER` from OS/2 2.x.
+    Os2,
+    /// `BITMAPINFOHEADER`, the most common version, possibly followed by channel masks.
+    Info,
+    /// `BITMAPV4HEADER`, which adds channel masks and color space information.
+    V4,
+    /// `BITMAPV5HEADER`, which adds rendering intent and color profiles.
+    V5,
+}
+
+impl fmt::Display for HeaderKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match *self {
+            HeaderKind::Core => "BITMAPCOREHEADER",
+            HeaderKind::Os2 => "OS22XBITMAPHEADER",
+            HeaderKind::Info => "BITMAPINFOHEADER",
+            HeaderKind::V4 => "BITMAPV4HEADER",
+            HeaderKind::V5 => "BITMAPV5HEADER",
+        })
+    }
+}
+
+impl HeaderKind {
+    fn from_size(size: u32) -> Option<HeaderKind> {
+        match size {
+            12 => Some(HeaderKind::Core),
+            40 | 52 | 56 => Some(HeaderKind::Info),
+            16..=64 => Some(HeaderKind::Os2),
+            108 => Some(HeaderKind::V4),
+            124..=MAX_HEADER_SIZE => Some(HeaderKind::V5),
+            _ => None,
+        }
+    }
+}
+
+/// Compression method of the pixel data.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Compression {
+    /// No compression.
+    Rgb,
+    /// Run length encoding of 8-bit indices.
+    Rle8,
+    /// Run length encoding of 4-bit indices.
+    Rle4,
+    /// No compression, channels are defined by bit masks.
+    Bitfields,
+    /// The pixel data is a JPEG image.
+    Jpeg,
+    /// The pixel data is a PNG image.
+    Png,
+    /// No compression, channels including alpha are defined by bit masks.
+    AlphaBitfields,
+    /// No compression, CMYK pixels.
+    Cmyk,
+    /// Run length encoding of 8-bit CMYK pixels.
+    CmykRle8,
+    /// Run length encoding of 4-bit CMYK pixels.
+    CmykRle4,
+    /// Unknown compression method.
+    Unknown(u32),
+}
+
+impl fmt::Display for Compression {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Compression::Rgb => f.write_str("RGB"),
+            Compression::Rle8 => f.write_str("RLE8"),
+            Compression::Rle4 => f.write_str("RLE4"),
+            Compression::Bitfields => f.write_str("Bitfields"),
+            Compression::Jpeg => f.write_str("JPEG"),
+            Compression::Png => f.write_str("PNG"),
+            Compression::AlphaBitfields => f.write_str("Alpha bitfields"),
+            Compression::Cmyk => f.write_str("CMYK"),
+            Compression::CmykRle8 => f.write_str("CMYK RLE8"),
+            Compression::CmykRle4 => f.write_str("CMYK RLE4"),
+            Compression::Unknown(n) => write!(f, "Unknown ({n})"),
+        }
+    }
+}
+
+impl Compression {
+    fn from_u32(n: u32) -> Compression {
+        match n {
+            0 => Compression::Rgb,
+            1 => Compression::Rle8,
+            2 => Compression::Rle4,
+            3 => Compression::Bitfields,
+            4 => Compression::Jpeg,
+            5 => Compression::Png,
+            6 => Compression::AlphaBitfields,
+            11 => Compression::Cmyk,
+            12 => Compression::CmykRle8,
+            13 => Compression::CmykRle4,
+            n => Compression::Unknown(n),
+        }
+    }
+}
+
+/// Bit masks of color channels in a pixel.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct ChannelMasks {
+    pub red: u32,
+    pub green: u32,
+    pub blue: u32,
+    /// Alpha channel mask, 0 if the image has no alpha channel.
+    pub alpha: u32,
+}
+
+/// Color space of the image from V4 and V5 headers.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum ColorSpace {
+    /// Calibrated RGB defined by the endpoints and gamma values in the header.
+    CalibratedRgb,
+    /// sRGB color space.
+    Srgb,
+    /// The default color space of the system, which is sRGB nowadays.
+    Windows,
+    /// The color profile is stored in a separate file.
+    LinkedProfile,
+    /// The color profile is embedded in the file.
+    EmbeddedProfile,
+    /// Unknown color space.
+    Unknown(u32),
+}
+
+impl fmt::Display for ColorSpace {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ColorSpace::CalibratedRgb => f.write_str("Calibrated RGB"),
+            ColorSpace::Srgb => f.write_str("sRGB"),
+            ColorSpace::Windows => f.write_str("Windows"),
+            ColorSpace::LinkedProfile => f.write_str("Linked profile"),
+            ColorSpace::EmbeddedProfile => f.write_str("Embedded profile"),
+            ColorSpace::Unknown(n) => write!(f, "Unknown ({n:#x})"),
+        }
+    }
+}
+
+impl ColorSpace {
+    fn from_u32(n: u32) -> ColorSpace {
+        // color space types are four-character codes stored as little-endian integers
+        match &n.to_be_bytes() {
+            [0, 0, 0, 0] => ColorSpace::CalibratedRgb,
+            b"sRGB" => ColorSpace::Srgb,
+            b"Win " => ColorSpace::Windows,
+            b"LINK" => ColorSpace::LinkedProfile,
+            b"MBED" => ColorSpace::EmbeddedProfile,
+            _ => ColorSpace::Unknown(n),
+        }
+    }
+}
+
+// offset of color profile data relative to the beginning of the DIB header and its size
+type ProfileLocation = (u32, u32);
+
+/// Contains information from a DIB header.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct DibHeader {
+    /// Version of the header.
+    pub kind: HeaderKind,
+    /// Image dimensions.
+    pub dimensions: Dimensions,
+    /// Whether rows are stored from top to bottom; usually they are stored from bottom to top.
+    pub top_down: bool,
+    /// Number of bits per pixel.
+    pub bit_count: u16,
+    /// Compression method of the pixel data.
+    pub compression: Compression,
+    /// Bit masks of channels, present only for `Bitfields` and `AlphaBitfields` compression.
+    pub masks: Option<ChannelMasks>,
+    /// Number of entries in the color table.
+    pub palette_size: u32,
+    /// Horizontal and vertical resolution in pixels per meter, if it is specified.
+    pub pixels_per_meter: Option<(u32, u32)>,
+    /// Color space of the image, present only in V4 and V5 headers.
+    pub color_space: Option<ColorSpace>,
+}
+
+impl DibHeader {
+    /// Computes horizontal and vertical resolution in dots per inch, if it is specified.
+    #[inline]
+    pub fn dpi(&self) -> Option<(f64, f64)> {
+        self.pixels_per_meter
+            .map(|(x, y)| (x as f64 * 0.0254, y as f64 * 0.0254))
+    }
+
+    /// Reads a DIB header, including channel masks following `BITMAPINFOHEADER`.
+    ///
+    /// Returns the header, the number of bytes read and the offset and size of the color
+    /// profile data relative to the beginning of the header.
+    pub(crate) fn load<R: ?Sized + Read>(
+        r: &mut R,
+    ) -> Result<(DibHeader, u64, Option<ProfileLocation>)> {
+        let size = try_if_eof!(r.read_u32::<LittleEndian>(), "when reading DIB header size");
+        let kind = HeaderKind::from_size(size)
+            .ok_or_else(|| invalid_format!("invalid DIB header size: {}", size))?;
+        let mut data = vec![0u8; size as usize];
+        r.read_exact(&mut data[4..])
+            .map_err(if_eof!(std, "when reading {}", kind))?;
+        let mut read = size as u64;
+
+        if kind == HeaderKind::Core {
+            let bit_count = LittleEndian::read_u16(&data[10..12]);
+            let header = DibHeader {
+                kind,
+                dimensions: (
+                    LittleEndian::read_u16(&data[4..6]) as u32,
+                    LittleEndian::read_u16(&data[6..8]) as u32,
+                )
+                    .into(),
+                top_down: false,
+                bit_count,
+                compression: Compression::Rgb,
+                masks: None,
+                palette_size: if (1..=8).contains(&bit_count) {
+                    1 << bit_count
+                } else {
+                    0
+                },
+                pixels_per_meter: None,
+                color_space: None,
+            };
+            return Ok((header, read, None));
+        }
+
+        // fields which are absent in short OS/2 headers are zero
+        data.resize(data.len().max(124), 0);
+        let compression = Compression::from_u32(LittleEndian::read_u32(&data[16..20]));
+        let mask_count = match compression {
+            Compression::Bitfields => 3,
+            Compression::AlphaBitfields => 4,
+            _ => 0,
+        };
+        // masks follow BITMAPINFOHEADER, while extended versions of it contain them
+        if size == 40 {
+            let masks = &mut data[40..40 + 4 * mask_count as usize];
+            r.read_exact(masks)
+                .map_err(if_eof!(std, "when reading channel masks"))?;
+            read += 4 * mask_count as u64;
+        }
+        let u32_at = |i: usize| LittleEndian::read_u32(&data[i..i + 4]);
+
+        let width = u32_at(4) as i32;
+        let height = u32_at(8) as i32;
+        if width < 0 {
+            return Err(invalid_format!("invalid image width: {}", width));
+        }
+        let bit_count = LittleEndian::read_u16(&data[14..16]);
+        let (x_ppm, y_ppm) = (u32_at(24), u32_at(28));
+        let colors_used = u32_at(32);
+        let masks = if mask_count > 0 {
+            Some(ChannelMasks {
+                red: u32_at(40),
+                green: u32_at(44),
+                blue: u32_at(48),
+                alpha: u32_at(52),
+            })
+        } else {
+            None
+        };
+
+        let color_space = match kind {
+            HeaderKind::V4 | HeaderKind::V5 => Some(ColorSpace::from_u32(u32_at(56))),
+            _ => None,
+        };
+        let profile = match (kind, color_space) {
+            (HeaderKind::V5, Some(ColorSpace::LinkedProfile | ColorSpace::EmbeddedProfile)) => {
+                Some((u32_at(112), u32_at(116)))
+            }
+            _ => None,
+        };
+
+        let header = DibHeader {
+            kind,
+            dimensions: (width as u32, height.unsigned_abs()).into(),
+            top_down: height < 0,
+            bit_count,
+            compression,
+            masks,
+            palette_size: match colors_used {
+                // JPEG and PNG bitmaps have zero bit count and no color table
+                0 if (1..=8).contains(&bit_count) => 1 << bit_count,
+                n => n,
+            },
+            pixels_per_meter: if x_ppm == 0 && y_ppm == 0 {
+                None
+            } else {
+                Some((x_ppm, y_ppm))
+            },
+            color_space,
+        };
+        Ok((header, read, profile))
+    }
+}
+
+/// Represents metadata of a BMP image.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Metadata {
+    /// File size from the file header, which is not always correct.
+    pub file_size: u32,
+    /// Offset of the pixel data from the beginning of the file.
+    pub pixel_data_offset: u32,
+    /// Information from the DIB header.
+    pub header: DibHeader,
+    /// Embedded ICC profile, if it is present.
+    pub icc_profile: Option<Vec<u8>>,
+    /// File name of the linked ICC profile, if it is specified.
+    pub linked_profile: Option<String>,
+}
+
+impl Metadata {
+    /// Returns image dimensions.
+    #[inline]
+    pub fn dimensions(&self) -> Dimensions {
+        self.header.dimensions
+    }
+}
+
+impl LoadableMetadata for Metadata {
+    fn load<R: ?Sized + BufRead>(r: &mut R) -> Result<Metadata> {
+        let mut file_header = [0u8; 14];
+        r.read_exact(&mut file_header)
+            .map_err(if_eof!(std, "when reading BMP file header"))?;
+        if &file_header[..2] != b"BM" {
+            return Err(invalid_format!(
+                "invalid BMP signature: {:?}",
+                &file_header[..2]
+            ));
+        }
+        let file_size = LittleEndian::read_u32(&file_header[2..6]);
+        let pixel_data_offset = LittleEndian::read_u32(&file_header[10..14]);
+
+        let (header, read, profile) = DibHeader::load(r)?;
+        let position = file_header.len() as u64 + read;
+
+        let mut icc_profile = None;
+        let mut linked_profile = None;
+        if let Some((offset, size)) = profile {
+            // the profile is usually stored after the pixel data
+            let offset = file_header.len() as u64 + offset as u64;
+            if offset >= position && size <= MAX_PROFILE_SIZE {
+                let to_skip = offset - position;
+                if r.skip_exact(to_skip)? != to_skip {
+                    return Err(unexpected_eof!("when skipping to color profile"));
+                }
+                let mut data = vec![0u8; size as usize];
+                r.read_exact(&mut data)
+                    .map_err(if_eof!(std, "when reading color profile"))?;
+
+                if header.color_space == Some(ColorSpace::EmbeddedProfile) {
+                    icc_profile = Some(data);
+                } else {
+                    // the file name is a null-terminated Windows-1252 string
+                    let end = data.iter().position(|&b| b == 0).unwrap_or(data.len());
+                    linked_profile = Some(data[..end].iter().map(|&b| b as char).collect());
+                }
+            }
+        }
+
+        Ok(Metadata {
+            file_size,
+            pixel_data_offset,
+            header,
+            icc_profile,
+            linked_profile,
+        })
+    }
+}
@@ -1,11 +1,12 @@
 //! Metadata of GIF images.
 
 use std::borrow::Cow;
-use std::io::BufRead;
+use std::io::{BufRead, Read};
 use std::str;
 
 use byteorder::{LittleEndian, ReadBytesExt};
 
+use crate::common::xmp;
 use crate::traits::LoadableMetadata;
 use crate::types::{Dimensions, Result};
 use crate::utils::BufReadExt;
@@ -58,6 +59,24 @@ where
     }
 }
 
+// reads data sub-blocks as is, including their length bytes
+fn read_blocks<R: ?Sized + BufRead, F>(r: &mut R, on_eof: F) -> Result<Vec<u8>>
+where
+    F: Fn() -> Cow<'static, str>,
+{
+    let mut data = Vec::new();
+    loop {
+        let n = try_if_eof!(r.read_u8(), on_eof());
+        data.push(n);
+        if n == 0 {
+            return Ok(data);
+        }
+        if r.take(n as u64).read_to_end(&mut data)? != n as usize {
+            return Err(unexpected_eof!(on_eof()));
+        }
+    }
+}
+
 /// Contains information about a color table (global or local).
 #[derive(Clone, PartialEq, Eq, Debug)]
 pub struct ColorTable {
@@ -66,11 +85,55 @@ pub struct ColorTable {
     /// Whether the color table is sorted. Quoting from GIF spec:
     ///
     /// > If the flag is set, the [..] Color Table is sorted, in order of
-    /// decreasing importance. Typically, the order would be decreasing frequency, with most
-    /// frequent color first. This assists a decoder, with fewer available colors, in choosing
-    /// the best subset of colors; the decoder may use an initial segment of the
-    /// table to render the graphic.
+    /// > decreasing importance. Typically, the order would be decreasing frequency, with most
+    /// > frequent color first. This assists a decoder, with fewer available colors, in choosing
+    /// > the best subset of colors; the decoder may use an initial segment of the
+    /// > table to render the graphic.
     pub sorted: bool,
+    /// Colors of the table as red, green and blue triples.
+    ///
+    /// This field is only set if it was requested with `LoadOptions::retain_color_tables`.
+    pub entries: Option<Vec<[u8; 3]>>,
+}
+
+impl ColorTable {
+    fn load<R: ?Sized + BufRead, F>(
+        r: &mut R,
+        size: u16,
+        sorted: bool,
+        retain: bool,
+        on_eof: F,
+    ) -> Result<ColorTable>
+    where
+        F: Fn() -> Cow<'static, str>,
+    {
+        let table_size = size as u64 * 3;
+        let entries = if retain {
+            let mut data = Vec::new();
+            if r.take(table_size).read_to_end(&mut data)? as u64 != table_size {
+                return Err(unexpected_eof!(on_eof()));
+            }
+            Some(data.chunks_exact(3).map(|c| [c[0], c[1], c[2]]).collect())
+        } else {
+            if r.skip_exact(table_size)? != table_size {
+                return Err(unexpected_eof!(on_eof()));
+            }
+            None
+        };
+        Ok(ColorTable {
+            size,
+            sorted,
+            entries,
+        })
+    }
+}
+
+/// Options which can be used to configure how GIF metadata is loaded.
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
+pub struct LoadOptions {
+    /// Whether to read entries of global and local color tables into `ColorTable::entries`;
+    /// otherwise only their sizes are recorded.
+    pub retain_color_tables: bool,
 }
 
 /// Contains metadata about an image block, i.e. a single frame of a GIF image.
@@ -93,7 +156,11 @@ pub struct ImageDescriptor {
 }
 
 impl ImageDescriptor {
-    fn load<R: ?Sized + BufRead>(index: usize, r: &mut R) -> Result<ImageDescriptor> {
+    fn load<R: ?Sized + BufRead>(
+        index: usize,
+        r: &mut R,
+        options: &LoadOptions,
+    ) -> Result<ImageDescriptor> {
         let left = try_if_eof!(
             r.read_u16::<LittleEndian>(),
             "when reading left offset of image block {}",
@@ -121,22 +188,18 @@ impl ImageDescriptor {
         let local_color_table_sorted = (0b00100000 & packed_flags) > 0;
         let local_color_table_size_p = 0b00000111 & packed_flags;
 
-        let local_color_table_size = if local_color_table {
-            1u16 << (local_color_table_size_p + 1)
+        let local_color_table = if local_color_table {
+            Some(ColorTable::load(
+                r,
+                1u16 << (local_color_table_size_p + 1),
+                local_color_table_sorted,
+                options.retain_color_tables,
+                || format!("when reading color table of image block {index}").into(),
+            )?)
         } else {
-            0
+            None
         };
 
-        if local_color_table {
-            let skip_size = local_color_table_size as u64 * 3;
-            if r.skip_exact(skip_size)? != skip_size {
-                return Err(unexpected_eof!(
-                    "when reading color table of image block {}",
-                    index
-                ));
-            }
-        }
-
         let _ = try_if_eof!(
             r.read_u8(),
             "when reading LZW minimum code size of image block {}",
@@ -152,14 +215,7 @@ impl ImageDescriptor {
             width,
             height,
 
-            local_color_table: if local_color_table {
-                Some(ColorTable {
-                    size: local_color_table_size,
-                    sorted: local_color_table_sorted,
-                })
-            } else {
-                None
-            },
+            local_color_table,
 
             interlace,
         })
@@ -416,8 +472,8 @@ pub struct ApplicationExtension {
     /// Citing the GIF spec:
     ///
     /// > Sequence of three bytes used to authenticate the Application Identifier.
-    /// An Application program may use an algorithm to compute a binary code that uniquely
-    /// identifies it as the application owning the Application Extension.
+    /// > An Application program may use an algorithm to compute a binary code that uniquely
+    /// > identifies it as the application owning the Application Extension.
     pub authentication_code: [u8; 3],
 }
 
@@ -434,7 +490,17 @@ impl ApplicationExtension {
         str::from_utf8(&self.authentication_code).ok()
     }
 
-    fn load<R: ?Sized + BufRead>(index: usize, r: &mut R) -> Result<ApplicationExtension> {
+    /// Returns `true` if this block contains an XMP packet.
+    #[inline]
+    pub fn is_xmp(&self) -> bool {
+        &self.application_identifier == b"XMP Data" && &self.authentication_code == b"XMP"
+    }
+
+    // returns the XMP packet as well if this block contains it
+    fn load<R: ?Sized + BufRead>(
+        index: usize,
+        r: &mut R,
+    ) -> Result<(ApplicationExtension, Option<Vec<u8>>)> {
         const NAME: &str = "application extension block";
 
         let block_size = try_if_eof!(r.read_u8(), "when reading block size of {} {}", NAME, index);
@@ -463,17 +529,28 @@ impl ApplicationExtension {
             index
         ))?;
 
-        skip_blocks(r, || {
-            format!("when reading application data of {NAME} {index}").into()
-        })?;
-
-        Ok(ApplicationExtension {
+        let block = ApplicationExtension {
             application_identifier,
             authentication_code,
-        })
+        };
+        let on_eof = || format!("when reading application data of {NAME} {index}").into();
+
+        // XMP packet is stored as is, with sub-block length bytes being a part of the packet;
+        // it is followed by a "magic trailer" which makes these bytes look like valid sub-blocks
+        if block.is_xmp() {
+            let mut data = read_blocks(r, on_eof)?;
+            data.truncate(data.len().saturating_sub(XMP_MAGIC_TRAILER_LEN));
+            Ok((block, Some(data)))
+        } else {
+            skip_blocks(r, on_eof)?;
+            Ok((block, None))
+        }
     }
 }
 
+// 0x01, 0xff, 0xfe, ..., 0x01, 0x00, followed by the block terminator
+const XMP_MAGIC_TRAILER_LEN: usize = 258;
+
 /// Represents a comment extension block.
 ///
 /// Comment block does not contain any metadata, so this struct is used for uniformity
@@ -509,12 +586,12 @@ pub struct Metadata {
     /// Quoting the GIF spec:
     ///
     /// > Number of bits per primary color available to the original image, minus 1.
-    /// This value represents the size of the entire palette from which the colors in the
-    /// graphic were selected, not the number of colors actually used in the graphic.
-    /// For example, if the value in this field is 3, then the palette of the original image
-    /// had 4 bits per primary color available to create the image. This value should be set
-    /// to indicate the richness of the original palette, even if not every color from the whole
-    /// palette is available on the source machine.
+    /// > This value represents the size of the entire palette from which the colors in the
+    /// > graphic were selected, not the number of colors actually used in the graphic.
+    /// > For example, if the value in this field is 3, then the palette of the original image
+    /// > had 4 bits per primary color available to create the image. This value should be set
+    /// > to indicate the richness of the original palette, even if not every color from the whole
+    /// > palette is available on the source machine.
     ///
     /// Note that the value in this structure is the number of *colors*, not the number of *bits*.
     pub color_resolution: u16,
@@ -525,14 +602,14 @@ pub struct Metadata {
     /// Quoting from the GIF spec:
     ///
     /// > Factor used to compute an approximation of the aspect ratio of the pixel in the original
-    /// image. If the value of the field is not 0, this approximation of the aspect ratio is
-    /// computed based on the formula:
+    /// > image. If the value of the field is not 0, this approximation of the aspect ratio is
+    /// > computed based on the formula:
     /// >
     /// >    Aspect Ratio = (Pixel Aspect Ratio + 15) / 64
     /// >
     /// > The Pixel Aspect Ratio is defined to be the quotient of the pixel's width over its
-    /// height. The value range in this field allows specification of the widest pixel of 4:1 to
-    /// the tallest pixel of 1:4 in increments of 1/64th.
+    /// > height. The value range in this field allows specification of the widest pixel of 4:1 to
+    /// > the tallest pixel of 1:4 in increments of 1/64th.
     ///
     /// If zero, no information about pixel aspect ratio is available.
     ///
@@ -541,6 +618,10 @@ pub struct Metadata {
 
     /// Metadata for each block in the GIF image.
     pub blocks: Vec<Block>,
+
+    /// XMP packet from the first XMP application extension block, if it is present and
+    /// is a valid UTF-8 string.
+    pub xmp: Option<String>,
 }
 
 impl Metadata {
@@ -578,6 +659,16 @@ impl Metadata {
 
 impl LoadableMetadata for Metadata {
     fn load<R: ?Sized + BufRead>(r: &mut R) -> Result<Metadata> {
+        Metadata::load_with_options(r, &LoadOptions::default())
+    }
+}
+
+impl Metadata {
+    /// Loads metadata like `LoadableMetadata::load()`, using the given options.
+    pub fn load_with_options<R: ?Sized + BufRead>(
+        r: &mut R,
+        options: &LoadOptions,
+    ) -> Result<Metadata> {
         let mut signature = [0u8; 6];
         r.read_exact(&mut signature)
             .map_err(if_eof!(std, "when reading GIF signature"))?;
@@ -597,28 +688,29 @@ impl LoadableMetadata for Metadata {
         let global_color_table_sorted = (packed_flags & 0b00001000) > 0;
         let global_color_table_size_p = packed_flags & 0b00000111;
 
-        let global_color_table_size = if global_color_table {
-            1u16 << (global_color_table_size_p + 1)
-        } else {
-            0
-        };
         let background_color_index =
             try_if_eof!(r.read_u8(), "when reading background color index");
         let pixel_aspect_ratio = try_if_eof!(r.read_u8(), "when reading pixel aspect ration");
 
-        if global_color_table {
-            let skip_size = global_color_table_size as u64 * 3;
-            if r.skip_exact(skip_size)? != skip_size {
-                return Err(unexpected_eof!("when reading global color table"));
-            }
-        }
+        let global_color_table = if global_color_table {
+            Some(ColorTable::load(
+                r,
+                1u16 << (global_color_table_size_p + 1),
+                global_color_table_sorted,
+                options.retain_color_tables,
+                || "when reading global color table".into(),
+            )?)
+        } else {
+            None
+        };
 
         let mut blocks = Vec::new();
+        let mut xmp = None;
         let mut index = 0usize;
         loop {
             let separator = try_if_eof!(r.read_u8(), "when reading separator of block {}", index);
             let block = match separator {
-                0x2c => Block::ImageDescriptor(ImageDescriptor::load(index, r)?),
+                0x2c => Block::ImageDescriptor(ImageDescriptor::load(index, r, options)?),
                 0x21 => {
                     let label = try_if_eof!(r.read_u8(), "when reading label of block {}", index);
                     match label {
@@ -627,7 +719,13 @@ impl LoadableMetadata for Metadata {
                             Block::GraphicControlExtension(GraphicControlExtension::load(index, r)?)
                         }
                         0xfe => Block::CommentExtension(CommentExtension::load(index, r)?),
-                        0xff => Block::ApplicationExtension(ApplicationExtension::load(index, r)?),
+                        0xff => {
+                            let (block, data) = ApplicationExtension::load(index, r)?;
+                            if let (None, Some(data)) = (&xmp, data) {
+                                xmp = xmp::decode(&data);
+                            }
+                            Block::ApplicationExtension(block)
+                        }
                         _ => {
                             return Err(invalid_format!(
                                 "unknown extension type of block {}: 0x{:X}",
@@ -655,14 +753,7 @@ impl LoadableMetadata for Metadata {
 
             dimensions: (width, height).into(),
 
-            global_color_table: if global_color_table {
-                Some(ColorTable {
-                    size: global_color_table_size,
-                    sorted: global_color_table_sorted,
-                })
-            } else {
-                None
-            },
+            global_color_table,
 
             color_resolution: 1u16 << (color_resolution + 1),
 
@@ -670,6 +761,7 @@ impl LoadableMetadata for Metadata {
             pixel_aspect_ratio,
 
             blocks,
+            xmp,
         })
     }
 }
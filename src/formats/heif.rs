@@ -0,0 +1,809 @@
+//! Metadata of HEIF images, including HEIC images produced by phone cameras.
+//!
+//! HEIF images are ISO base media files. Their metadata is stored in the `meta` box, which
+//! describes items (images, EXIF and XMP metadata) and properties associated with them.
+
+use std::fmt;
+use std::io::{self, BufRead, Read, Seek, SeekFrom};
+
+use byteorder::{BigEndian, ReadBytesExt};
+
+use crate::common::exif::{Exif, Gps};
+use crate::common::isobmff::{self, BoxHeader, Boxes, FourCc};
+use crate::common::xmp;
+use crate::traits::LoadableMetadata;
+use crate::types::{Dimensions, Orientation, Result};
+use crate::utils::BufReadExt;
+
+/// Brands which identify HEIF images.
+pub const BRANDS: &[&[u8; 4]] = &[
+    b"heic", b"heix", b"heim", b"heis", b"hevc", b"hevx", b"mif1", b"msf1",
+];
+
+// meta and moov boxes contain only descriptions of the data, so larger ones are most
+// likely malicious
+const MAX_BOX_SIZE: u64 = 16 << 20;
+
+// EXIF and XMP items are small as well
+const MAX_ITEM_SIZE: u64 = 16 << 20;
+
+const XMP_CONTENT_TYPE: &str = "application/rdf+xml";
+
+/// Contains information about an item of the image.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Item {
+    /// Item identifier.
+    pub id: u32,
+    /// Item type, e.g. `hvc1` for HEVC-coded images, `grid` for image grids or `Exif`.
+    pub item_type: FourCc,
+    /// Item name, usually empty.
+    pub name: String,
+    /// MIME type of the item contents for items of type `mime`, e.g. `application/rdf+xml`
+    /// for XMP packets.
+    pub content_type: Option<String>,
+    /// Whether the item is not intended to be displayed, e.g. tiles of a grid.
+    pub hidden: bool,
+}
+
+/// Axis used to mirror an image.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum MirrorAxis {
+    /// The image is mirrored about the vertical axis, i.e. left and right are swapped.
+    Vertical,
+    /// The image is mirrored about the horizontal axis, i.e. top and bottom are swapped.
+    Horizontal,
+}
+
+impl fmt::Display for MirrorAxis {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match *self {
+            MirrorAxis::Vertical => "Vertical",
+            MirrorAxis::Horizontal => "Horizontal",
+        })
+    }
+}
+
+/// A transformation which has to be applied to the image to display it.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Transformation {
+    /// Rotation by the given angle anti-clockwise, in degrees (`irot` property).
+    Rotation(u16),
+    /// Mirroring about the given axis (`imir` property).
+    Mirror(MirrorAxis),
+}
+
+/// Color information from a `colr` property with `nclx` color type.
+///
+/// The values are defined in ITU-T H.273.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct Nclx {
+    /// Color primaries, e.g. 1 for BT.709 or 12 for Display P3.
+    pub color_primaries: u16,
+    /// Transfer characteristics, e.g. 13 for sRGB, 16 for PQ or 18 for HLG.
+    pub transfer_characteristics: u16,
+    /// Matrix coefficients, e.g. 6 for BT.601.
+    pub matrix_coefficients: u16,
+    /// Whether the image uses full range of sample values, as opposed to narrow range.
+    pub full_range: bool,
+}
+
+/// Represents metadata of a HEIF image.
+///
+/// Information about the image comes from properties of its primary item.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Metadata {
+    /// Major brand from the `ftyp` box, e.g. `heic`.
+    pub major_brand: FourCc,
+    /// Minor version of the major brand.
+    pub minor_version: u32,
+    /// Brands the file is compatible with, e.g. `mif1` and `heic`.
+    pub compatible_brands: Vec<FourCc>,
+    /// Handler type of the `meta` box, `pict` for images.
+    pub handler_type: FourCc,
+    /// Identifier of the primary item.
+    pub primary_item_id: u32,
+    /// All items of the file, including hidden ones.
+    pub items: Vec<Item>,
+    /// Size of the primary image, before transformations are applied.
+    pub dimensions: Dimensions,
+    /// Transformations of the primary image, in the order they are applied.
+    pub transformations: Vec<Transformation>,
+    /// Number of bits in each channel of the primary image (`pixi` property).
+    pub bits_per_channel: Option<Vec<u8>>,
+    /// Color information of the primary image from a `colr` property, if it is present.
+    pub nclx: Option<Nclx>,
+    /// ICC profile of the primary image from a `colr` property, if it is present.
+    pub icc_profile: Option<Vec<u8>>,
+    /// EXIF metadata from the first `Exif` item, if it is present, can be read and is valid.
+    pub exif: Option<Exif>,
+    /// XMP packet from the first XMP item, if it is present, can be read and is a valid
+    /// UTF-8 string.
+    pub xmp: Option<String>,
+}
+
+impl Metadata {
+    /// Returns the primary item.
+    pub fn primary_item(&self) -> Option<&Item> {
+        self.items.iter().find(|i| i.id == self.primary_item_id)
+    }
+
+    /// Returns `true` if the primary image is a grid of several tiles, which is the case
+    /// for most photos taken by phone cameras.
+    pub fn has_grid(&self) -> bool {
+        self.primary_item()
+            .is_some_and(|i| i.item_type.as_bytes() == b"grid")
+    }
+
+    /// Returns the orientation of the primary image derived from its transformations.
+    ///
+    /// Unlike in other formats, transformations of HEIF images are mandatory, so the EXIF
+    /// orientation tag should be ignored.
+    pub fn orientation(&self) -> Orientation {
+        orientation(&self.transformations)
+    }
+
+    /// Returns GPS information from EXIF metadata, if it is present.
+    #[inline]
+    pub fn gps(&self) -> Option<Gps> {
+        self.exif.as_ref().and_then(Exif::gps)
+    }
+}
+
+// combines the given transformations into the equivalent orientation
+pub(crate) fn orientation(transformations: &[Transformation]) -> Orientation {
+    // the orientation is represented as an optional horizontal flip followed by
+    // a number of clockwise quarter turns
+    let (flip, turns) = transformations
+        .iter()
+        .fold((false, 0u16), |(flip, turns), t| match *t {
+            Transformation::Rotation(angle) => (flip, (turns + 4 - angle / 90 % 4) % 4),
+            Transformation::Mirror(MirrorAxis::Vertical) => (!flip, (4 - turns) % 4),
+            Transformation::Mirror(MirrorAxis::Horizontal) => (!flip, (6 - turns) % 4),
+        });
+    match (flip, turns) {
+        (false, 0) => Orientation::Normal,
+        (false, 1) => Orientation::Rotate90,
+        (false, 2) => Orientation::Rotate180,
+        (false, _) => Orientation::Rotate270,
+        (true, 0) => Orientation::FlipHorizontal,
+        (true, 1) => Orientation::Transverse,
+        (true, 2) => Orientation::FlipVertical,
+        (true, _) => Orientation::Transpose,
+    }
+}
+
+// location of item data
+#[derive(Clone, Debug)]
+struct ItemLocation {
+    item_id: u32,
+    construction_method: u16,
+    extents: Vec<(u64, u64)>,
+}
+
+// properties of items from the iprp box
+#[derive(Default)]
+struct Properties {
+    boxes: Vec<(FourCc, Vec<u8>)>,
+    // item id and indices of its properties, starting from 1
+    associations: Vec<(u32, Vec<u16>)>,
+}
+
+impl Properties {
+    fn parse(data: &[u8]) -> Result<Properties> {
+        let mut properties = Properties::default();
+        for b in Boxes::new(data) {
+            let (box_type, data) = b?;
+            match box_type.as_bytes() {
+                b"ipco" => {
+                    for b in Boxes::new(data) {
+                        let (box_type, data) = b?;
+                        properties.boxes.push((box_type, data.to_vec()));
+                    }
+                }
+                b"ipma" => properties.associations.extend(parse_ipma(data)?),
+                _ => {}
+            }
+        }
+        Ok(properties)
+    }
+}
+
+fn parse_ipma(data: &[u8]) -> Result<Vec<(u32, Vec<u16>)>> {
+    let (version, flags, mut r) = isobmff::full_box(data)?;
+    let count = try_if_eof!(r.read_u32::<BigEndian>(), "when reading ipma entry count");
+
+    let mut entries = Vec::new();
+    for _ in 0..count {
+        let item_id = read_item_id(&mut r, version >= 1, "ipma")?;
+        let n = try_if_eof!(r.read_u8(), "when reading ipma association count");
+        let mut indices = Vec::with_capacity(n as usize);
+        for _ in 0..n {
+            // the highest bit is the essential flag
+            let index = if flags & 1 != 0 {
+                try_if_eof!(r.read_u16::<BigEndian>(), "when reading ipma association") & 0x7fff
+            } else {
+                (try_if_eof!(r.read_u8(), "when reading ipma association") & 0x7f) as u16
+            };
+            indices.push(index);
+        }
+        entries.push((item_id, indices));
+    }
+    Ok(entries)
+}
+
+fn read_item_id(r: &mut &[u8], long: bool, box_name: &str) -> Result<u32> {
+    Ok(if long {
+        try_if_eof!(
+            r.read_u32::<BigEndian>(),
+            "when reading {} item ID",
+            box_name
+        )
+    } else {
+        try_if_eof!(
+            r.read_u16::<BigEndian>(),
+            "when reading {} item ID",
+            box_name
+        ) as u32
+    })
+}
+
+// reads an integer whose size is specified in the iloc box
+fn read_sized(r: &mut &[u8], size: u8) -> Result<u64> {
+    match size {
+        0 => Ok(0),
+        4 => Ok(try_if_eof!(r.read_u32::<BigEndian>(), "when reading iloc entry") as u64),
+        8 => Ok(try_if_eof!(
+            r.read_u64::<BigEndian>(),
+            "when reading iloc entry"
+        )),
+        _ => Err(invalid_format!("invalid iloc field size: {}", size)),
+    }
+}
+
+fn parse_iloc(data: &[u8]) -> Result<Vec<ItemLocation>> {
+    let (version, _, mut r) = isobmff::full_box(data)?;
+    let sizes = try_if_eof!(r.read_u16::<BigEndian>(), "when reading iloc field sizes");
+    let (offset_size, length_size) = ((sizes >> 12) as u8, (sizes >> 8 & 0xf) as u8);
+    let base_offset_size = (sizes >> 4 & 0xf) as u8;
+    let index_size = if version >= 1 { (sizes & 0xf) as u8 } else { 0 };
+
+    let count = read_item_id(&mut r, version >= 2, "iloc")?;
+    let mut locations = Vec::new();
+    for _ in 0..count {
+        let item_id = read_item_id(&mut r, version >= 2, "iloc")?;
+        let construction_method = if version >= 1 {
+            try_if_eof!(
+                r.read_u16::<BigEndian>(),
+                "when reading iloc construction method"
+            ) & 0xf
+        } else {
+            0
+        };
+        let _data_reference_index = try_if_eof!(
+            r.read_u16::<BigEndian>(),
+            "when reading iloc data reference index"
+        );
+        let base_offset = read_sized(&mut r, base_offset_size)?;
+        let extent_count = try_if_eof!(r.read_u16::<BigEndian>(), "when reading iloc extent count");
+        let mut extents = Vec::new();
+        for _ in 0..extent_count {
+            read_sized(&mut r, index_size)?;
+            let offset = read_sized(&mut r, offset_size)?;
+            let length = read_sized(&mut r, length_size)?;
+            extents.push((base_offset.saturating_add(offset), length));
+        }
+        locations.push(ItemLocation {
+            item_id,
+            construction_method,
+            extents,
+        });
+    }
+    Ok(locations)
+}
+
+fn read_string(r: &mut &[u8]) -> String {
+    let end = r.iter().position(|&b| b == 0).unwrap_or(r.len());
+    let s = String::from_utf8_lossy(&r[..end]).into_owned();
+    *r = r.get(end + 1..).unwrap_or(&[]);
+    s
+}
+
+fn parse_infe(data: &[u8]) -> Result<Option<Item>> {
+    let (version, flags, mut r) = isobmff::full_box(data)?;
+    // item types are only defined in versions 2 and 3
+    if version < 2 {
+        return Ok(None);
+    }
+    let id = read_item_id(&mut r, version >= 3, "infe")?;
+    let _protection_index = try_if_eof!(
+        r.read_u16::<BigEndian>(),
+        "when reading infe protection index"
+    );
+    if r.len() < 4 {
+        return Err(unexpected_eof!("when reading infe item type"));
+    }
+    let item_type = FourCc([r[0], r[1], r[2], r[3]]);
+    r = &r[4..];
+    let name = read_string(&mut r);
+    let content_type = if item_type.as_bytes() == b"mime" {
+        Some(read_string(&mut r))
+    } else {
+        None
+    };
+
+    Ok(Some(Item {
+        id,
+        item_type,
+        name,
+        content_type,
+        hidden: flags & 1 != 0,
+    }))
+}
+
+fn parse_iinf(data: &[u8]) -> Result<Vec<Item>> {
+    let (version, _, mut r) = isobmff::full_box(data)?;
+    read_item_id(&mut r, version >= 1, "iinf")?;
+
+    let mut items = Vec::new();
+    for b in Boxes::new(r) {
+        let (box_type, data) = b?;
+        if box_type.as_bytes() == b"infe" {
+            items.extend(parse_infe(data)?);
+        }
+    }
+    Ok(items)
+}
+
+fn parse_iref(data: &[u8]) -> Result<Vec<Reference>> {
+    let (version, _, data) = isobmff::full_box(data)?;
+
+    let mut references = Vec::new();
+    for b in Boxes::new(data) {
+        let (reference_type, mut r) = b?;
+        let from_item_id = read_item_id(&mut r, version >= 1, "iref")?;
+        let count = try_if_eof!(
+            r.read_u16::<BigEndian>(),
+            "when reading iref reference count"
+        );
+        let to_item_ids = (0..count)
+            .map(|_| read_item_id(&mut r, version >= 1, "iref"))
+            .collect::<Result<_>>()?;
+        references.push(Reference {
+            reference_type,
+            from_item_id,
+            to_item_ids,
+        });
+    }
+    Ok(references)
+}
+
+// a typed reference from one item to others, e.g. `dimg` from a grid to its tiles
+pub(crate) struct Reference {
+    pub(crate) reference_type: FourCc,
+    pub(crate) from_item_id: u32,
+    pub(crate) to_item_ids: Vec<u32>,
+}
+
+// contents of the meta box
+pub(crate) struct Meta {
+    pub(crate) handler_type: FourCc,
+    pub(crate) primary_item_id: u32,
+    pub(crate) items: Vec<Item>,
+    pub(crate) references: Vec<Reference>,
+    locations: Vec<ItemLocation>,
+    properties: Properties,
+    idat: Vec<u8>,
+}
+
+impl Meta {
+    fn parse(data: &[u8]) -> Result<Meta> {
+        let (_, _, data) = isobmff::full_box(data)?;
+
+        let mut handler_type = None;
+        let mut primary_item_id = None;
+        let mut items = Vec::new();
+        let mut references = Vec::new();
+        let mut locations = Vec::new();
+        let mut properties = Properties::default();
+        let mut idat = Vec::new();
+        for b in Boxes::new(data) {
+            let (box_type, data) = b?;
+            match box_type.as_bytes() {
+                b"hdlr" => {
+                    let (_, _, data) = isobmff::full_box(data)?;
+                    let handler = data
+                        .get(4..8)
+                        .ok_or_else(|| unexpected_eof!("when reading handler type"))?;
+                    handler_type = Some(FourCc([handler[0], handler[1], handler[2], handler[3]]));
+                }
+                b"pitm" => {
+                    let (version, _, mut r) = isobmff::full_box(data)?;
+                    primary_item_id = Some(read_item_id(&mut r, version >= 1, "pitm")?);
+                }
+                b"iinf" => items = parse_iinf(data)?,
+                b"iref" => references = parse_iref(data)?,
+                b"iloc" => locations = parse_iloc(data)?,
+                b"iprp" => properties = Properties::parse(data)?,
+                b"idat" => idat = data.to_vec(),
+                _ => {}
+            }
+        }
+
+        Ok(Meta {
+            handler_type: handler_type.ok_or_else(|| invalid_format!("no hdlr box in meta box"))?,
+            primary_item_id: primary_item_id
+                .ok_or_else(|| invalid_format!("no primary item in meta box"))?,
+            items,
+            references,
+            locations,
+            properties,
+            idat,
+        })
+    }
+
+    fn location(&self, item_id: u32) -> Option<&ItemLocation> {
+        self.locations.iter().find(|l| l.item_id == item_id)
+    }
+
+    // returns properties associated with the given item, in the order of association
+    pub(crate) fn properties_of(&self, item_id: u32) -> impl Iterator<Item = (FourCc, &[u8])> {
+        self.properties
+            .associations
+            .iter()
+            .filter(move |(id, _)| *id == item_id)
+            .flat_map(|(_, indices)| indices)
+            .filter_map(|&i| self.properties.boxes.get((i as usize).checked_sub(1)?))
+            .map(|(box_type, data)| (*box_type, &data[..]))
+    }
+}
+
+fn parse_ftyp(data: &[u8]) -> Result<(FourCc, u32, Vec<FourCc>)> {
+    if data.len() < 8 {
+        return Err(unexpected_eof!("when reading ftyp box"));
+    }
+    let major_brand = FourCc([data[0], data[1], data[2], data[3]]);
+    let minor_version = u32::from_be_bytes([data[4], data[5], data[6], data[7]]);
+    let compatible_brands = data[8..]
+        .chunks_exact(4)
+        .map(|b| FourCc([b[0], b[1], b[2], b[3]]))
+        .collect();
+    Ok((major_brand, minor_version, compatible_brands))
+}
+
+fn read_box_data<R: ?Sized + BufRead>(
+    r: &mut R,
+    header: &BoxHeader,
+    limit: u64,
+) -> Result<Vec<u8>> {
+    let size = match header.data_size {
+        Some(size) if size <= limit => size,
+        _ => return Err(invalid_format!("{} box is too large", header.box_type)),
+    };
+    let mut data = Vec::with_capacity(size as usize);
+    if r.take(size).read_to_end(&mut data)? as u64 != size {
+        return Err(unexpected_eof!("when reading {} box", header.box_type));
+    }
+    Ok(data)
+}
+
+// reads data of the given items which is located after the current position in the file;
+// items stored before it or in an unsupported way are omitted from the result
+fn read_items<R: ?Sized + BufRead>(
+    r: &mut R,
+    skip: &mut impl FnMut(&mut R, u64) -> io::Result<u64>,
+    mut position: u64,
+    meta: &Meta,
+    item_ids: &[u32],
+) -> Result<Vec<(u32, Vec<u8>)>> {
+    // item identifiers and their data, one piece per extent
+    let mut items = Vec::new();
+    // extents located in the file: (offset, length, item index, extent index)
+    let mut extents = Vec::new();
+    for &item_id in item_ids {
+        let location = match meta.location(item_id) {
+            Some(location) => location,
+            None => continue,
+        };
+        let total = location
+            .extents
+            .iter()
+            .map(|e| e.1)
+            .fold(0u64, u64::saturating_add);
+        if total > MAX_ITEM_SIZE {
+            continue;
+        }
+        match location.construction_method {
+            // offsets are relative to the beginning of the file
+            0 => {
+                for (i, &(offset, length)) in location.extents.iter().enumerate() {
+                    extents.push((offset, length, items.len(), i));
+                }
+                items.push((item_id, vec![None; location.extents.len()]));
+            }
+            // offsets are relative to the beginning of the idat box
+            1 => {
+                let pieces = location
+                    .extents
+                    .iter()
+                    .map(|&(offset, length)| {
+                        let end = offset.checked_add(length)?;
+                        meta.idat
+                            .get(offset as usize..end as usize)
+                            .map(<[u8]>::to_vec)
+                    })
+                    .collect();
+                items.push((item_id, pieces));
+            }
+            _ => {}
+        }
+    }
+
+    extents.sort_by_key(|e| e.0);
+    for (offset, length, item, index) in extents {
+        if offset < position {
+            continue;
+        }
+        let to_skip = offset - position;
+        if skip(r, to_skip)? != to_skip {
+            return Err(unexpected_eof!("when skipping to item data"));
+        }
+        let mut data = Vec::with_capacity(length as usize);
+        if r.take(length).read_to_end(&mut data)? as u64 != length {
+            return Err(unexpected_eof!("when reading item data"));
+        }
+        position = offset + length;
+        items[item].1[index] = Some(data);
+    }
+
+    Ok(items
+        .into_iter()
+        .filter_map(|(id, pieces)| {
+            let pieces: Option<Vec<_>> = pieces.into_iter().collect();
+            Some((id, pieces?.concat()))
+        })
+        .collect())
+}
+
+fn load_exif(data: &[u8]) -> Option<Exif> {
+    // the TIFF header is preceded by its offset from the end of the offset field
+    let offset = u32::from_be_bytes(data.get(..4)?.try_into().ok()?) as usize;
+    Exif::load(data.get(4 + offset..)?).ok()
+}
+
+// contents of a HEIF file common to HEIF and AVIF images
+pub(crate) struct Container {
+    pub(crate) major_brand: FourCc,
+    pub(crate) minor_version: u32,
+    pub(crate) compatible_brands: Vec<FourCc>,
+    pub(crate) meta: Meta,
+    // payload of the moov box of image sequences
+    pub(crate) movie: Option<Vec<u8>>,
+    pub(crate) exif: Option<Exif>,
+    pub(crate) xmp: Option<String>,
+}
+
+impl Container {
+    // `format` is used in error messages; the moov box is only read if the file has
+    // `movie_brand` among its brands
+    pub(crate) fn load<R: ?Sized + BufRead>(
+        r: &mut R,
+        format: &str,
+        movie_brand: Option<&[u8; 4]>,
+    ) -> Result<Container> {
+        Container::read(r, format, movie_brand, &mut |r, n| r.skip_exact(n))
+    }
+
+    // same as `load()`, but seeks over the boxes and the data which are not needed
+    pub(crate) fn load_from_seek<R: ?Sized + BufRead + Seek>(
+        r: &mut R,
+        format: &str,
+        movie_brand: Option<&[u8; 4]>,
+    ) -> Result<Container> {
+        let start = r.stream_position()?;
+        let end = r.seek(SeekFrom::End(0))?;
+        r.seek(SeekFrom::Start(start))?;
+        Container::read(r, format, movie_brand, &mut |r, n| {
+            let position = r.stream_position()?;
+            let n = n.min(end.saturating_sub(position));
+            r.seek(SeekFrom::Start(position + n))?;
+            Ok(n)
+        })
+    }
+
+    // `skip` moves the reader forward and returns the number of bytes skipped, which is
+    // less than requested only at the end of the file
+    fn read<R: ?Sized + BufRead>(
+        r: &mut R,
+        format: &str,
+        movie_brand: Option<&[u8; 4]>,
+        skip: &mut impl FnMut(&mut R, u64) -> io::Result<u64>,
+    ) -> Result<Container> {
+        let header =
+            isobmff::read_box_header(r)?.ok_or_else(|| unexpected_eof!("when reading ftyp box"))?;
+        if header.box_type.as_bytes() != b"ftyp" {
+            return Err(invalid_format!(
+                "invalid {} header: {}",
+                format,
+                header.box_type
+            ));
+        }
+        let mut position = header.header_size;
+        let ftyp = read_box_data(r, &header, 4096)?;
+        position += ftyp.len() as u64;
+        let (major_brand, minor_version, compatible_brands) = parse_ftyp(&ftyp)?;
+        let read_movie = movie_brand.is_some_and(|brand| {
+            major_brand.as_bytes() == brand
+                || compatible_brands.iter().any(|b| b.as_bytes() == brand)
+        });
+
+        // look for the meta and moov boxes, skipping other top-level boxes
+        let mut meta = None;
+        let mut movie = None;
+        while meta.is_none() || (read_movie && movie.is_none()) {
+            let header = match isobmff::read_box_header(r)? {
+                Some(header) => header,
+                None => break,
+            };
+            position += header.header_size;
+            let data = match header.box_type.as_bytes() {
+                b"meta" if meta.is_none() => &mut meta,
+                b"moov" if read_movie && movie.is_none() => &mut movie,
+                _ => {
+                    // a box without size extends to the end of the file
+                    let size = match header.data_size {
+                        Some(size) => size,
+                        None => break,
+                    };
+                    if skip(r, size)? != size {
+                        return Err(unexpected_eof!("when skipping {} box", header.box_type));
+                    }
+                    position += size;
+                    continue;
+                }
+            };
+            let box_data = read_box_data(r, &header, MAX_BOX_SIZE)?;
+            position += box_data.len() as u64;
+            *data = Some(box_data);
+        }
+        let meta = meta.ok_or_else(|| invalid_format!("no meta box in {} image", format))?;
+        let meta = Meta::parse(&meta)?;
+
+        // EXIF and XMP are stored in items, usually in the mdat box after the meta box
+        let exif_item = meta
+            .items
+            .iter()
+            .find(|i| i.item_type.as_bytes() == b"Exif");
+        let xmp_item = meta
+            .items
+            .iter()
+            .find(|i| i.content_type.as_deref() == Some(XMP_CONTENT_TYPE));
+        let item_ids: Vec<_> = exif_item.iter().chain(&xmp_item).map(|i| i.id).collect();
+        let item_data = read_items(r, skip, position, &meta, &item_ids)?;
+        let find_data = |item: Option<&Item>| {
+            let id = item?.id;
+            item_data.iter().find(|d| d.0 == id).map(|d| &*d.1)
+        };
+
+        // invalid metadata should not prevent loading the rest of the metadata
+        let exif = find_data(exif_item).and_then(load_exif);
+        let xmp = find_data(xmp_item).and_then(xmp::decode);
+
+        Ok(Container {
+            major_brand,
+            minor_version,
+            compatible_brands,
+            meta,
+            movie,
+            exif,
+            xmp,
+        })
+    }
+}
+
+// properties describing an image item, common to all coding formats
+pub(crate) struct ImageProperties {
+    pub(crate) dimensions: Option<Dimensions>,
+    pub(crate) transformations: Vec<Transformation>,
+    pub(crate) bits_per_channel: Option<Vec<u8>>,
+    pub(crate) nclx: Option<Nclx>,
+    pub(crate) icc_profile: Option<Vec<u8>>,
+}
+
+impl ImageProperties {
+    pub(crate) fn parse(meta: &Meta, item_id: u32) -> Result<ImageProperties> {
+        let mut properties = ImageProperties {
+            dimensions: None,
+            transformations: Vec::new(),
+            bits_per_channel: None,
+            nclx: None,
+            icc_profile: None,
+        };
+        for (box_type, data) in meta.properties_of(item_id) {
+            match box_type.as_bytes() {
+                b"ispe" => {
+                    let (_, _, mut r) = isobmff::full_box(data)?;
+                    let width = try_if_eof!(r.read_u32::<BigEndian>(), "when reading image width");
+                    let height =
+                        try_if_eof!(r.read_u32::<BigEndian>(), "when reading image height");
+                    properties.dimensions = Some((width, height).into());
+                }
+                b"irot" => {
+                    if let Some(&angle) = data.first() {
+                        properties
+                            .transformations
+                            .push(Transformation::Rotation((angle & 3) as u16 * 90));
+                    }
+                }
+                b"imir" => {
+                    if let Some(&axis) = data.first() {
+                        properties
+                            .transformations
+                            .push(Transformation::Mirror(if axis & 1 == 0 {
+                                MirrorAxis::Vertical
+                            } else {
+                                MirrorAxis::Horizontal
+                            }));
+                    }
+                }
+                b"pixi" => {
+                    let (_, _, data) = isobmff::full_box(data)?;
+                    if let Some((&n, bits)) = data.split_first() {
+                        properties.bits_per_channel = bits.get(..n as usize).map(<[u8]>::to_vec);
+                    }
+                }
+                b"colr" if data.len() >= 4 => match &data[..4] {
+                    b"nclx" if properties.nclx.is_none() && data.len() >= 11 => {
+                        properties.nclx = Some(Nclx {
+                            color_primaries: u16::from_be_bytes([data[4], data[5]]),
+                            transfer_characteristics: u16::from_be_bytes([data[6], data[7]]),
+                            matrix_coefficients: u16::from_be_bytes([data[8], data[9]]),
+                            full_range: data[10] & 0x80 != 0,
+                        });
+                    }
+                    b"prof" | b"rICC" if properties.icc_profile.is_none() => {
+                        properties.icc_profile = Some(data[4..].to_vec());
+                    }
+                    _ => {}
+                },
+                _ => {}
+            }
+        }
+        Ok(properties)
+    }
+}
+
+impl Metadata {
+    fn from_container(container: Container) -> Result<Metadata> {
+        let meta = container.meta;
+        let properties = ImageProperties::parse(&meta, meta.primary_item_id)?;
+        let dimensions = properties
+            .dimensions
+            .ok_or_else(|| invalid_format!("primary item of HEIF image has no size"))?;
+
+        Ok(Metadata {
+            major_brand: container.major_brand,
+            minor_version: container.minor_version,
+            compatible_brands: container.compatible_brands,
+            handler_type: meta.handler_type,
+            primary_item_id: meta.primary_item_id,
+            items: meta.items,
+            dimensions,
+            transformations: properties.transformations,
+            bits_per_channel: properties.bits_per_channel,
+            nclx: properties.nclx,
+            icc_profile: properties.icc_profile,
+            exif: container.exif,
+            xmp: container.xmp,
+        })
+    }
+}
+
+impl LoadableMetadata for Metadata {
+    fn load<R: ?Sized + BufRead>(r: &mut R) -> Result<Metadata> {
+        Metadata::from_container(Container::load(r, "HEIF", None)?)
+    }
+
+    fn load_from_seek<R: ?Sized + BufRead + Seek>(r: &mut R) -> Result<Metadata> {
+        Metadata::from_container(Container::load_from_seek(r, "HEIF", None)?)
+    }
+}
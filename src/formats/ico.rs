@@ -0,0 +1,244 @@
+//! Metadata of ICO and CUR files.
+//!
+//! Icons and cursors contain several images of different sizes and color depths. Each image
+//! is either a DIB without the BMP file header or a PNG image. Sizes and color depths stated
+//! in the directory are often inaccurate, so the images themselves are inspected as well.
+
+use std::fmt;
+use std::io::{BufRead, Read};
+
+use byteorder::{ByteOrder, LittleEndian};
+#[cfg(feature = "async")]
+use futures_io::AsyncBufRead;
+
+#[cfg(feature = "async")]
+use crate::asynchronous;
+use crate::formats::bmp::DibHeader;
+use crate::formats::png;
+use crate::traits::LoadableMetadata;
+use crate::types::{Dimensions, Result};
+use crate::utils::{BufReadExt, ReadExt};
+
+const PNG_SIGNATURE: &[u8] = b"\x89PNG\r\n\x1a\n";
+
+/// Kind of the file.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Kind {
+    /// An icon, usually with `.ico` extension.
+    Icon,
+    /// A cursor, usually with `.cur` extension.
+    Cursor,
+}
+
+impl fmt::Display for Kind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match *self {
+            Kind::Icon => "Icon",
+            Kind::Cursor => "Cursor",
+        })
+    }
+}
+
+/// An image contained in an entry.
+#[allow(clippy::large_enum_variant)]
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum Image {
+    /// A DIB image, whose header describes both the image and its transparency mask.
+    Bmp(DibHeader),
+    /// A PNG image.
+    Png(png::Metadata),
+}
+
+impl Image {
+    /// Returns dimensions of the image.
+    ///
+    /// The height of DIB images is halved because their headers also account for
+    /// the transparency mask following the image.
+    pub fn dimensions(&self) -> Dimensions {
+        match *self {
+            Image::Bmp(ref header) => Dimensions {
+                width: header.dimensions.width,
+                height: header.dimensions.height / 2,
+            },
+            Image::Png(ref md) => md.dimensions,
+        }
+    }
+
+    /// Returns the number of bits per pixel of the image.
+    pub fn bits_per_pixel(&self) -> u16 {
+        match *self {
+            Image::Bmp(ref header) => header.bit_count,
+            Image::Png(ref md) => md.color_depth as u16,
+        }
+    }
+}
+
+/// An entry of the icon directory.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Entry {
+    /// Dimensions stated in the directory, where 0 stands for 256.
+    pub declared_dimensions: Dimensions,
+    /// Number of colors in the palette, 0 if the image does not have a palette.
+    pub color_count: u8,
+    /// Number of bits per pixel stated in the directory; 0 if it is not stated and always
+    /// 0 for cursors.
+    pub bit_count: u16,
+    /// Coordinates of the cursor hotspot; only present for cursors.
+    pub hotspot: Option<(u16, u16)>,
+    /// Size of the image data.
+    pub data_size: u32,
+    /// Offset of the image data from the beginning of the file.
+    pub data_offset: u32,
+    /// The image itself, if it could be read and is valid.
+    pub image: Option<Image>,
+}
+
+impl Entry {
+    /// Returns dimensions of the image, or the dimensions stated in the directory if the image
+    /// could not be read.
+    pub fn dimensions(&self) -> Dimensions {
+        match self.image {
+            Some(ref image) => image.dimensions(),
+            None => self.declared_dimensions,
+        }
+    }
+
+    /// Returns the number of bits per pixel of the image, or the one stated in the directory
+    /// if the image could not be read.
+    pub fn bits_per_pixel(&self) -> u16 {
+        match self.image {
+            Some(ref image) => image.bits_per_pixel(),
+            None => self.bit_count,
+        }
+    }
+}
+
+/// Represents metadata of an icon or a cursor.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Metadata {
+    /// Kind of the file.
+    pub kind: Kind,
+    /// Directory entries in the order they are stored.
+    pub entries: Vec<Entry>,
+}
+
+impl Metadata {
+    /// Returns the largest entry, preferring entries with more bits per pixel among
+    /// entries of equal size.
+    pub fn largest_entry(&self) -> Option<&Entry> {
+        self.entries.iter().max_by_key(|e| {
+            let dimensions = e.dimensions();
+            (
+                dimensions.width as u64 * dimensions.height as u64,
+                e.bits_per_pixel(),
+            )
+        })
+    }
+
+    /// Returns dimensions of the largest entry.
+    pub fn dimensions(&self) -> Dimensions {
+        // loaded metadata always contains at least one entry
+        self.largest_entry()
+            .map_or((0u32, 0u32).into(), Entry::dimensions)
+    }
+}
+
+// reads an image from the data of an entry; invalid images are returned as `None`
+fn load_image<R: ?Sized + BufRead>(r: &mut R) -> Result<Option<Image>> {
+    let mut signature = [0u8; 8];
+    let n = r.read_exact_0(&mut signature)?;
+    let mut r = (&signature[..n]).chain(r);
+    // invalid images should not prevent loading the rest of the metadata
+    Ok(if signature[..n] == *PNG_SIGNATURE {
+        png::Metadata::load(&mut r).ok().map(Image::Png)
+    } else {
+        DibHeader::load(&mut r)
+            .ok()
+            .map(|(header, _, _)| Image::Bmp(header))
+    })
+}
+
+impl LoadableMetadata for Metadata {
+    fn load<R: ?Sized + BufRead>(r: &mut R) -> Result<Metadata> {
+        let mut header = [0u8; 6];
+        r.read_exact(&mut header)
+            .map_err(if_eof!(std, "when reading ICO header"))?;
+        let kind = match (
+            LittleEndian::read_u16(&header[0..2]),
+            LittleEndian::read_u16(&header[2..4]),
+        ) {
+            (0, 1) => Kind::Icon,
+            (0, 2) => Kind::Cursor,
+            (_, n) => return Err(invalid_format!("invalid ICO type: {}", n)),
+        };
+        let count = LittleEndian::read_u16(&header[4..6]);
+        if count == 0 {
+            return Err(invalid_format!("no images in ICO file"));
+        }
+
+        let mut entries = Vec::with_capacity(count as usize);
+        for i in 0..count {
+            let mut entry = [0u8; 16];
+            r.read_exact(&mut entry).map_err(if_eof!(
+                std,
+                "when reading ICO directory entry {}",
+                i
+            ))?;
+            let size = |n: u8| if n == 0 { 256 } else { n as u32 };
+            let (first, second) = (
+                LittleEndian::read_u16(&entry[4..6]),
+                LittleEndian::read_u16(&entry[6..8]),
+            );
+            entries.push(Entry {
+                declared_dimensions: (size(entry[0]), size(entry[1])).into(),
+                color_count: entry[2],
+                bit_count: if kind == Kind::Icon { second } else { 0 },
+                hotspot: if kind == Kind::Cursor {
+                    Some((first, second))
+                } else {
+                    None
+                },
+                data_size: LittleEndian::read_u32(&entry[8..12]),
+                data_offset: LittleEndian::read_u32(&entry[12..16]),
+                image: None,
+            });
+        }
+
+        // images are read in the order they are stored; overlapping ones are skipped, and
+        // truncated files still provide the directory and the images stored completely
+        let mut order: Vec<_> = (0..entries.len()).collect();
+        order.sort_by_key(|&i| entries[i].data_offset);
+        let mut position = header.len() as u64 + 16 * count as u64;
+        for i in order {
+            let entry = &mut entries[i];
+            let offset = entry.data_offset as u64;
+            if offset < position {
+                continue;
+            }
+            let to_skip = offset - position;
+            if r.skip_exact(to_skip)? != to_skip {
+                break;
+            }
+
+            let mut data = r.take(entry.data_size as u64);
+            let image = load_image(&mut data)?;
+            let rest = data.limit();
+            if data.skip_exact(rest)? != rest {
+                break;
+            }
+            entry.image = image;
+            position = offset + entry.data_size as u64;
+        }
+
+        Ok(Metadata { kind, entries })
+    }
+
+    // truncated files are loaded successfully, so parsing cannot be retried with more data
+    // once it fails because of the end of data, and the whole stream is read instead
+    #[cfg(feature = "async")]
+    async fn load_async<R: ?Sized + AsyncBufRead + Unpin>(r: &mut R) -> Result<Metadata> {
+        let mut data = Vec::new();
+        asynchronous::read_to_end(r, &mut data).await?;
+        Metadata::load_from_buf(&data)
+    }
+}
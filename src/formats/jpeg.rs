@@ -1,12 +1,16 @@
 //! Metadata of JPEG images.
 
 use std::fmt;
-use std::io::BufRead;
+use std::io::{BufRead, Read};
 
-use byteorder::{BigEndian, ReadBytesExt};
+use byteorder::{BigEndian, ByteOrder, ReadBytesExt};
 
+use crate::common::exif::{Exif, Gps};
+use crate::common::iptc::Iptc;
+use crate::common::photoshop::{self, ImageResources};
+use crate::common::xmp::{self, Xmp};
 use crate::traits::LoadableMetadata;
-use crate::types::{Dimensions, Result};
+use crate::types::{Dimensions, Orientation, Result};
 use crate::utils::BufReadExt;
 
 /// Coding process used in an image.
@@ -72,7 +76,9 @@ impl EntropyCoding {
 /// Represents metadata of a JPEG image.
 ///
 /// It provides information contained in JPEG frame header, including image dimensions,
-/// coding process type and entropy coding type.
+/// coding process type and entropy coding type, as well as EXIF and XMP metadata stored in
+/// APP1 segments, ICC profile stored in APP2 segments and Photoshop image resources stored
+/// in APP13 segments.
 #[derive(Clone, PartialEq, Eq, Debug)]
 pub struct Metadata {
     /// Image size.
@@ -87,18 +93,52 @@ pub struct Metadata {
     pub baseline: bool,
     /// Whether this image uses a differential encoding.
     pub differential: bool,
+    /// EXIF metadata, if it is present and valid.
+    pub exif: Option<Exif>,
+    /// Main XMP packet, if it is present and is a valid UTF-8 string.
+    pub xmp: Option<String>,
+    /// Extended XMP packet reassembled from multiple APP1 segments, if it is present
+    /// and complete.
+    ///
+    /// Extended XMP is used when the serialized XMP data does not fit into a single
+    /// JPEG segment. The main packet then refers to the extended one by its GUID
+    /// in the `xmpNote:HasExtendedXMP` property.
+    pub extended_xmp: Option<String>,
+    /// ICC profile reassembled from APP2 segments, if it is present and complete.
+    pub icc_profile: Option<Vec<u8>>,
+    /// Photoshop image resources, if they are present and valid.
+    pub photoshop: Option<ImageResources>,
+    /// IPTC-IIM metadata from the IPTC-NAA image resource, if it is present and valid.
+    pub iptc: Option<Iptc>,
 }
 
-fn find_marker<R: ?Sized, F>(r: &mut R, name: &str, mut matcher: F) -> Result<u8>
+impl Metadata {
+    /// Returns orientation from EXIF metadata, if it is present and valid.
+    #[inline]
+    pub fn orientation(&self) -> Option<Orientation> {
+        self.exif.as_ref().and_then(Exif::orientation)
+    }
+
+    /// Returns GPS information from EXIF metadata, if it is present.
+    #[inline]
+    pub fn gps(&self) -> Option<Gps> {
+        self.exif.as_ref().and_then(Exif::gps)
+    }
+}
+
+fn find_marker<R: ?Sized + BufRead, F>(r: &mut R, name: &str, mut matcher: F) -> Result<u8>
 where
-    R: BufRead,
     F: FnMut(u8) -> bool,
 {
     loop {
         if r.skip_until(0xff)? == 0 {
             return Err(unexpected_eof!("when searching for {} marker", name));
         }
-        let marker_type = try_if_eof!(r.read_u8(), "when reading marker type");
+        let mut marker_type = try_if_eof!(r.read_u8(), "when reading marker type");
+        // skip fill bytes
+        while marker_type == 0xff {
+            marker_type = try_if_eof!(r.read_u8(), "when reading marker type");
+        }
         if marker_type == 0 {
             continue;
         } // skip "stuffed" byte
@@ -109,6 +149,96 @@ where
     }
 }
 
+const ICC_PROFILE_SIGNATURE: &[u8] = b"ICC_PROFILE\0";
+const XMP_SIGNATURE: &[u8] = b"http://ns.adobe.com/xap/1.0/\0";
+const EXTENDED_XMP_SIGNATURE: &[u8] = b"http://ns.adobe.com/xmp/extension/\0";
+
+// A portion of an extended XMP packet from a single APP1 segment.
+struct ExtendedXmpChunk {
+    guid: [u8; 32],
+    full_length: u32,
+    offset: u32,
+    data: Vec<u8>,
+}
+
+impl ExtendedXmpChunk {
+    fn parse(data: &[u8]) -> Option<ExtendedXmpChunk> {
+        // the signature is followed by a 32-byte GUID, the full packet length and
+        // the offset of this portion, the latter two are big endian 32-bit integers
+        if data.len() < 40 {
+            return None;
+        }
+        let mut guid = [0u8; 32];
+        guid.copy_from_slice(&data[..32]);
+        Some(ExtendedXmpChunk {
+            guid,
+            full_length: BigEndian::read_u32(&data[32..36]),
+            offset: BigEndian::read_u32(&data[36..40]),
+            data: data[40..].to_vec(),
+        })
+    }
+}
+
+// Reassembles the extended XMP packet referenced by the main packet.
+fn reassemble_extended_xmp(
+    main: Option<&str>,
+    mut chunks: Vec<ExtendedXmpChunk>,
+) -> Option<String> {
+    // find the GUID of the extended packet in the main one; if there is no main packet,
+    // use the first encountered GUID
+    let guid = match main.and_then(extended_xmp_guid) {
+        Some(guid) => guid,
+        None => chunks.first()?.guid,
+    };
+    chunks.retain(|c| c.guid == guid);
+    chunks.sort_by_key(|c| c.offset);
+
+    let full_length = chunks.first()?.full_length;
+    let mut data = Vec::new();
+    for chunk in chunks {
+        if chunk.full_length != full_length {
+            return None;
+        }
+        // skip duplicate portions, but do not allow gaps
+        let offset = chunk.offset as usize;
+        if offset > data.len() {
+            return None;
+        }
+        if offset + chunk.data.len() > data.len() {
+            data.extend_from_slice(&chunk.data[data.len() - offset..]);
+        }
+    }
+    if data.len() != full_length as usize {
+        return None;
+    }
+    xmp::decode(&data)
+}
+
+// Reassembles an ICC profile from portions stored in APP2 segments.
+fn reassemble_icc_profile(mut chunks: Vec<(u8, u8, Vec<u8>)>) -> Option<Vec<u8>> {
+    // each portion is preceded by its 1-based sequence number and the total number
+    // of portions; all of them must be present
+    chunks.sort_by_key(|c| c.0);
+    let count = chunks.first()?.1;
+    if chunks.len() != count as usize
+        || chunks
+            .iter()
+            .enumerate()
+            .any(|(i, c)| c.0 as usize != i + 1 || c.1 != count)
+    {
+        return None;
+    }
+    Some(chunks.into_iter().flat_map(|c| c.2).collect())
+}
+
+// Extracts the value of the HasExtendedXMP property from the XMP note namespace, which
+// may be written either as an attribute or as an element with any prefix.
+fn extended_xmp_guid(packet: &str) -> Option<[u8; 32]> {
+    let main = Xmp::parse(packet).ok()?;
+    let value = main.get(xmp::ns::XMP_NOTE, "HasExtendedXMP")?.as_text()?;
+    value.trim().as_bytes().try_into().ok()
+}
+
 impl LoadableMetadata for Metadata {
     fn load<R: ?Sized + BufRead>(r: &mut R) -> Result<Metadata> {
         // read SOI marker, it must be present in all JPEG files
@@ -117,8 +247,86 @@ impl LoadableMetadata for Metadata {
         // XXX: do we need to check for APP0 JFIF marker? This doesn't seem strictly necessary
         // XXX: to me, and it seems that other interchange formats are also possible.
 
-        // read SOF marker, it must also be present in all JPEG files
-        let marker = find_marker(r, "SOF", is_sof_marker)?;
+        // walk through the segments until SOF marker, which must also be present in all
+        // JPEG files; segment payloads are skipped as a whole because they may contain
+        // other JPEG images, e.g. EXIF thumbnails
+        let mut exif = None;
+        let mut xmp = None;
+        let mut extended_xmp_chunks = Vec::new();
+        let mut icc_profile_chunks = Vec::new();
+        let mut photoshop_data: Option<Vec<u8>> = None;
+        let marker = loop {
+            let marker = find_marker(r, "SOF", |_| true)?;
+            if is_sof_marker(marker) {
+                break marker;
+            }
+
+            match marker {
+                // markers without payload
+                0x01 | 0xd0..=0xd8 => continue,
+                0xd9 => return Err(invalid_format!("JPEG image ends before SOF marker")),
+                _ => {}
+            }
+
+            let size = try_if_eof!(
+                r.read_u16::<BigEndian>(),
+                "when reading marker {:#x} payload size",
+                marker
+            );
+            if size < 2 {
+                return Err(invalid_format!(
+                    "invalid JPEG marker {:#x} payload size: {}",
+                    marker,
+                    size
+                ));
+            }
+            let size = size as u64 - 2;
+
+            // metadata may be stored in APP1, APP2 and APP13 segments
+            if !matches!(marker, 0xe1 | 0xe2 | 0xed) {
+                if r.skip_exact(size)? != size {
+                    return Err(unexpected_eof!(
+                        "when skipping marker {:#x} payload",
+                        marker
+                    ));
+                }
+                continue;
+            }
+
+            let mut data = Vec::with_capacity(size as usize);
+            if r.take(size).read_to_end(&mut data)? as u64 != size {
+                return Err(unexpected_eof!("when reading marker {:#x} payload", marker));
+            }
+
+            // invalid metadata should not prevent loading the rest of the metadata
+            if marker == 0xe1 {
+                if data.starts_with(b"Exif\0\0") {
+                    if exif.is_none() {
+                        exif = Exif::load(&data).ok();
+                    }
+                } else if let Some(packet) = data.strip_prefix(XMP_SIGNATURE) {
+                    if xmp.is_none() {
+                        xmp = xmp::decode(packet);
+                    }
+                } else if let Some(chunk) = data.strip_prefix(EXTENDED_XMP_SIGNATURE) {
+                    extended_xmp_chunks.extend(ExtendedXmpChunk::parse(chunk));
+                }
+            } else if marker == 0xe2 {
+                if let Some([seq, count, profile @ ..]) = data.strip_prefix(ICC_PROFILE_SIGNATURE) {
+                    icc_profile_chunks.push((*seq, *count, profile.to_vec()));
+                }
+            } else if let Some(resources) = data.strip_prefix(photoshop::APP13_SIGNATURE) {
+                // image resources may be split across several segments
+                photoshop_data
+                    .get_or_insert_with(Vec::new)
+                    .extend_from_slice(resources);
+            }
+        };
+
+        let extended_xmp = reassemble_extended_xmp(xmp.as_deref(), extended_xmp_chunks);
+        let icc_profile = reassemble_icc_profile(icc_profile_chunks);
+        let photoshop = photoshop_data.and_then(|data| ImageResources::load(&data).ok());
+        let iptc = photoshop.as_ref().and_then(ImageResources::iptc);
 
         // read and check SOF marker length
         let size = try_if_eof!(
@@ -159,6 +367,12 @@ impl LoadableMetadata for Metadata {
             entropy_coding,
             baseline,
             differential,
+            exif,
+            xmp,
+            extended_xmp,
+            icc_profile,
+            photoshop,
+            iptc,
         })
     }
 }
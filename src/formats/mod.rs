@@ -1,6 +1,11 @@
 //! Supported image types.
 
+pub mod avif;
+pub mod bmp;
 pub mod gif;
+pub mod heif;
+pub mod ico;
 pub mod jpeg;
 pub mod png;
+pub mod tiff;
 pub mod webp;
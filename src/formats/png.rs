@@ -1,12 +1,18 @@
 //! Metadata for PNG images.
 
 use std::fmt;
-use std::io::BufRead;
+use std::io::{BufRead, Read};
+use std::str;
 
-use byteorder::{BigEndian, ReadBytesExt};
+use byteorder::{BigEndian, ByteOrder, ReadBytesExt};
 
+use crate::common::exif::{Exif, Gps};
+use crate::common::icc::RenderingIntent;
+use crate::common::xmp;
+use crate::inflate;
 use crate::traits::LoadableMetadata;
-use crate::types::{Dimensions, Result};
+use crate::types::{Dimensions, Orientation, Result};
+use crate::utils::{BufReadExt, Crc32};
 
 /// Color type used in an image.
 ///
@@ -82,7 +88,7 @@ fn compute_color_depth(bit_depth: u8, color_type: u8) -> Option<u8> {
 /// PNG spec currently defines only one compression method:
 ///
 /// > At present, only compression method 0 (deflate/inflate compression with a sliding window of
-/// at most 32768 bytes) is defined.
+/// > at most 32768 bytes) is defined.
 #[derive(Copy, Clone, Eq, PartialEq, Debug)]
 pub enum CompressionMethod {
     DeflateInflate,
@@ -110,7 +116,7 @@ impl CompressionMethod {
 /// PNG spec currently defines only one filter method:
 ///
 /// > At present, only filter method 0 (adaptive filtering with five basic filter types) is
-/// defined.
+/// > defined.
 #[derive(Copy, Clone, Eq, PartialEq, Debug)]
 pub enum FilterMethod {
     AdaptiveFiltering,
@@ -161,6 +167,697 @@ impl InterlaceMethod {
     }
 }
 
+/// Describes how the frame area is treated before rendering the next frame of an
+/// animated image.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum DisposeOp {
+    /// The frame area is left as is.
+    None,
+    /// The frame area is cleared to fully transparent black.
+    Background,
+    /// The frame area is reverted to its contents before rendering the frame.
+    Previous,
+}
+
+impl fmt::Display for DisposeOp {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match *self {
+            DisposeOp::None => "None",
+            DisposeOp::Background => "Background",
+            DisposeOp::Previous => "Previous",
+        })
+    }
+}
+
+impl DisposeOp {
+    fn from_u8(n: u8) -> Option<DisposeOp> {
+        match n {
+            0 => Some(DisposeOp::None),
+            1 => Some(DisposeOp::Background),
+            2 => Some(DisposeOp::Previous),
+            _ => None,
+        }
+    }
+}
+
+/// Describes how a frame of an animated image is rendered onto the output buffer.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum BlendOp {
+    /// The frame overwrites the frame area, including its alpha channel.
+    Source,
+    /// The frame is alpha-composited over the frame area.
+    Over,
+}
+
+impl fmt::Display for BlendOp {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match *self {
+            BlendOp::Source => "Source",
+            BlendOp::Over => "Over",
+        })
+    }
+}
+
+impl BlendOp {
+    fn from_u8(n: u8) -> Option<BlendOp> {
+        match n {
+            0 => Some(BlendOp::Source),
+            1 => Some(BlendOp::Over),
+            _ => None,
+        }
+    }
+}
+
+/// Contains animation parameters of an APNG image and metadata of each frame.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct Animation {
+    /// Number of frames declared in the `acTL` chunk.
+    pub num_frames: u32,
+    /// Number of times to play the animation, zero means infinitely.
+    pub num_plays: u32,
+    /// Whether the default image is the first frame of the animation.
+    ///
+    /// If it is not, the default image is only displayed by decoders which do not
+    /// support APNG.
+    pub default_image_is_first_frame: bool,
+    /// Metadata for each frame of the animation, from `fcTL` chunks.
+    pub frames: Vec<FrameControl>,
+}
+
+impl Animation {
+    /// Computes the total duration of the animation in milliseconds, i.e. the sum of
+    /// delays of all frames, rounded to the nearest millisecond.
+    ///
+    /// Number of plays is not taken into account.
+    #[inline]
+    pub fn total_duration_ms(&self) -> u64 {
+        self.frames
+            .iter()
+            .map(FrameControl::delay_ms)
+            .sum::<f64>()
+            .round() as u64
+    }
+}
+
+/// Contains metadata of a single animation frame (`fcTL` chunk).
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub struct FrameControl {
+    /// Sequence number of the chunk, shared with `fdAT` chunks.
+    pub sequence_number: u32,
+    /// Frame size.
+    pub dimensions: Dimensions,
+    /// Horizontal offset of the frame on the canvas.
+    pub x_offset: u32,
+    /// Vertical offset of the frame on the canvas.
+    pub y_offset: u32,
+    /// Numerator of the frame delay in seconds.
+    pub delay_num: u16,
+    /// Denominator of the frame delay in seconds; zero means 100.
+    ///
+    /// Use `delay_ms()` method to obtain a more conventional time representation.
+    pub delay_den: u16,
+    /// How the frame area is treated before rendering the next frame.
+    pub dispose_op: DisposeOp,
+    /// How the frame is rendered onto the output buffer.
+    pub blend_op: BlendOp,
+}
+
+impl FrameControl {
+    /// Returns the time to wait before displaying the next frame, in milliseconds.
+    #[inline]
+    pub fn delay_ms(&self) -> f64 {
+        let den = if self.delay_den == 0 {
+            100
+        } else {
+            self.delay_den
+        };
+        self.delay_num as f64 * 1000.0 / den as f64
+    }
+
+    fn parse(data: &[u8]) -> Option<FrameControl> {
+        if data.len() < 26 {
+            return None;
+        }
+        Some(FrameControl {
+            sequence_number: BigEndian::read_u32(&data[0..4]),
+            dimensions: (
+                BigEndian::read_u32(&data[4..8]),
+                BigEndian::read_u32(&data[8..12]),
+            )
+                .into(),
+            x_offset: BigEndian::read_u32(&data[12..16]),
+            y_offset: BigEndian::read_u32(&data[16..20]),
+            delay_num: BigEndian::read_u16(&data[20..22]),
+            delay_den: BigEndian::read_u16(&data[22..24]),
+            dispose_op: DisposeOp::from_u8(data[24])?,
+            blend_op: BlendOp::from_u8(data[25])?,
+        })
+    }
+}
+
+/// Unit of measurement for physical pixel dimensions.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum PhysicalUnit {
+    /// No absolute unit; the values only define the pixel aspect ratio.
+    Unspecified,
+    /// Pixels per meter.
+    Meter,
+}
+
+impl fmt::Display for PhysicalUnit {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match *self {
+            PhysicalUnit::Unspecified => "Unspecified",
+            PhysicalUnit::Meter => "Meter",
+        })
+    }
+}
+
+impl PhysicalUnit {
+    fn from_u8(n: u8) -> Option<PhysicalUnit> {
+        match n {
+            0 => Some(PhysicalUnit::Unspecified),
+            1 => Some(PhysicalUnit::Meter),
+            _ => None,
+        }
+    }
+}
+
+/// Contains physical pixel dimensions from the `pHYs` chunk.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub struct PhysicalDimensions {
+    /// Number of pixels per unit in the horizontal direction.
+    pub x: u32,
+    /// Number of pixels per unit in the vertical direction.
+    pub y: u32,
+    /// Unit of measurement for `x` and `y`.
+    pub unit: PhysicalUnit,
+}
+
+impl PhysicalDimensions {
+    /// Computes horizontal and vertical resolution in dots per inch, if the unit is known.
+    #[inline]
+    pub fn dpi(&self) -> Option<(f64, f64)> {
+        match self.unit {
+            PhysicalUnit::Meter => Some((self.x as f64 * 0.0254, self.y as f64 * 0.0254)),
+            PhysicalUnit::Unspecified => None,
+        }
+    }
+
+    fn parse(data: &[u8]) -> Option<PhysicalDimensions> {
+        if data.len() < 9 {
+            return None;
+        }
+        Some(PhysicalDimensions {
+            x: BigEndian::read_u32(&data[0..4]),
+            y: BigEndian::read_u32(&data[4..8]),
+            unit: PhysicalUnit::from_u8(data[8])?,
+        })
+    }
+}
+
+/// Contains chromaticities of the primaries and the white point from the `cHRM` chunk.
+///
+/// Each value is a CIE 1931 `(x, y)` pair multiplied by 100000.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub struct Chromaticities {
+    pub white_point: (u32, u32),
+    pub red: (u32, u32),
+    pub green: (u32, u32),
+    pub blue: (u32, u32),
+}
+
+impl Chromaticities {
+    fn parse(data: &[u8]) -> Option<Chromaticities> {
+        if data.len() < 32 {
+            return None;
+        }
+        let point = |i: usize| {
+            (
+                BigEndian::read_u32(&data[i..i + 4]),
+                BigEndian::read_u32(&data[i + 4..i + 8]),
+            )
+        };
+        Some(Chromaticities {
+            white_point: point(0),
+            red: point(8),
+            green: point(16),
+            blue: point(24),
+        })
+    }
+}
+
+/// Background color from the `bKGD` chunk.
+///
+/// Sample values are in the range defined by the bit depth of the image.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum BackgroundColor {
+    /// Gray level, for grayscale images.
+    Gray(u16),
+    /// Red, green and blue samples, for RGB images.
+    Rgb(u16, u16, u16),
+    /// Index of the color in the palette, for indexed images.
+    PaletteIndex(u8),
+}
+
+impl BackgroundColor {
+    fn parse(data: &[u8], color_type: u8) -> Option<BackgroundColor> {
+        let sample = |i: usize| data.get(i * 2..i * 2 + 2).map(BigEndian::read_u16);
+        match color_type {
+            CT_INDEXED => data.first().map(|&i| BackgroundColor::PaletteIndex(i)),
+            CT_GRAYSCALE | CT_GRAYSCALE_ALPHA => sample(0).map(BackgroundColor::Gray),
+            CT_RGB | CT_RGB_ALPHA => Some(BackgroundColor::Rgb(sample(0)?, sample(1)?, sample(2)?)),
+            _ => None,
+        }
+    }
+}
+
+/// Simple transparency information from the `tRNS` chunk.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub enum Transparency {
+    /// Gray level of pixels which are fully transparent, for grayscale images.
+    Gray(u16),
+    /// Red, green and blue samples of pixels which are fully transparent, for RGB images.
+    Rgb(u16, u16, u16),
+    /// Alpha values of palette entries, for indexed images.
+    ///
+    /// Entries beyond the end of this list are fully opaque.
+    Palette(Vec<u8>),
+}
+
+impl Transparency {
+    fn parse(data: &[u8], color_type: u8) -> Option<Transparency> {
+        let sample = |i: usize| data.get(i * 2..i * 2 + 2).map(BigEndian::read_u16);
+        match color_type {
+            CT_INDEXED => Some(Transparency::Palette(data.to_vec())),
+            CT_GRAYSCALE => sample(0).map(Transparency::Gray),
+            CT_RGB => Some(Transparency::Rgb(sample(0)?, sample(1)?, sample(2)?)),
+            // images with alpha channel must not contain tRNS chunks
+            _ => None,
+        }
+    }
+}
+
+/// Coding-independent code points from the `cICP` chunk.
+///
+/// The values are defined in ITU-T H.273 and take precedence over other color space
+/// information, e.g. to signal HDR images.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub struct Cicp {
+    /// Color primaries, e.g. 1 for BT.709 or 9 for BT.2020.
+    pub color_primaries: u8,
+    /// Transfer characteristics, e.g. 13 for sRGB, 16 for PQ or 18 for HLG.
+    pub transfer_characteristics: u8,
+    /// Matrix coefficients; PNG images are always RGB, so this value is 0.
+    pub matrix_coefficients: u8,
+    /// Whether the image uses full range of sample values, as opposed to narrow range.
+    pub full_range: bool,
+}
+
+impl Cicp {
+    fn parse(data: &[u8]) -> Option<Cicp> {
+        match *data {
+            [color_primaries, transfer_characteristics, matrix_coefficients, full_range, ..] => {
+                Some(Cicp {
+                    color_primaries,
+                    transfer_characteristics,
+                    matrix_coefficients,
+                    full_range: full_range != 0,
+                })
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Color volume of the display used to master the image, from the `mDCv` chunk.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub struct MasteringDisplayColorVolume {
+    /// Chromaticities of the red, green and blue display primaries.
+    ///
+    /// Each value is a CIE 1931 `(x, y)` pair in units of 0.00002.
+    pub primaries: [(u16, u16); 3],
+    /// Chromaticity of the white point, in units of 0.00002.
+    pub white_point: (u16, u16),
+    /// Maximum display luminance in units of 0.0001 cd/m².
+    pub max_luminance: u32,
+    /// Minimum display luminance in units of 0.0001 cd/m².
+    pub min_luminance: u32,
+}
+
+impl MasteringDisplayColorVolume {
+    fn parse(data: &[u8]) -> Option<MasteringDisplayColorVolume> {
+        if data.len() < 24 {
+            return None;
+        }
+        let point = |i: usize| {
+            (
+                BigEndian::read_u16(&data[i..i + 2]),
+                BigEndian::read_u16(&data[i + 2..i + 4]),
+            )
+        };
+        Some(MasteringDisplayColorVolume {
+            primaries: [point(0), point(4), point(8)],
+            white_point: point(12),
+            max_luminance: BigEndian::read_u32(&data[16..20]),
+            min_luminance: BigEndian::read_u32(&data[20..24]),
+        })
+    }
+}
+
+/// Content light level information from the `cLLi` chunk.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub struct ContentLightLevel {
+    /// Maximum light level of any pixel, in units of 0.0001 cd/m².
+    pub max_content_light_level: u32,
+    /// Maximum frame-average light level, in units of 0.0001 cd/m².
+    pub max_frame_average_light_level: u32,
+}
+
+impl ContentLightLevel {
+    fn parse(data: &[u8]) -> Option<ContentLightLevel> {
+        if data.len() < 8 {
+            return None;
+        }
+        Some(ContentLightLevel {
+            max_content_light_level: BigEndian::read_u32(&data[0..4]),
+            max_frame_average_light_level: BigEndian::read_u32(&data[4..8]),
+        })
+    }
+}
+
+// returns the number of sBIT values for the given color type
+fn significant_bits_count(color_type: u8) -> Option<usize> {
+    match color_type {
+        CT_GRAYSCALE => Some(1),
+        CT_GRAYSCALE_ALPHA => Some(2),
+        CT_RGB | CT_INDEXED => Some(3),
+        CT_RGB_ALPHA => Some(4),
+        _ => None,
+    }
+}
+
+/// Type of a textual chunk.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum TextKind {
+    /// `tEXt` chunk, Latin-1 text.
+    Text,
+    /// `zTXt` chunk, compressed Latin-1 text.
+    CompressedText,
+    /// `iTXt` chunk, UTF-8 text, optionally compressed.
+    InternationalText,
+}
+
+impl fmt::Display for TextKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match *self {
+            TextKind::Text => "tEXt",
+            TextKind::CompressedText => "zTXt",
+            TextKind::InternationalText => "iTXt",
+        })
+    }
+}
+
+/// Contains a keyword/text pair from a textual chunk.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct TextChunk {
+    /// Type of the chunk the text comes from.
+    pub kind: TextKind,
+    /// Keyword, e.g. `Title`, `Comment` or `Software`.
+    pub keyword: String,
+    /// Text, decompressed if necessary.
+    pub text: String,
+    /// Whether the text was compressed in the chunk.
+    pub compressed: bool,
+    /// Language of the text, e.g. `en-US`, for `iTXt` chunks; it is empty if the language
+    /// is unknown.
+    pub language_tag: Option<String>,
+    /// Keyword translated into the language of the text, for `iTXt` chunks.
+    pub translated_keyword: Option<String>,
+}
+
+const SIGNATURE: &[u8; 8] = b"\x89PNG\r\n\x1a\n";
+
+/// Type of a PNG chunk.
+///
+/// Properties of a chunk are encoded in the case of the letters of its type.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub struct ChunkType(pub [u8; 4]);
+
+impl ChunkType {
+    /// Returns the chunk type as a string, if it is valid.
+    #[inline]
+    pub fn as_str(&self) -> Option<&str> {
+        str::from_utf8(&self.0).ok()
+    }
+
+    /// Returns `true` if the chunk is critical, i.e. it is necessary to display the image.
+    #[inline]
+    pub fn is_critical(&self) -> bool {
+        self.0[0] & 0x20 == 0
+    }
+
+    /// Returns `true` if the chunk is a part of the PNG specification or is registered.
+    #[inline]
+    pub fn is_public(&self) -> bool {
+        self.0[1] & 0x20 == 0
+    }
+
+    /// Returns `true` if the reserved bit is not set, as required by the current
+    /// PNG specification.
+    #[inline]
+    pub fn is_reserved_bit_valid(&self) -> bool {
+        self.0[2] & 0x20 == 0
+    }
+
+    /// Returns `true` if the chunk may be copied by editors which do not recognize it
+    /// even if the image data was modified.
+    #[inline]
+    pub fn is_safe_to_copy(&self) -> bool {
+        self.0[3] & 0x20 != 0
+    }
+}
+
+impl fmt::Display for ChunkType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.as_str() {
+            Some(s) => f.write_str(s),
+            None => write!(f, "{:?}", self.0),
+        }
+    }
+}
+
+/// Contains information about a single chunk.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct ChunkInfo {
+    /// Chunk type.
+    pub chunk_type: ChunkType,
+    /// Length of the chunk data, excluding the length, type and CRC fields.
+    pub length: u32,
+    /// Offset of the chunk from the beginning of the file.
+    pub offset: u64,
+}
+
+// the chunk whose data is being read
+struct CurrentChunk {
+    chunk_type: ChunkType,
+    remaining: u32,
+    crc: Crc32,
+}
+
+/// Reads chunks of a PNG stream one by one.
+///
+/// The reader is an iterator over information about chunks, starting right after the PNG
+/// signature and ending with the `IEND` chunk. Data of the last returned chunk can be
+/// obtained with `read_data()`; otherwise it is skipped when the next chunk is requested.
+/// A stream which ends before the `IEND` chunk results in an error.
+pub struct ChunkReader<'a, R: ?Sized> {
+    source: &'a mut R,
+    offset: u64,
+    verify_crc: bool,
+    current: Option<CurrentChunk>,
+    finished: bool,
+}
+
+impl<'a, R: ?Sized + BufRead> ChunkReader<'a, R> {
+    /// Creates a new reader of the given stream, which must be positioned right after
+    /// the PNG signature.
+    pub fn new(source: &'a mut R) -> ChunkReader<'a, R> {
+        ChunkReader {
+            source,
+            offset: SIGNATURE.len() as u64,
+            verify_crc: false,
+            current: None,
+            finished: false,
+        }
+    }
+
+    /// Sets whether CRC of each chunk should be verified; it is not verified by default.
+    ///
+    /// If verification is enabled, a CRC mismatch results in an error.
+    pub fn verify_crc(mut self, verify_crc: bool) -> ChunkReader<'a, R> {
+        self.verify_crc = verify_crc;
+        self
+    }
+
+    /// Reads data of the last returned chunk.
+    ///
+    /// Returns an empty vector if the data has already been read.
+    pub fn read_data(&mut self) -> Result<Vec<u8>> {
+        let current = match self.current {
+            Some(ref mut c) => c,
+            None => return Ok(Vec::new()),
+        };
+
+        let mut data = Vec::new();
+        let n = current.remaining as u64;
+        if self.source.take(n).read_to_end(&mut data)? as u64 != n {
+            return Err(unexpected_eof!(
+                "when reading {} chunk data",
+                current.chunk_type
+            ));
+        }
+        if self.verify_crc {
+            current.crc.update(&data);
+        }
+        current.remaining = 0;
+        Ok(data)
+    }
+
+    // skips the rest of the current chunk data and checks its CRC
+    fn finish_chunk(&mut self) -> Result<()> {
+        let mut current = match self.current.take() {
+            Some(c) => c,
+            None => return Ok(()),
+        };
+
+        let n = current.remaining as u64;
+        if self.verify_crc {
+            let mut remaining = n;
+            while remaining > 0 {
+                let buf = self.source.fill_buf()?;
+                if buf.is_empty() {
+                    break;
+                }
+                let len = buf.len().min(remaining as usize);
+                current.crc.update(&buf[..len]);
+                self.source.consume(len);
+                remaining -= len as u64;
+            }
+            if remaining > 0 {
+                return Err(unexpected_eof!(
+                    "when reading {} chunk data",
+                    current.chunk_type
+                ));
+            }
+        } else if self.source.skip_exact(n)? != n {
+            return Err(unexpected_eof!(
+                "when skipping {} chunk data",
+                current.chunk_type
+            ));
+        }
+
+        let crc = try_if_eof!(
+            self.source.read_u32::<BigEndian>(),
+            "when reading {} chunk CRC",
+            current.chunk_type
+        );
+        if self.verify_crc && crc != current.crc.finish() {
+            return Err(invalid_format!(
+                "CRC mismatch in {} chunk at offset {}",
+                current.chunk_type,
+                self.offset
+            ));
+        }
+        Ok(())
+    }
+
+    fn read_chunk(&mut self) -> Result<ChunkInfo> {
+        self.finish_chunk()?;
+        let offset = self.offset;
+
+        let length = try_if_eof!(
+            self.source.read_u32::<BigEndian>(),
+            "when reading length of chunk at offset {}",
+            offset
+        );
+        if length > i32::MAX as u32 {
+            return Err(invalid_format!(
+                "invalid length of chunk at offset {}: {}",
+                offset,
+                length
+            ));
+        }
+
+        let mut chunk_type = [0u8; 4];
+        self.source.read_exact(&mut chunk_type).map_err(if_eof!(
+            std,
+            "when reading type of chunk at offset {}",
+            offset
+        ))?;
+        if !chunk_type.iter().all(u8::is_ascii_alphabetic) {
+            return Err(invalid_format!(
+                "invalid type of chunk at offset {}: {:?}",
+                offset,
+                chunk_type
+            ));
+        }
+        let chunk_type = ChunkType(chunk_type);
+
+        let mut crc = Crc32::new();
+        if self.verify_crc {
+            crc.update(&chunk_type.0);
+        }
+        self.current = Some(CurrentChunk {
+            chunk_type,
+            remaining: length,
+            crc,
+        });
+        // length, type and CRC fields take 12 bytes
+        self.offset += length as u64 + 12;
+
+        Ok(ChunkInfo {
+            chunk_type,
+            length,
+            offset,
+        })
+    }
+}
+
+impl<'a, R: ?Sized + BufRead> Iterator for ChunkReader<'a, R> {
+    type Item = Result<ChunkInfo>;
+
+    fn next(&mut self) -> Option<Result<ChunkInfo>> {
+        if self.finished {
+            // the CRC of the IEND chunk still has to be read
+            return match self.finish_chunk() {
+                Ok(()) => None,
+                Err(e) => Some(Err(e)),
+            };
+        }
+
+        let result = self.read_chunk();
+        match result {
+            Ok(ref chunk) if &chunk.chunk_type.0 == b"IEND" => self.finished = true,
+            Err(_) => {
+                self.current = None;
+                self.finished = true;
+            }
+            _ => {}
+        }
+        Some(result)
+    }
+}
+
+/// Options which can be used to configure how PNG metadata is loaded.
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
+pub struct LoadOptions {
+    /// Whether to verify CRC of every chunk; see `Metadata::load_with_crc_check()`.
+    pub verify_crc: bool,
+    /// Whether to read palette entries into `Metadata::palette`.
+    pub retain_palette: bool,
+}
+
 /// Represents metadata of a PNG image.
 #[derive(Clone, Eq, PartialEq, Debug)]
 pub struct Metadata {
@@ -176,46 +873,398 @@ pub struct Metadata {
     pub filter_method: FilterMethod,
     /// Transmission order used in the image.
     pub interlace_method: InterlaceMethod,
+    /// Palette entries from the `PLTE` chunk as red, green, blue and alpha values.
+    ///
+    /// Alpha values come from the `tRNS` chunk; entries without them are fully opaque.
+    /// Non-indexed images may also contain a palette, which suggests a set of colors
+    /// to quantize the image to.
+    ///
+    /// This field is only set if it was requested with `LoadOptions::retain_palette`.
+    pub palette: Option<Vec<[u8; 4]>>,
+    /// Physical pixel dimensions from the `pHYs` chunk, if it is present and valid.
+    pub physical_dimensions: Option<PhysicalDimensions>,
+    /// Image gamma from the `gAMA` chunk, multiplied by 100000.
+    ///
+    /// Use `gamma_approx()` method to obtain the value as a floating point number.
+    pub gamma: Option<u32>,
+    /// Chromaticities of the primaries and the white point from the `cHRM` chunk.
+    pub chromaticities: Option<Chromaticities>,
+    /// Rendering intent from the `sRGB` chunk.
+    ///
+    /// If this field is set, the image uses the sRGB color space.
+    pub srgb_intent: Option<RenderingIntent>,
+    /// Number of significant bits in each channel of the original image, from the `sBIT`
+    /// chunk.
+    ///
+    /// For indexed images, the values correspond to red, green and blue channels of
+    /// the palette.
+    pub significant_bits: Option<Vec<u8>>,
+    /// Background color from the `bKGD` chunk, if it is present and valid.
+    pub background_color: Option<BackgroundColor>,
+    /// Transparency information from the `tRNS` chunk, if it is present and valid.
+    pub transparency: Option<Transparency>,
+    /// Coding-independent code points from the `cICP` chunk.
+    pub cicp: Option<Cicp>,
+    /// Mastering display color volume from the `mDCv` chunk.
+    pub mastering_display_color_volume: Option<MasteringDisplayColorVolume>,
+    /// Content light level information from the `cLLi` chunk.
+    pub content_light_level: Option<ContentLightLevel>,
+    /// Information about all chunks of the image, from `IHDR` to `IEND`.
+    pub chunks: Vec<ChunkInfo>,
+    /// Animation parameters and frames, if the image is an animated PNG (APNG).
+    ///
+    /// This field is `None` if there is no `acTL` chunk, or if any of the animation chunks
+    /// is invalid, in which case the image is displayed as a static one.
+    pub animation: Option<Animation>,
+    /// Keyword/text pairs from `tEXt`, `zTXt` and `iTXt` chunks, in the order of chunks.
+    ///
+    /// Chunks which cannot be decoded are skipped.
+    pub texts: Vec<TextChunk>,
+    /// EXIF metadata from the `eXIf` chunk, if it is present and valid.
+    pub exif: Option<Exif>,
+    /// ICC profile decompressed from the `iCCP` chunk, if it is present and valid.
+    pub icc_profile: Option<Vec<u8>>,
+    /// XMP packet from the first valid `iTXt` chunk with `XML:com.adobe.xmp` keyword, if it
+    /// is present; compressed chunks are decompressed.
+    pub xmp: Option<String>,
+}
+
+impl Metadata {
+    /// Returns image gamma as a floating point number, if it is present.
+    #[inline]
+    pub fn gamma_approx(&self) -> Option<f64> {
+        self.gamma.map(|g| g as f64 / 100000.0)
+    }
+
+    /// Returns `true` if the image may contain transparent pixels.
+    ///
+    /// This is the case for images with an alpha channel and for images with transparency
+    /// information in the `tRNS` chunk, unless all palette entries it defines are opaque.
+    pub fn has_transparency(&self) -> bool {
+        match self.color_type {
+            ColorType::GrayscaleAlpha | ColorType::RgbAlpha => true,
+            _ => match self.transparency {
+                Some(Transparency::Palette(ref alpha)) => alpha.iter().any(|&a| a != 255),
+                Some(_) => true,
+                None => false,
+            },
+        }
+    }
+
+    /// Computes the number of frames.
+    ///
+    /// Images which are not animated always consist of exactly one frame.
+    #[inline]
+    pub fn frames_number(&self) -> usize {
+        self.animation.as_ref().map_or(1, |a| a.frames.len())
+    }
+
+    /// Returns `true` if the image is animated, `false` otherwise.
+    ///
+    /// Similarly to GIF images, an image is considered animated if it has more than one frame.
+    #[inline]
+    pub fn is_animated(&self) -> bool {
+        self.frames_number() > 1
+    }
+
+    /// Computes the total duration of the animation in milliseconds.
+    ///
+    /// Returns zero for images which are not animated. See `Animation::total_duration_ms()`.
+    #[inline]
+    pub fn total_duration_ms(&self) -> u64 {
+        self.animation
+            .as_ref()
+            .map_or(0, Animation::total_duration_ms)
+    }
+
+    /// Returns the text of the first textual chunk with the given keyword, if there is one.
+    ///
+    /// Keywords are case-sensitive, e.g. `Comment`, `Software` or `parameters`.
+    pub fn text(&self, keyword: &str) -> Option<&str> {
+        self.texts
+            .iter()
+            .find(|t| t.keyword == keyword)
+            .map(|t| &*t.text)
+    }
+
+    /// Returns orientation from EXIF metadata, if it is present and valid.
+    #[inline]
+    pub fn orientation(&self) -> Option<Orientation> {
+        self.exif.as_ref().and_then(Exif::orientation)
+    }
+
+    /// Returns GPS information from EXIF metadata, if it is present.
+    #[inline]
+    pub fn gps(&self) -> Option<Gps> {
+        self.exif.as_ref().and_then(Exif::gps)
+    }
+}
+
+// ICC profiles are small, so larger data is most likely malicious
+const MAX_ICC_PROFILE_SIZE: usize = 16 << 20;
+
+// returns the decompressed ICC profile from the given iCCP chunk payload
+fn read_icc_profile(data: &[u8]) -> Option<Vec<u8>> {
+    // profile name is followed by a NUL separator and compression method, which must be 0
+    let rest = &data[data.iter().position(|&b| b == 0)? + 1..];
+    match rest.split_first()? {
+        (0, compressed) => inflate::zlib_decompress(compressed, MAX_ICC_PROFILE_SIZE).ok(),
+        _ => None,
+    }
+}
+
+const XMP_KEYWORD: &str = "XML:com.adobe.xmp";
+
+// compressed text is not limited by the format, but larger data is most likely malicious
+const MAX_TEXT_SIZE: usize = 16 << 20;
+
+// splits the data at the first NUL byte, dropping it
+fn split_nul(data: &[u8]) -> Option<(&[u8], &[u8])> {
+    let i = data.iter().position(|&b| b == 0)?;
+    Some((&data[..i], &data[i + 1..]))
+}
+
+fn latin1(data: &[u8]) -> String {
+    data.iter().map(|&b| b as char).collect()
+}
+
+// parses the payload of a tEXt, zTXt or iTXt chunk
+fn read_text_chunk(kind: TextKind, data: &[u8]) -> Option<TextChunk> {
+    let (keyword, rest) = split_nul(data)?;
+    if keyword.is_empty() || keyword.len() > 79 {
+        return None;
+    }
+    let keyword = latin1(keyword);
+
+    match kind {
+        TextKind::Text => Some(TextChunk {
+            kind,
+            keyword,
+            text: latin1(rest),
+            compressed: false,
+            language_tag: None,
+            translated_keyword: None,
+        }),
+        TextKind::CompressedText => match rest.split_first()? {
+            (0, compressed) => Some(TextChunk {
+                kind,
+                keyword,
+                text: latin1(&inflate::zlib_decompress(compressed, MAX_TEXT_SIZE).ok()?),
+                compressed: true,
+                language_tag: None,
+                translated_keyword: None,
+            }),
+            _ => None,
+        },
+        TextKind::InternationalText => {
+            // compression flag and method are followed by language tag and translated keyword
+            let (&compressed, rest) = rest.split_first()?;
+            let (&method, rest) = rest.split_first()?;
+            let (language_tag, rest) = split_nul(rest)?;
+            let (translated_keyword, text) = split_nul(rest)?;
+            let text = match (compressed, method) {
+                (0, _) => text.to_vec(),
+                (1, 0) => inflate::zlib_decompress(text, MAX_TEXT_SIZE).ok()?,
+                _ => return None,
+            };
+            Some(TextChunk {
+                kind,
+                keyword,
+                text: String::from_utf8(text).ok()?,
+                compressed: compressed != 0,
+                language_tag: Some(str::from_utf8(language_tag).ok()?.to_owned()),
+                translated_keyword: Some(str::from_utf8(translated_keyword).ok()?.to_owned()),
+            })
+        }
+    }
 }
 
 impl LoadableMetadata for Metadata {
     fn load<R: ?Sized + BufRead>(r: &mut R) -> Result<Metadata> {
+        Metadata::load_with_options(r, &LoadOptions::default())
+    }
+}
+
+impl Metadata {
+    /// Loads metadata like `LoadableMetadata::load()`, additionally verifying CRC of every
+    /// chunk.
+    ///
+    /// This requires computing checksums of image data, so it is slower than the regular
+    /// loading, but it allows detecting corrupted files.
+    pub fn load_with_crc_check<R: ?Sized + BufRead>(r: &mut R) -> Result<Metadata> {
+        let options = LoadOptions {
+            verify_crc: true,
+            ..LoadOptions::default()
+        };
+        Metadata::load_with_options(r, &options)
+    }
+
+    /// Loads metadata like `LoadableMetadata::load()`, using the given options.
+    pub fn load_with_options<R: ?Sized + BufRead>(
+        r: &mut R,
+        options: &LoadOptions,
+    ) -> Result<Metadata> {
         let mut signature = [0u8; 8];
         r.read_exact(&mut signature)
             .map_err(if_eof!(std, "when reading PNG signature"))?;
 
-        if &signature != b"\x89PNG\r\n\x1a\n" {
+        if &signature != SIGNATURE {
             return Err(invalid_format!("invalid PNG header: {:?}", signature));
         }
 
-        // chunk length
-        let _ = r
-            .read_u32::<BigEndian>()
-            .map_err(if_eof!("when reading chunk length"))?;
+        let mut reader = ChunkReader::new(r).verify_crc(options.verify_crc);
 
-        let mut chunk_type = [0u8; 4];
-        r.read_exact(&mut chunk_type)
-            .map_err(if_eof!(std, "when reading chunk type"))?;
-
-        if &chunk_type != b"IHDR" {
-            return Err(invalid_format!("invalid PNG chunk: {:?}", chunk_type));
-        }
-
-        let width = r
-            .read_u32::<BigEndian>()
-            .map_err(if_eof!("when reading width"))?;
-        let height = r
-            .read_u32::<BigEndian>()
-            .map_err(if_eof!("when reading height"))?;
-        let bit_depth = r.read_u8().map_err(if_eof!("when reading bit depth"))?;
-        let color_type = r.read_u8().map_err(if_eof!("when reading color type"))?;
-        let compression_method = r
-            .read_u8()
-            .map_err(if_eof!("when reading compression method"))?;
-        let filter_method = r.read_u8().map_err(if_eof!("when reading filter method"))?;
-        let interlace_method = r
-            .read_u8()
-            .map_err(if_eof!("when reading interlace method"))?;
+        let ihdr = reader
+            .next()
+            .ok_or_else(|| unexpected_eof!("when reading IHDR chunk"))??;
+        if &ihdr.chunk_type.0 != b"IHDR" {
+            return Err(invalid_format!("invalid PNG chunk: {}", ihdr.chunk_type));
+        }
+        if ihdr.length < 13 {
+            return Err(invalid_format!(
+                "invalid IHDR chunk length: {}",
+                ihdr.length
+            ));
+        }
+        let data = reader.read_data()?;
+        let width = BigEndian::read_u32(&data[0..4]);
+        let height = BigEndian::read_u32(&data[4..8]);
+        let (bit_depth, color_type, compression_method, filter_method, interlace_method) =
+            (data[8], data[9], data[10], data[11], data[12]);
+
+        // walk through the rest of the chunks looking for metadata
+        let mut chunks = vec![ihdr];
+        let mut exif = None;
+        let mut xmp = None;
+        let mut icc_profile = None;
+        let mut texts = Vec::new();
+        let mut palette = None;
+        let mut physical_dimensions = None;
+        let mut gamma = None;
+        let mut chromaticities = None;
+        let mut srgb_intent = None;
+        let mut significant_bits = None;
+        let mut background_color = None;
+        let mut transparency = None;
+        let mut cicp = None;
+        let mut mastering_display_color_volume = None;
+        let mut content_light_level = None;
+        let mut animation = None;
+        let mut animation_valid = true;
+        let mut seen_idat = false;
+        while let Some(chunk) = reader.next() {
+            let chunk = chunk?;
+            match &chunk.chunk_type.0 {
+                b"IDAT" => seen_idat = true,
+                b"PLTE" if options.retain_palette && palette.is_none() => {
+                    let data = reader.read_data()?;
+                    palette = Some(
+                        data.chunks_exact(3)
+                            .map(|c| [c[0], c[1], c[2], 255])
+                            .collect::<Vec<_>>(),
+                    );
+                }
+                // invalid chunks below are ignored, similarly to other metadata
+                b"pHYs" if physical_dimensions.is_none() => {
+                    physical_dimensions = PhysicalDimensions::parse(&reader.read_data()?);
+                }
+                b"gAMA" if gamma.is_none() => {
+                    gamma = reader.read_data()?.get(0..4).map(BigEndian::read_u32);
+                }
+                b"cHRM" if chromaticities.is_none() => {
+                    chromaticities = Chromaticities::parse(&reader.read_data()?);
+                }
+                b"sRGB" if srgb_intent.is_none() => {
+                    srgb_intent = reader
+                        .read_data()?
+                        .first()
+                        .map(|&n| RenderingIntent::from_u32(n as u32));
+                }
+                b"sBIT" if significant_bits.is_none() => {
+                    let mut data = reader.read_data()?;
+                    significant_bits = significant_bits_count(color_type)
+                        .filter(|&n| data.len() >= n)
+                        .map(|n| {
+                            data.truncate(n);
+                            data
+                        });
+                }
+                b"bKGD" if background_color.is_none() => {
+                    background_color = BackgroundColor::parse(&reader.read_data()?, color_type);
+                }
+                b"tRNS" if transparency.is_none() => {
+                    transparency = Transparency::parse(&reader.read_data()?, color_type);
+                }
+                b"cICP" if cicp.is_none() => {
+                    cicp = Cicp::parse(&reader.read_data()?);
+                }
+                b"mDCv" if mastering_display_color_volume.is_none() => {
+                    mastering_display_color_volume =
+                        MasteringDisplayColorVolume::parse(&reader.read_data()?);
+                }
+                b"cLLi" if content_light_level.is_none() => {
+                    content_light_level = ContentLightLevel::parse(&reader.read_data()?);
+                }
+                // acTL must precede image data
+                b"acTL" if animation.is_none() && !seen_idat => {
+                    let data = reader.read_data()?;
+                    animation_valid = data.len() >= 8;
+                    animation = Some(Animation {
+                        num_frames: data.get(0..4).map_or(0, BigEndian::read_u32),
+                        num_plays: data.get(4..8).map_or(0, BigEndian::read_u32),
+                        default_image_is_first_frame: false,
+                        frames: Vec::new(),
+                    });
+                }
+                b"fcTL" => match (
+                    animation.as_mut(),
+                    FrameControl::parse(&reader.read_data()?),
+                ) {
+                    (Some(animation), Some(frame)) => {
+                        // the default image is a frame if its fcTL precedes image data
+                        if !seen_idat {
+                            animation.default_image_is_first_frame = true;
+                        }
+                        animation.frames.push(frame);
+                    }
+                    (Some(_), None) => animation_valid = false,
+                    (None, _) => {}
+                },
+                b"eXIf" if exif.is_none() => {
+                    // invalid EXIF data should not prevent loading the rest of the metadata
+                    exif = Exif::load(&reader.read_data()?).ok();
+                }
+                b"iCCP" if icc_profile.is_none() => {
+                    icc_profile = read_icc_profile(&reader.read_data()?);
+                }
+                b"tEXt" | b"zTXt" | b"iTXt" => {
+                    let kind = match &chunk.chunk_type.0 {
+                        b"tEXt" => TextKind::Text,
+                        b"zTXt" => TextKind::CompressedText,
+                        _ => TextKind::InternationalText,
+                    };
+                    // invalid text chunks should not prevent loading the rest of the metadata
+                    if let Some(text) = read_text_chunk(kind, &reader.read_data()?) {
+                        if xmp.is_none()
+                            && kind == TextKind::InternationalText
+                            && text.keyword == XMP_KEYWORD
+                        {
+                            xmp = xmp::decode(text.text.as_bytes());
+                        }
+                        texts.push(text);
+                    }
+                }
+                _ => {}
+            }
+            chunks.push(chunk);
+        }
+
+        // alpha values of palette entries are stored in a separate chunk
+        if let (Some(palette), Some(Transparency::Palette(alpha))) = (&mut palette, &transparency) {
+            for (entry, &a) in palette.iter_mut().zip(alpha) {
+                entry[3] = a;
+            }
+        }
 
         Ok(Metadata {
             dimensions: (width, height).into(),
@@ -232,6 +1281,24 @@ impl LoadableMetadata for Metadata {
                 "invalid interlace method: {}",
                 interlace_method
             ))?,
+            palette,
+            physical_dimensions,
+            gamma,
+            chromaticities,
+            srgb_intent,
+            significant_bits,
+            background_color,
+            transparency,
+            cicp,
+            mastering_display_color_volume,
+            content_light_level,
+            chunks,
+            // per the APNG spec, images with invalid animation chunks are displayed as static
+            animation: animation.filter(|_| animation_valid),
+            texts,
+            exif,
+            icc_profile,
+            xmp,
         })
     }
 }
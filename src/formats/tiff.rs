@@ -0,0 +1,553 @@
+//! Metadata of TIFF images.
+
+use std::fmt;
+use std::io::{BufRead, Cursor, Read, Seek};
+
+#[cfg(feature = "async")]
+use futures_io::AsyncBufRead;
+
+#[cfg(feature = "async")]
+use crate::asynchronous;
+use crate::common::exif::{Exif, Gps};
+use crate::common::tiff::{Ifd, TiffReader, Value};
+use crate::common::xmp;
+use crate::traits::LoadableMetadata;
+use crate::types::{Dimensions, Orientation, Result};
+
+/// Compression scheme used in an image.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Compression {
+    /// No compression.
+    None,
+    /// CCITT modified Huffman run length encoding.
+    CcittRle,
+    /// CCITT T.4 bi-level encoding (Group 3 fax).
+    CcittGroup3,
+    /// CCITT T.6 bi-level encoding (Group 4 fax).
+    CcittGroup4,
+    /// Lempel-Ziv-Welch compression.
+    Lzw,
+    /// JPEG compression as defined by TIFF 6.0, now obsolete.
+    OldJpeg,
+    /// JPEG compression as defined by TIFF Technical Note #2.
+    Jpeg,
+    /// Deflate compression, as registered by Adobe.
+    AdobeDeflate,
+    /// PackBits run length encoding.
+    PackBits,
+    /// Deflate compression, the original PKZIP-style code.
+    Deflate,
+    /// Unknown compression scheme.
+    Unknown(u16),
+}
+
+impl fmt::Display for Compression {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Compression::None => f.write_str("None"),
+            Compression::CcittRle => f.write_str("CCITT RLE"),
+            Compression::CcittGroup3 => f.write_str("CCITT Group 3"),
+            Compression::CcittGroup4 => f.write_str("CCITT Group 4"),
+            Compression::Lzw => f.write_str("LZW"),
+            Compression::OldJpeg => f.write_str("JPEG (old-style)"),
+            Compression::Jpeg => f.write_str("JPEG"),
+            Compression::AdobeDeflate => f.write_str("Deflate (Adobe)"),
+            Compression::PackBits => f.write_str("PackBits"),
+            Compression::Deflate => f.write_str("Deflate"),
+            Compression::Unknown(n) => write!(f, "Unknown ({n})"),
+        }
+    }
+}
+
+impl Compression {
+    fn from_u16(n: u16) -> Compression {
+        match n {
+            1 => Compression::None,
+            2 => Compression::CcittRle,
+            3 => Compression::CcittGroup3,
+            4 => Compression::CcittGroup4,
+            5 => Compression::Lzw,
+            6 => Compression::OldJpeg,
+            7 => Compression::Jpeg,
+            8 => Compression::AdobeDeflate,
+            32773 => Compression::PackBits,
+            32946 => Compression::Deflate,
+            n => Compression::Unknown(n),
+        }
+    }
+}
+
+/// Color space of the image data.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum PhotometricInterpretation {
+    /// Grayscale or bilevel, zero is imaged as white.
+    WhiteIsZero,
+    /// Grayscale or bilevel, zero is imaged as black.
+    BlackIsZero,
+    /// RGB color.
+    Rgb,
+    /// Indexed color, with a color map.
+    Palette,
+    /// A transparency mask for another image in the same file.
+    TransparencyMask,
+    /// Separated color, usually CMYK.
+    Separated,
+    /// YCbCr color.
+    YCbCr,
+    /// CIE L*a*b* color.
+    CieLab,
+    /// ICC L*a*b* color.
+    IccLab,
+    /// ITU L*a*b* color.
+    ItuLab,
+    /// Unknown color space.
+    Unknown(u16),
+}
+
+impl fmt::Display for PhotometricInterpretation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            PhotometricInterpretation::WhiteIsZero => f.write_str("White is zero"),
+            PhotometricInterpretation::BlackIsZero => f.write_str("Black is zero"),
+            PhotometricInterpretation::Rgb => f.write_str("RGB"),
+            PhotometricInterpretation::Palette => f.write_str("Palette"),
+            PhotometricInterpretation::TransparencyMask => f.write_str("Transparency mask"),
+            PhotometricInterpretation::Separated => f.write_str("Separated"),
+            PhotometricInterpretation::YCbCr => f.write_str("YCbCr"),
+            PhotometricInterpretation::CieLab => f.write_str("CIE L*a*b*"),
+            PhotometricInterpretation::IccLab => f.write_str("ICC L*a*b*"),
+            PhotometricInterpretation::ItuLab => f.write_str("ITU L*a*b*"),
+            PhotometricInterpretation::Unknown(n) => write!(f, "Unknown ({n})"),
+        }
+    }
+}
+
+impl PhotometricInterpretation {
+    fn from_u16(n: u16) -> PhotometricInterpretation {
+        match n {
+            0 => PhotometricInterpretation::WhiteIsZero,
+            1 => PhotometricInterpretation::BlackIsZero,
+            2 => PhotometricInterpretation::Rgb,
+            3 => PhotometricInterpretation::Palette,
+            4 => PhotometricInterpretation::TransparencyMask,
+            5 => PhotometricInterpretation::Separated,
+            6 => PhotometricInterpretation::YCbCr,
+            8 => PhotometricInterpretation::CieLab,
+            9 => PhotometricInterpretation::IccLab,
+            10 => PhotometricInterpretation::ItuLab,
+            n => PhotometricInterpretation::Unknown(n),
+        }
+    }
+}
+
+/// How the components of each pixel are stored.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum PlanarConfiguration {
+    /// Components of each pixel are stored contiguously, e.g. RGBRGBRGB.
+    Chunky,
+    /// Components are stored in separate planes, e.g. RRR...GGG...BBB.
+    Planar,
+    /// Unknown planar configuration.
+    Unknown(u16),
+}
+
+impl fmt::Display for PlanarConfiguration {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            PlanarConfiguration::Chunky => f.write_str("Chunky"),
+            PlanarConfiguration::Planar => f.write_str("Planar"),
+            PlanarConfiguration::Unknown(n) => write!(f, "Unknown ({n})"),
+        }
+    }
+}
+
+impl PlanarConfiguration {
+    fn from_u16(n: u16) -> PlanarConfiguration {
+        match n {
+            1 => PlanarConfiguration::Chunky,
+            2 => PlanarConfiguration::Planar,
+            n => PlanarConfiguration::Unknown(n),
+        }
+    }
+}
+
+/// Describes how image data is split into pieces.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum DataLayout {
+    /// Image data is stored in strips, each containing several rows.
+    Strips {
+        /// Number of rows in each strip, except possibly the last one.
+        rows_per_strip: u32,
+        /// Total number of strips.
+        strips_number: u64,
+    },
+    /// Image data is stored in tiles.
+    Tiles {
+        /// Size of each tile.
+        tile_dimensions: Dimensions,
+        /// Total number of tiles.
+        tiles_number: u64,
+    },
+}
+
+/// Unit of measurement for image resolution.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum ResolutionUnit {
+    /// No absolute unit; the resolution only defines the aspect ratio.
+    None,
+    /// Pixels per inch.
+    Inch,
+    /// Pixels per centimeter.
+    Centimeter,
+    /// Unknown resolution unit.
+    Unknown(u16),
+}
+
+impl fmt::Display for ResolutionUnit {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ResolutionUnit::None => f.write_str("None"),
+            ResolutionUnit::Inch => f.write_str("Inch"),
+            ResolutionUnit::Centimeter => f.write_str("Centimeter"),
+            ResolutionUnit::Unknown(n) => write!(f, "Unknown ({n})"),
+        }
+    }
+}
+
+impl ResolutionUnit {
+    fn from_u16(n: u16) -> ResolutionUnit {
+        match n {
+            1 => ResolutionUnit::None,
+            2 => ResolutionUnit::Inch,
+            3 => ResolutionUnit::Centimeter,
+            n => ResolutionUnit::Unknown(n),
+        }
+    }
+}
+
+/// Contains physical resolution of an image.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct Resolution {
+    /// Number of pixels per resolution unit in the horizontal direction, as a fraction.
+    pub x: (u32, u32),
+    /// Number of pixels per resolution unit in the vertical direction, as a fraction.
+    pub y: (u32, u32),
+    /// Unit of measurement for `x` and `y`.
+    pub unit: ResolutionUnit,
+}
+
+impl Resolution {
+    /// Computes horizontal resolution as a floating point number.
+    #[inline]
+    pub fn x_approx(&self) -> f64 {
+        self.x.0 as f64 / self.x.1 as f64
+    }
+
+    /// Computes vertical resolution as a floating point number.
+    #[inline]
+    pub fn y_approx(&self) -> f64 {
+        self.y.0 as f64 / self.y.1 as f64
+    }
+}
+
+/// Contains metadata of a single page (image file directory) of a TIFF image.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Page {
+    /// Page size.
+    pub dimensions: Dimensions,
+    /// Number of bits in each component of a pixel.
+    pub bits_per_sample: Vec<u16>,
+    /// Number of components in each pixel.
+    pub samples_per_pixel: u16,
+    /// Compression scheme used for image data.
+    pub compression: Compression,
+    /// Color space of image data.
+    ///
+    /// This field is required by the spec, but some writers omit it.
+    pub photometric_interpretation: Option<PhotometricInterpretation>,
+    /// How the components of each pixel are stored.
+    pub planar_configuration: PlanarConfiguration,
+    /// How image data is split into strips or tiles.
+    pub data_layout: DataLayout,
+    /// Physical resolution, if it is present.
+    pub resolution: Option<Resolution>,
+    /// How the page should be transformed to be displayed, if it is present and valid.
+    pub orientation: Option<Orientation>,
+    /// XMP packet, if it is present and is a valid UTF-8 string.
+    pub xmp: Option<String>,
+    /// Embedded ICC profile, if it is present.
+    pub icc_profile: Option<Vec<u8>>,
+}
+
+impl Page {
+    /// Computes color depth (bits per pixel) of the page.
+    #[inline]
+    pub fn color_depth(&self) -> u32 {
+        self.bits_per_sample.iter().map(|&b| b as u32).sum()
+    }
+
+    fn load<R: Read + Seek>(index: usize, ifd: Ifd<R>) -> Result<Page> {
+        let mut width = None;
+        let mut height = None;
+        let mut bits_per_sample = None;
+        let mut samples_per_pixel = 1;
+        let mut compression = Compression::None;
+        let mut photometric_interpretation = None;
+        let mut planar_configuration = PlanarConfiguration::Chunky;
+        let mut rows_per_strip = u32::MAX;
+        let mut strips_number = None;
+        let mut tile_width = None;
+        let mut tile_height = None;
+        let mut tiles_number = None;
+        let mut x_resolution = None;
+        let mut y_resolution = None;
+        let mut resolution_unit = ResolutionUnit::Inch;
+        let mut orientation = None;
+        let mut xmp = None;
+        let mut icc_profile = None;
+
+        for entry in ifd {
+            let entry = entry?;
+
+            // offsets arrays may be huge, but only their lengths are needed
+            match entry.tag() {
+                STRIP_OFFSETS => {
+                    strips_number = Some(entry.count());
+                    continue;
+                }
+                TILE_OFFSETS => {
+                    tiles_number = Some(entry.count());
+                    continue;
+                }
+                IMAGE_WIDTH
+                | IMAGE_LENGTH
+                | BITS_PER_SAMPLE
+                | COMPRESSION
+                | PHOTOMETRIC_INTERPRETATION
+                | ORIENTATION
+                | SAMPLES_PER_PIXEL
+                | ROWS_PER_STRIP
+                | X_RESOLUTION
+                | Y_RESOLUTION
+                | PLANAR_CONFIGURATION
+                | RESOLUTION_UNIT
+                | TILE_WIDTH
+                | TILE_LENGTH
+                | XMP
+                | ICC_PROFILE => {}
+                _ => continue,
+            }
+
+            let value = match entry.value() {
+                Some(value) => value?,
+                None => continue,
+            };
+
+            let invalid_value = || {
+                invalid_format!(
+                    "invalid value of tag {} in TIFF page {}: {:?}",
+                    entry.tag(),
+                    index,
+                    value
+                )
+            };
+            let number = || value.as_u32().ok_or_else(invalid_value);
+            let rational = || match value {
+                Value::Rational(ref v) if !v.is_empty() && v[0].1 != 0 => Ok(v[0]),
+                _ => Err(invalid_value()),
+            };
+
+            match entry.tag() {
+                IMAGE_WIDTH => width = Some(number()?),
+                IMAGE_LENGTH => height = Some(number()?),
+                BITS_PER_SAMPLE => {
+                    bits_per_sample = Some(match value {
+                        Value::Short(ref v) => v.clone(),
+                        _ => return Err(invalid_value()),
+                    })
+                }
+                COMPRESSION => compression = Compression::from_u16(number()? as u16),
+                PHOTOMETRIC_INTERPRETATION => {
+                    photometric_interpretation =
+                        Some(PhotometricInterpretation::from_u16(number()? as u16))
+                }
+                ORIENTATION => orientation = Orientation::from_exif(number()? as u16),
+                SAMPLES_PER_PIXEL => samples_per_pixel = number()? as u16,
+                ROWS_PER_STRIP => rows_per_strip = number()?,
+                X_RESOLUTION => x_resolution = Some(rational()?),
+                Y_RESOLUTION => y_resolution = Some(rational()?),
+                PLANAR_CONFIGURATION => {
+                    planar_configuration = PlanarConfiguration::from_u16(number()? as u16)
+                }
+                RESOLUTION_UNIT => resolution_unit = ResolutionUnit::from_u16(number()? as u16),
+                TILE_WIDTH => tile_width = Some(number()?),
+                TILE_LENGTH => tile_height = Some(number()?),
+                // invalid XMP data should not prevent loading the rest of the metadata
+                XMP => xmp = value.as_bytes().and_then(xmp::decode),
+                ICC_PROFILE => icc_profile = value.as_bytes().map(<[u8]>::to_vec),
+                _ => {}
+            }
+        }
+
+        let width = width.ok_or_else(|| invalid_format!("TIFF page {} has no width", index))?;
+        let height = height.ok_or_else(|| invalid_format!("TIFF page {} has no height", index))?;
+
+        let data_layout = match (tile_width, tile_height, tiles_number) {
+            (Some(w), Some(h), Some(n)) => DataLayout::Tiles {
+                tile_dimensions: (w, h).into(),
+                tiles_number: n,
+            },
+            _ => DataLayout::Strips {
+                rows_per_strip,
+                strips_number: strips_number
+                    .ok_or_else(|| invalid_format!("TIFF page {} has no data offsets", index))?,
+            },
+        };
+
+        let resolution = match (x_resolution, y_resolution) {
+            (Some(x), Some(y)) => Some(Resolution {
+                x,
+                y,
+                unit: resolution_unit,
+            }),
+            _ => None,
+        };
+
+        Ok(Page {
+            dimensions: (width, height).into(),
+            bits_per_sample: bits_per_sample.unwrap_or_else(|| vec![1; samples_per_pixel as usize]),
+            samples_per_pixel,
+            compression,
+            photometric_interpretation,
+            planar_configuration,
+            data_layout,
+            resolution,
+            orientation,
+            xmp,
+            icc_profile,
+        })
+    }
+}
+
+const IMAGE_WIDTH: u16 = 256;
+const IMAGE_LENGTH: u16 = 257;
+const BITS_PER_SAMPLE: u16 = 258;
+const COMPRESSION: u16 = 259;
+const PHOTOMETRIC_INTERPRETATION: u16 = 262;
+const STRIP_OFFSETS: u16 = 273;
+const ORIENTATION: u16 = 274;
+const SAMPLES_PER_PIXEL: u16 = 277;
+const ROWS_PER_STRIP: u16 = 278;
+const X_RESOLUTION: u16 = 282;
+const Y_RESOLUTION: u16 = 283;
+const PLANAR_CONFIGURATION: u16 = 284;
+const RESOLUTION_UNIT: u16 = 296;
+const TILE_WIDTH: u16 = 322;
+const TILE_LENGTH: u16 = 323;
+const TILE_OFFSETS: u16 = 324;
+const XMP: u16 = 700;
+const ICC_PROFILE: u16 = 34675;
+
+/// Represents metadata of a TIFF image.
+///
+/// A TIFF file may contain several images (pages), each described by its own image file
+/// directory. Image dimensions are taken from the first page.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Metadata {
+    /// Whether the file is a BigTIFF file, i.e. it uses 64-bit offsets.
+    pub big_tiff: bool,
+    /// Metadata of each page of the image, in the order they are stored in the file.
+    pub pages: Vec<Page>,
+    /// EXIF metadata, if the first page references EXIF or GPS sub-IFDs.
+    ///
+    /// IFD0 of the EXIF metadata is the first page directory, and IFD1 is never set.
+    pub exif: Option<Exif>,
+}
+
+impl Metadata {
+    /// Returns dimensions of the first page.
+    #[inline]
+    pub fn dimensions(&self) -> Dimensions {
+        self.pages[0].dimensions
+    }
+
+    /// Returns the number of pages in the image.
+    #[inline]
+    pub fn pages_number(&self) -> usize {
+        self.pages.len()
+    }
+
+    /// Returns orientation of the first page, if it is present and valid.
+    #[inline]
+    pub fn orientation(&self) -> Option<Orientation> {
+        self.pages[0].orientation
+    }
+
+    /// Returns GPS information from EXIF metadata, if it is present.
+    #[inline]
+    pub fn gps(&self) -> Option<Gps> {
+        self.exif.as_ref().and_then(Exif::gps)
+    }
+
+    /// Returns XMP packet of the first page, if it is present and valid.
+    #[inline]
+    pub fn xmp(&self) -> Option<&str> {
+        self.pages[0].xmp.as_deref()
+    }
+
+    /// Returns ICC profile of the first page, if it is present.
+    #[inline]
+    pub fn icc_profile(&self) -> Option<&[u8]> {
+        self.pages[0].icc_profile.as_deref()
+    }
+}
+
+impl LoadableMetadata for Metadata {
+    fn load<R: ?Sized + BufRead>(r: &mut R) -> Result<Metadata> {
+        // IFDs may be located anywhere in the file, so the whole file has to be buffered
+        let mut data = Vec::new();
+        r.read_to_end(&mut data)?;
+        Metadata::load_from_seek(&mut Cursor::new(data))
+    }
+
+    #[cfg(feature = "async")]
+    async fn load_async<R: ?Sized + AsyncBufRead + Unpin>(r: &mut R) -> Result<Metadata> {
+        // IFDs may be located anywhere in the file, so the whole file has to be buffered
+        let mut data = Vec::new();
+        asynchronous::read_to_end(r, &mut data).await?;
+        Metadata::load_from_buf(&data)
+    }
+
+    fn load_from_seek<R: ?Sized + BufRead + Seek>(r: &mut R) -> Result<Metadata> {
+        let ifds = TiffReader::new(r).ifds()?;
+
+        let mut pages = Vec::new();
+        let mut offsets = Vec::new();
+        for (index, ifd) in (&ifds).into_iter().enumerate() {
+            let ifd = ifd?;
+
+            // malformed files may contain loops in the IFD chain
+            if offsets.contains(&ifd.offset()) {
+                return Err(invalid_format!("TIFF page {} is a repeated IFD", index));
+            }
+            offsets.push(ifd.offset());
+
+            pages.push(Page::load(index, ifd)?);
+        }
+
+        if pages.is_empty() {
+            return Err(invalid_format!("TIFF image does not contain any pages"));
+        }
+
+        // invalid EXIF data should not prevent loading the rest of the metadata
+        let exif = Exif::from_ifd_at(&ifds, offsets[0])
+            .ok()
+            .filter(|e| e.exif_ifd.is_some() || e.gps_ifd.is_some());
+
+        Ok(Metadata {
+            big_tiff: ifds.is_big_tiff(),
+            pages,
+            exif,
+        })
+    }
+}
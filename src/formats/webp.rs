@@ -1,9 +1,17 @@
+//! Metadata of WebP images.
+
 use std::io::BufRead;
 
-use crate::common::riff::{ChunkId, RiffChunk, RiffReader};
+use byteorder::{LittleEndian, ReadBytesExt};
+
+use crate::common::exif::{Exif, Gps};
+use crate::common::riff::{ChunkId, RiffChunk, RiffListChunk, RiffReader};
+use crate::common::xmp;
 use crate::traits::LoadableMetadata;
-use crate::types::{Dimensions, Result};
+use crate::types::{Dimensions, Orientation, Result};
+use crate::utils::ReadExt;
 
+#[allow(clippy::large_enum_variant)]
 #[derive(Clone, Eq, PartialEq, Debug)]
 pub enum Metadata {
     VP8(VP8Metadata),
@@ -29,28 +37,259 @@ pub enum VP8Frame {
     Inter,
 }
 
+/// Contains metadata of a lossless (VP8L) WebP image.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct VP8LMetadata {
+    /// Version number of the lossless bitstream; only zero is defined by the spec.
+    pub version_number: u8,
+    /// Image size, each dimension is at most 16384 pixels.
+    pub dimensions: Dimensions,
+    /// A hint that the image uses the alpha channel.
+    ///
+    /// The spec requires this flag to be unset when all alpha values are 255, but decoders
+    /// must not depend on it.
+    pub alpha_is_used: bool,
+}
+
+/// Contains metadata of an extended (VP8X) WebP image.
+///
+/// Extended format images start with a VP8X chunk which describes the canvas and
+/// announces which optional chunks are present in the file.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct VP8XMetadata {
+    /// Size of the canvas the image (or the animation frames) is rendered onto.
+    pub canvas_dimensions: Dimensions,
+    /// Whether the file contains an ICC profile (ICCP chunk).
+    pub has_icc_profile: bool,
+    /// Whether any of the frames contain transparency information.
+    pub has_alpha: bool,
+    /// Whether the file contains EXIF metadata (EXIF chunk).
+    pub has_exif: bool,
+    /// Whether the file contains XMP metadata (XMP chunk).
+    pub has_xmp: bool,
+    /// Whether the image is animated (ANIM and ANMF chunks).
+    pub has_animation: bool,
+    /// Animation parameters and frames, if the file contains an animation.
+    pub animation: Option<Animation>,
+    /// Contents of the ICCP chunk, if it is present.
+    pub icc_profile_chunk: Option<ChunkData>,
+    /// Contents of the EXIF chunk, if it is present.
+    pub exif_chunk: Option<ChunkData>,
+    /// Contents of the XMP chunk, if it is present.
+    pub xmp_chunk: Option<ChunkData>,
+    /// EXIF metadata parsed from the EXIF chunk.
+    ///
+    /// This field is `None` if there is no EXIF chunk or if its contents are not a valid
+    /// EXIF structure; in the latter case the raw data is still available in `exif_chunk`.
+    pub exif: Option<Exif>,
+    /// XMP packet decoded from the XMP chunk.
+    ///
+    /// This field is `None` if there is no XMP chunk or if its contents are not valid UTF-8.
+    pub xmp: Option<String>,
+}
+
+/// Contains raw contents of a metadata chunk.
 #[derive(Clone, Eq, PartialEq, Debug)]
-pub struct VP8LMetadata;
+pub struct ChunkData {
+    /// Offset of the chunk payload from the beginning of the file, in bytes.
+    pub offset: u64,
+    /// Chunk payload.
+    pub data: Vec<u8>,
+}
 
+/// Contains global animation parameters and metadata of each animation frame.
 #[derive(Clone, Eq, PartialEq, Debug)]
-pub struct VP8XMetadata;
+pub struct Animation {
+    /// Default background color of the canvas, in RGBA order.
+    ///
+    /// The spec says that this color may be ignored and is only a hint.
+    pub background_color: [u8; 4],
+    /// Number of times to loop the animation, zero means infinitely.
+    pub loop_count: u16,
+    /// Metadata for each frame of the animation.
+    pub frames: Vec<AnimationFrame>,
+}
+
+impl Animation {
+    /// Computes the total duration of the animation in milliseconds, i.e. the sum of
+    /// durations of all frames.
+    ///
+    /// Loop count is not taken into account.
+    #[inline]
+    pub fn total_duration_ms(&self) -> u64 {
+        self.frames.iter().map(|f| f.duration as u64).sum()
+    }
+}
+
+/// Contains metadata of a single animation frame (ANMF chunk).
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct AnimationFrame {
+    /// Horizontal offset of the frame on the canvas.
+    pub left: u32,
+    /// Vertical offset of the frame on the canvas.
+    pub top: u32,
+    /// Frame size.
+    pub dimensions: Dimensions,
+    /// Time to wait before displaying the next frame, in milliseconds.
+    pub duration: u32,
+    /// How transparent pixels of this frame are blended with the canvas.
+    pub blending_method: BlendingMethod,
+    /// How the frame area is treated before rendering the next frame.
+    pub disposal_method: DisposalMethod,
+    /// Type of the bitstream the frame is encoded with.
+    pub bitstream: Bitstream,
+    /// Whether the frame has a separate alpha (ALPH) chunk.
+    pub has_alpha_chunk: bool,
+}
+
+/// Describes blending methods used for WebP animation frames.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum BlendingMethod {
+    /// The frame is alpha-blended over the current canvas.
+    AlphaBlending,
+    /// The frame overwrites the rectangle on the canvas.
+    NoBlending,
+}
+
+/// Describes disposal methods used for WebP animation frames.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum DisposalMethod {
+    /// The canvas is left as is.
+    None,
+    /// The frame area is filled with the background color.
+    DisposeToBackground,
+}
+
+/// Type of the bitstream an image or a frame is encoded with.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum Bitstream {
+    /// Lossy bitstream (VP8 chunk).
+    Lossy,
+    /// Lossless bitstream (VP8L chunk).
+    Lossless,
+}
 
 const WEBP_CHUNK_TYPE: ChunkId = ChunkId([b'W', b'E', b'B', b'P']);
 const ALPH_CHUNK_ID: ChunkId = ChunkId([b'A', b'L', b'P', b'H']);
 const VP8_CHUNK_ID: ChunkId = ChunkId([b'V', b'P', b'8', b' ']);
 const VP8L_CHUNK_ID: ChunkId = ChunkId([b'V', b'P', b'8', b'L']);
 const VP8X_CHUNK_ID: ChunkId = ChunkId([b'V', b'P', b'8', b'X']);
+const ANIM_CHUNK_ID: ChunkId = ChunkId([b'A', b'N', b'I', b'M']);
+const ANMF_CHUNK_ID: ChunkId = ChunkId([b'A', b'N', b'M', b'F']);
+const ICCP_CHUNK_ID: ChunkId = ChunkId([b'I', b'C', b'C', b'P']);
+const EXIF_CHUNK_ID: ChunkId = ChunkId([b'E', b'X', b'I', b'F']);
+const XMP_CHUNK_ID: ChunkId = ChunkId([b'X', b'M', b'P', b' ']);
+
+// size of the RIFF header together with the WEBP chunk type
+const WEBP_HEADER_SIZE: u64 = 12;
 
 impl Metadata {
+    /// Returns image dimensions.
+    ///
+    /// For extended format images this is the size of the canvas.
     pub fn dimensions(&self) -> Dimensions {
         match *self {
             Metadata::VP8(VP8Metadata {
                 frame: VP8Frame::Key { dimensions, .. },
                 ..
             }) => dimensions,
-            _ => unimplemented!(),
+            // inter frames carry no dimensions, but they are rejected by the loader anyway
+            Metadata::VP8(VP8Metadata {
+                frame: VP8Frame::Inter,
+                ..
+            }) => (0, 0).into(),
+            Metadata::VP8L(ref md) => md.dimensions,
+            Metadata::VP8X(ref md) => md.canvas_dimensions,
+        }
+    }
+
+    /// Returns animation parameters and frames if the image is animated.
+    #[inline]
+    pub fn animation(&self) -> Option<&Animation> {
+        match *self {
+            Metadata::VP8X(ref md) => md.animation.as_ref(),
+            _ => None,
+        }
+    }
+
+    /// Computes the number of frames.
+    ///
+    /// Images which are not animated always consist of exactly one frame.
+    #[inline]
+    pub fn frames_number(&self) -> usize {
+        self.animation().map_or(1, |a| a.frames.len())
+    }
+
+    /// Returns `true` if the image is animated, `false` otherwise.
+    ///
+    /// Similarly to GIF images, an image is considered animated if it has more than one frame.
+    #[inline]
+    pub fn is_animated(&self) -> bool {
+        self.frames_number() > 1
+    }
+
+    /// Returns raw contents of the ICCP chunk, if it is present.
+    #[inline]
+    pub fn icc_profile_chunk(&self) -> Option<&ChunkData> {
+        match *self {
+            Metadata::VP8X(ref md) => md.icc_profile_chunk.as_ref(),
+            _ => None,
+        }
+    }
+
+    /// Returns the ICC profile, if it is present.
+    #[inline]
+    pub fn icc_profile(&self) -> Option<&[u8]> {
+        self.icc_profile_chunk().map(|c| &c.data[..])
+    }
+
+    /// Returns raw contents of the EXIF chunk, if it is present.
+    #[inline]
+    pub fn exif_chunk(&self) -> Option<&ChunkData> {
+        match *self {
+            Metadata::VP8X(ref md) => md.exif_chunk.as_ref(),
+            _ => None,
+        }
+    }
+
+    /// Returns raw contents of the XMP chunk, if it is present.
+    #[inline]
+    pub fn xmp_chunk(&self) -> Option<&ChunkData> {
+        match *self {
+            Metadata::VP8X(ref md) => md.xmp_chunk.as_ref(),
+            _ => None,
+        }
+    }
+
+    /// Returns EXIF metadata, if it is present and valid.
+    #[inline]
+    pub fn exif(&self) -> Option<&Exif> {
+        match *self {
+            Metadata::VP8X(ref md) => md.exif.as_ref(),
+            _ => None,
+        }
+    }
+
+    /// Returns XMP packet, if it is present and valid.
+    #[inline]
+    pub fn xmp(&self) -> Option<&str> {
+        match *self {
+            Metadata::VP8X(ref md) => md.xmp.as_deref(),
+            _ => None,
         }
     }
+
+    /// Returns orientation from EXIF metadata, if it is present and valid.
+    #[inline]
+    pub fn orientation(&self) -> Option<Orientation> {
+        self.exif().and_then(Exif::orientation)
+    }
+
+    /// Returns GPS information from EXIF metadata, if it is present.
+    #[inline]
+    pub fn gps(&self) -> Option<Gps> {
+        self.exif().and_then(Exif::gps)
+    }
 }
 
 impl LoadableMetadata for Metadata {
@@ -62,17 +301,26 @@ impl LoadableMetadata for Metadata {
             return Err(invalid_format!("invalid WEBP signature"));
         }
 
-        let mut chunk = match root.next() {
-            Some(c) => c?,
-            None => return Err(unexpected_eof!("when reading first WEBP chunk")),
-        };
+        let mut offset = WEBP_HEADER_SIZE;
+        loop {
+            let mut chunk = match root.next() {
+                Some(c) => c?,
+                None => return Err(unexpected_eof!("when reading first WEBP chunk")),
+            };
+            offset += chunk_size(chunk.len());
 
-        match chunk.chunk_id() {
-            VP8_CHUNK_ID => read_vp8_chunk(&mut chunk).map(Metadata::VP8),
-            VP8L_CHUNK_ID => Err(invalid_format!("unsupported (yet) VP8 chunk id")),
-            VP8X_CHUNK_ID => Err(invalid_format!("unsupported (yet) VP8 chunk id")),
-            ALPH_CHUNK_ID => Err(invalid_format!("unsupported (yet) VP8 chunk id")),
-            cid => Err(invalid_format!("invalid WEBP chunk id: {}", cid)),
+            return match chunk.chunk_id() {
+                VP8_CHUNK_ID => read_vp8_chunk(&mut chunk).map(Metadata::VP8),
+                VP8L_CHUNK_ID => read_vp8l_chunk(&mut chunk).map(Metadata::VP8L),
+                VP8X_CHUNK_ID => {
+                    let mut md = read_vp8x_chunk(&mut chunk)?;
+                    read_extended_chunks(&mut root, offset, &mut md)?;
+                    Ok(Metadata::VP8X(md))
+                }
+                // some encoders put an alpha chunk in front of a simple format bitstream
+                ALPH_CHUNK_ID => continue,
+                cid => Err(invalid_format!("invalid WEBP chunk id: {}", cid)),
+            };
         }
     }
 }
@@ -136,7 +384,246 @@ fn read_vp8_chunk(chunk: &mut RiffChunk) -> Result<VP8Metadata> {
             x_scale,
             y_scale,
         };
+    } else {
+        return Err(invalid_format!(
+            "VP8 bitstream does not start with a key frame"
+        ));
     }
 
     Ok(result)
 }
+
+fn read_vp8l_chunk(chunk: &mut RiffChunk) -> Result<VP8LMetadata> {
+    let r = chunk.contents();
+
+    let signature = try_if_eof!(r.read_u8(), "when reading VP8L signature");
+    if signature != 0x2f {
+        return Err(invalid_format!("invalid VP8L signature: 0x{:X}", signature));
+    }
+
+    // next four bytes, as a little endian number, contain (from the least significant bit):
+    //    14 bits  --  image width minus one
+    //    14 bits  --  image height minus one
+    //     1 bit   --  alpha is used hint
+    //     3 bits  --  version number
+    let hdr = try_if_eof!(
+        r.read_u32::<LittleEndian>(),
+        "when reading VP8L image header"
+    );
+
+    let width = (hdr & 0x3fff) + 1;
+    let height = ((hdr >> 14) & 0x3fff) + 1;
+    let alpha_is_used = (hdr >> 28) & 1 == 1;
+    let version_number = (hdr >> 29) as u8;
+
+    if version_number != 0 {
+        return Err(invalid_format!(
+            "invalid VP8L version number: {}",
+            version_number
+        ));
+    }
+
+    Ok(VP8LMetadata {
+        version_number,
+        dimensions: (width, height).into(),
+        alpha_is_used,
+    })
+}
+
+fn read_vp8x_chunk(chunk: &mut RiffChunk) -> Result<VP8XMetadata> {
+    let r = chunk.contents();
+
+    // flags byte:
+    //    rrilexar
+    // where
+    //    r  --  reserved
+    //    i  --  ICC profile
+    //    l  --  alpha
+    //    e  --  EXIF metadata
+    //    x  --  XMP metadata
+    //    a  --  animation
+    let flags = try_if_eof!(r.read_u8(), "when reading VP8X flags");
+
+    // three reserved bytes follow the flags
+    let mut reserved = [0u8; 3];
+    r.read_exact(&mut reserved)
+        .map_err(if_eof!(std, "when reading VP8X header"))?;
+
+    let width = try_if_eof!(
+        r.read_u24::<LittleEndian>(),
+        "when reading VP8X canvas width"
+    ) + 1;
+    let height = try_if_eof!(
+        r.read_u24::<LittleEndian>(),
+        "when reading VP8X canvas height"
+    ) + 1;
+
+    Ok(VP8XMetadata {
+        canvas_dimensions: (width, height).into(),
+        has_icc_profile: flags & 0b00100000 != 0,
+        has_alpha: flags & 0b00010000 != 0,
+        has_exif: flags & 0b00001000 != 0,
+        has_xmp: flags & 0b00000100 != 0,
+        has_animation: flags & 0b00000010 != 0,
+        animation: None,
+        icc_profile_chunk: None,
+        exif_chunk: None,
+        xmp_chunk: None,
+        exif: None,
+        xmp: None,
+    })
+}
+
+// chunk header and padding are included into the total chunk size
+#[inline]
+fn chunk_size(len: u32) -> u64 {
+    8 + len as u64 + len as u64 % 2
+}
+
+fn read_chunk_data(chunk: &mut RiffChunk, offset: u64) -> Result<ChunkData> {
+    let len = chunk.len();
+    let data = chunk.contents().read_to_vec()?;
+    if data.len() != len as usize {
+        return Err(unexpected_eof!(
+            "when reading contents of {} chunk",
+            chunk.chunk_id()
+        ));
+    }
+    Ok(ChunkData {
+        offset: offset + 8,
+        data,
+    })
+}
+
+fn read_extended_chunks(
+    root: &mut RiffListChunk,
+    mut offset: u64,
+    md: &mut VP8XMetadata,
+) -> Result<()> {
+    while let Some(chunk) = root.next() {
+        let mut chunk = chunk?;
+        let chunk_offset = offset;
+        offset += chunk_size(chunk.len());
+
+        match chunk.chunk_id() {
+            ICCP_CHUNK_ID => {
+                md.icc_profile_chunk = Some(read_chunk_data(&mut chunk, chunk_offset)?)
+            }
+            EXIF_CHUNK_ID => {
+                let exif_chunk = read_chunk_data(&mut chunk, chunk_offset)?;
+                md.exif = Exif::load(&exif_chunk.data).ok();
+                md.exif_chunk = Some(exif_chunk);
+            }
+            XMP_CHUNK_ID => {
+                let xmp_chunk = read_chunk_data(&mut chunk, chunk_offset)?;
+                md.xmp = xmp::decode(&xmp_chunk.data);
+                md.xmp_chunk = Some(xmp_chunk);
+            }
+            ANIM_CHUNK_ID => md.animation = Some(read_anim_chunk(&mut chunk)?),
+            ANMF_CHUNK_ID => {
+                let frame = read_anmf_chunk(&mut chunk)?;
+                match md.animation {
+                    Some(ref mut animation) => animation.frames.push(frame),
+                    None => return Err(invalid_format!("ANMF chunk precedes ANIM chunk")),
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+fn read_anim_chunk(chunk: &mut RiffChunk) -> Result<Animation> {
+    let r = chunk.contents();
+
+    // the color is stored in BGRA order
+    let mut bgra = [0u8; 4];
+    r.read_exact(&mut bgra)
+        .map_err(if_eof!(std, "when reading ANIM background color"))?;
+
+    let loop_count = try_if_eof!(r.read_u16::<LittleEndian>(), "when reading ANIM loop count");
+
+    Ok(Animation {
+        background_color: [bgra[2], bgra[1], bgra[0], bgra[3]],
+        loop_count,
+        frames: Vec::new(),
+    })
+}
+
+fn read_anmf_chunk(chunk: &mut RiffChunk) -> Result<AnimationFrame> {
+    let r = chunk.contents();
+
+    // offsets are stored divided by two
+    let left = try_if_eof!(
+        r.read_u24::<LittleEndian>(),
+        "when reading ANMF frame X offset"
+    ) * 2;
+    let top = try_if_eof!(
+        r.read_u24::<LittleEndian>(),
+        "when reading ANMF frame Y offset"
+    ) * 2;
+    let width = try_if_eof!(
+        r.read_u24::<LittleEndian>(),
+        "when reading ANMF frame width"
+    ) + 1;
+    let height = try_if_eof!(
+        r.read_u24::<LittleEndian>(),
+        "when reading ANMF frame height"
+    ) + 1;
+    let duration = try_if_eof!(
+        r.read_u24::<LittleEndian>(),
+        "when reading ANMF frame duration"
+    );
+
+    // flags byte:
+    //    rrrrrrbd
+    // where
+    //    r  --  reserved
+    //    b  --  blending method, 1 means do not blend
+    //    d  --  disposal method, 1 means dispose to background color
+    let flags = try_if_eof!(r.read_u8(), "when reading ANMF frame flags");
+
+    // frame data consists of an optional ALPH chunk followed by a VP8 or VP8L chunk
+    let mut has_alpha_chunk = false;
+    let bitstream = loop {
+        let mut id = [0u8; 4];
+        r.read_exact(&mut id)
+            .map_err(if_eof!(std, "when reading ANMF frame data chunk id"))?;
+        let len = try_if_eof!(
+            r.read_u32::<LittleEndian>(),
+            "when reading ANMF frame data chunk length"
+        );
+
+        match ChunkId(id) {
+            ALPH_CHUNK_ID if !has_alpha_chunk => {
+                has_alpha_chunk = true;
+                let to_skip = len as u64 + len as u64 % 2;
+                if r.skip_exact_0(to_skip)? != to_skip {
+                    return Err(unexpected_eof!("when reading ANMF frame alpha data"));
+                }
+            }
+            VP8_CHUNK_ID => break Bitstream::Lossy,
+            VP8L_CHUNK_ID => break Bitstream::Lossless,
+            cid => return Err(invalid_format!("invalid ANMF frame data chunk id: {}", cid)),
+        }
+    };
+
+    Ok(AnimationFrame {
+        left,
+        top,
+        dimensions: (width, height).into(),
+        duration,
+        blending_method: if flags & 0b10 != 0 {
+            BlendingMethod::NoBlending
+        } else {
+            BlendingMethod::AlphaBlending
+        },
+        disposal_method: if flags & 0b01 != 0 {
+            DisposalMethod::DisposeToBackground
+        } else {
+            DisposalMethod::None
+        },
+        bitstream,
+        has_alpha_chunk,
+    })
+}
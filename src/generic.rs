@@ -1,12 +1,21 @@
+use std::fmt;
 use std::fs::File;
-use std::io::{BufRead, BufReader, Cursor, Seek};
+use std::io::{BufRead, BufReader, Cursor, Read, Seek, SeekFrom};
 use std::path::Path;
 use std::result;
 
-use crate::formats::{gif, jpeg, png, webp};
+use byteorder::{BigEndian, ByteOrder};
+#[cfg(feature = "async")]
+use futures_io::{AsyncBufRead, AsyncSeek};
+
+#[cfg(feature = "async")]
+use crate::asynchronous;
+use crate::common::exif::{Exif, Gps};
+use crate::formats::{avif, bmp, gif, heif, ico, jpeg, png, tiff, webp};
 use crate::generic::markers::MetadataMarker;
 use crate::traits::LoadableMetadata;
-use crate::types::{Dimensions, Result};
+use crate::types::{Dimensions, Orientation, Result};
+use crate::utils::ReadExt;
 
 /// Contains metadata marker types.
 ///
@@ -37,11 +46,16 @@ use crate::types::{Dimensions, Result};
 /// `MetadataMarker::Metadata` associated type always points to concrete metadata type
 /// from one of `immeta::formats` submodules.
 pub mod markers {
+    #[cfg(feature = "async")]
+    use std::future::Future;
     use std::io::{BufRead, Seek};
     use std::path::Path;
     use std::result;
 
-    use crate::formats::{gif, jpeg, png, webp};
+    #[cfg(feature = "async")]
+    use futures_io::{AsyncBufRead, AsyncSeek};
+
+    use crate::formats::{avif, bmp, gif, heif, ico, jpeg, png, tiff, webp};
     use crate::generic::GenericMetadata;
     use crate::types::Result;
 
@@ -75,6 +89,7 @@ pub mod markers {
         /// let concrete: Result<jpeg::Metadata, GenericMetadata> = generic.into::<Jpeg>();
         /// assert!(concrete.is_err());
         /// ```
+        #[allow(clippy::result_large_err)]
         fn from_generic(gmd: GenericMetadata) -> result::Result<Self::Metadata, GenericMetadata>;
 
         /// Tries to extract a reference to a concrete metadata type from the given
@@ -169,6 +184,26 @@ pub mod markers {
         /// let metadata = Jpeg::load_from_buf(buf);
         /// ```
         fn load_from_buf(b: &[u8]) -> Result<Self::Metadata>;
+
+        /// Attempts to load metadata for an image of a concrete type from the provided
+        /// asynchronous reader.
+        ///
+        /// Invokes `LoadableMetadata::load_async()` for the associated metadata type. Requires
+        /// `async` feature.
+        #[cfg(feature = "async")]
+        fn load_async<R: ?Sized + AsyncBufRead + Unpin>(
+            r: &mut R,
+        ) -> impl Future<Output = Result<Self::Metadata>>;
+
+        /// Attempts to load metadata for an image of a concrete type from the provided
+        /// asynchronous seekable reader.
+        ///
+        /// Invokes `LoadableMetadata::load_async_seek()` for the associated metadata type.
+        /// Requires `async` feature.
+        #[cfg(feature = "async")]
+        fn load_async_seek<R: ?Sized + AsyncBufRead + AsyncSeek + Unpin>(
+            r: &mut R,
+        ) -> impl Future<Output = Result<Self::Metadata>>;
     }
 
     macro_rules! impl_metadata_marker {
@@ -213,6 +248,22 @@ pub mod markers {
                 fn load_from_buf(b: &[u8]) -> Result<$mtpe> {
                     $crate::traits::LoadableMetadata::load_from_buf(b)
                 }
+
+                #[cfg(feature = "async")]
+                #[inline]
+                fn load_async<R: ?Sized + AsyncBufRead + Unpin>(
+                    r: &mut R,
+                ) -> impl Future<Output = Result<$mtpe>> {
+                    <$mtpe as $crate::traits::LoadableMetadata>::load_async(r)
+                }
+
+                #[cfg(feature = "async")]
+                #[inline]
+                fn load_async_seek<R: ?Sized + AsyncBufRead + AsyncSeek + Unpin>(
+                    r: &mut R,
+                ) -> impl Future<Output = Result<$mtpe>> {
+                    <$mtpe as $crate::traits::LoadableMetadata>::load_async_seek(r)
+                }
             }
         };
     }
@@ -221,17 +272,148 @@ pub mod markers {
     impl_metadata_marker! { Png, Png, png::Metadata }
     impl_metadata_marker! { Gif, Gif, gif::Metadata }
     impl_metadata_marker! { Webp, Webp, webp::Metadata }
+    impl_metadata_marker! { Tiff, Tiff, tiff::Metadata }
+    impl_metadata_marker! { Heif, Heif, heif::Metadata }
+    impl_metadata_marker! { Avif, Avif, avif::Metadata }
+    impl_metadata_marker! { Bmp, Bmp, bmp::Metadata }
+    impl_metadata_marker! { Ico, Ico, ico::Metadata }
+}
+
+/// Image format recognized by its signature.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub enum ImageFormat {
+    Png,
+    Gif,
+    Jpeg,
+    Webp,
+    Tiff,
+    Heif,
+    Avif,
+    Bmp,
+    Ico,
+}
+
+impl fmt::Display for ImageFormat {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match *self {
+            ImageFormat::Png => "PNG",
+            ImageFormat::Gif => "GIF",
+            ImageFormat::Jpeg => "JPEG",
+            ImageFormat::Webp => "WebP",
+            ImageFormat::Tiff => "TIFF",
+            ImageFormat::Heif => "HEIF",
+            ImageFormat::Avif => "AVIF",
+            ImageFormat::Bmp => "BMP",
+            ImageFormat::Ico => "ICO",
+        })
+    }
+}
+
+impl ImageFormat {
+    /// Returns a MIME type string for this image format.
+    pub fn mime_type(&self) -> &'static str {
+        match *self {
+            ImageFormat::Png => "image/png",
+            ImageFormat::Gif => "image/gif",
+            ImageFormat::Jpeg => "image/jpeg",
+            ImageFormat::Webp => "image/webp",
+            ImageFormat::Tiff => "image/tiff",
+            ImageFormat::Heif => "image/heif",
+            ImageFormat::Avif => "image/avif",
+            ImageFormat::Bmp => "image/bmp",
+            ImageFormat::Ico => "image/vnd.microsoft.icon",
+        }
+    }
+}
+
+/// Number of bytes which is enough to recognize any supported image format.
+pub const SIGNATURE_LENGTH: usize = 12;
+
+// number of bytes examined by the loaders, enough for the `ftyp` box of most HEIF and
+// AVIF files with all of their compatible brands
+const PEEK_LENGTH: usize = 64;
+
+/// Determines image format by the signature at the beginning of the given data.
+///
+/// The first `SIGNATURE_LENGTH` bytes are enough to recognize the format; the rest of
+/// the image is not validated. Returns `None` if the signature is not recognized.
+///
+/// HEIF and AVIF images are recognized by the brands in their `ftyp` box, and AVIF brands
+/// take priority. AVIF files may have a generic HEIF major brand like `mif1` and list
+/// `avif` only among the compatible brands, which follow the first `SIGNATURE_LENGTH`
+/// bytes; if they are not included in `data`, such files are recognized as HEIF.
+pub fn detect_format(data: &[u8]) -> Option<ImageFormat> {
+    if data.starts_with(b"\x89PNG\r\n\x1a\n") {
+        Some(ImageFormat::Png)
+    } else if data.starts_with(b"GIF87a") || data.starts_with(b"GIF89a") {
+        Some(ImageFormat::Gif)
+    } else if data.starts_with(b"\xff\xd8\xff") {
+        Some(ImageFormat::Jpeg)
+    } else if data.len() >= 12 && &data[..4] == b"RIFF" && &data[8..12] == b"WEBP" {
+        Some(ImageFormat::Webp)
+    } else if [b"II*\0", b"MM\0*", b"II+\0", b"MM\0+"]
+        .iter()
+        .any(|s| data.starts_with(*s))
+    {
+        // both regular TIFF and BigTIFF
+        Some(ImageFormat::Tiff)
+    } else if data.len() >= 12 && &data[4..8] == b"ftyp" {
+        // HEIF-based formats are distinguished by the major brand and the compatible
+        // brands, which follow the minor version up to the end of the box
+        let end = match BigEndian::read_u32(&data[..4]) as usize {
+            0 | 1 => data.len(),
+            size => size.min(data.len()),
+        };
+        let compatible = data.get(16..end).unwrap_or(&[]).chunks_exact(4);
+        let brands = Some(&data[8..12]).into_iter().chain(compatible);
+        let has_brand = |known: &[&[u8; 4]]| brands.clone().any(|b| known.iter().any(|k| b == *k));
+        if has_brand(avif::BRANDS) {
+            Some(ImageFormat::Avif)
+        } else if has_brand(heif::BRANDS) {
+            Some(ImageFormat::Heif)
+        } else {
+            None
+        }
+    } else if data.starts_with(b"BM") {
+        Some(ImageFormat::Bmp)
+    } else if data.len() >= 6
+        && (data.starts_with(b"\0\0\x01\0") || data.starts_with(b"\0\0\x02\0"))
+        && data[4..6] != [0, 0]
+    {
+        // both icons and cursors with at least one image
+        Some(ImageFormat::Ico)
+    } else {
+        None
+    }
+}
+
+/// Determines image format by the signature at the current position of the given stream.
+///
+/// The stream is returned to its original position afterwards. See `detect_format()`.
+pub fn detect_format_from_reader<R: ?Sized + Read + Seek>(
+    r: &mut R,
+) -> Result<Option<ImageFormat>> {
+    let mut signature = [0u8; PEEK_LENGTH];
+    let n = r.read_exact_0(&mut signature)?;
+    r.seek(SeekFrom::Current(-(n as i64)))?;
+    Ok(detect_format(&signature[..n]))
 }
 
 /// Represents metadata loaded from a file whose format was determined automatically.
 ///
 /// Values of this type are obtained via `immeta::load()` function and its derivatives.
+#[allow(clippy::large_enum_variant)]
 #[derive(Debug, Clone, Eq, PartialEq)]
 pub enum GenericMetadata {
     Png(png::Metadata),
     Gif(gif::Metadata),
     Jpeg(jpeg::Metadata),
     Webp(webp::Metadata),
+    Tiff(tiff::Metadata),
+    Heif(heif::Metadata),
+    Avif(avif::Metadata),
+    Bmp(bmp::Metadata),
+    Ico(ico::Metadata),
 }
 
 impl GenericMetadata {
@@ -242,16 +424,125 @@ impl GenericMetadata {
             GenericMetadata::Gif(ref md) => md.dimensions,
             GenericMetadata::Jpeg(ref md) => md.dimensions,
             GenericMetadata::Webp(ref md) => md.dimensions(),
+            GenericMetadata::Tiff(ref md) => md.dimensions(),
+            GenericMetadata::Heif(ref md) => md.dimensions,
+            GenericMetadata::Avif(ref md) => md.dimensions,
+            GenericMetadata::Bmp(ref md) => md.dimensions(),
+            GenericMetadata::Ico(ref md) => md.dimensions(),
+        }
+    }
+
+    /// Returns the format of the image the contained metadata belongs to.
+    pub fn format(&self) -> ImageFormat {
+        match *self {
+            GenericMetadata::Png(_) => ImageFormat::Png,
+            GenericMetadata::Gif(_) => ImageFormat::Gif,
+            GenericMetadata::Jpeg(_) => ImageFormat::Jpeg,
+            GenericMetadata::Webp(_) => ImageFormat::Webp,
+            GenericMetadata::Tiff(_) => ImageFormat::Tiff,
+            GenericMetadata::Heif(_) => ImageFormat::Heif,
+            GenericMetadata::Avif(_) => ImageFormat::Avif,
+            GenericMetadata::Bmp(_) => ImageFormat::Bmp,
+            GenericMetadata::Ico(_) => ImageFormat::Ico,
         }
     }
 
     /// Returns a MIME type string for the image type of the contained metadata.
+    #[inline]
     pub fn mime_type(&self) -> &'static str {
+        self.format().mime_type()
+    }
+
+    /// Returns EXIF metadata from the contained metadata, if it is present.
+    pub fn exif(&self) -> Option<&Exif> {
+        match *self {
+            GenericMetadata::Png(ref md) => md.exif.as_ref(),
+            GenericMetadata::Gif(_) => None,
+            GenericMetadata::Jpeg(ref md) => md.exif.as_ref(),
+            GenericMetadata::Webp(ref md) => md.exif(),
+            GenericMetadata::Tiff(ref md) => md.exif.as_ref(),
+            GenericMetadata::Heif(ref md) => md.exif.as_ref(),
+            GenericMetadata::Avif(ref md) => md.exif.as_ref(),
+            GenericMetadata::Bmp(_) => None,
+            GenericMetadata::Ico(_) => None,
+        }
+    }
+
+    /// Returns orientation of the image from the contained metadata, if it is present.
+    pub fn orientation(&self) -> Option<Orientation> {
+        match *self {
+            GenericMetadata::Png(ref md) => md.orientation(),
+            GenericMetadata::Gif(_) => None,
+            GenericMetadata::Jpeg(ref md) => md.orientation(),
+            GenericMetadata::Webp(ref md) => md.orientation(),
+            GenericMetadata::Tiff(ref md) => md.orientation(),
+            GenericMetadata::Heif(ref md) => Some(md.orientation()),
+            GenericMetadata::Avif(ref md) => Some(md.orientation()),
+            GenericMetadata::Bmp(_) => None,
+            GenericMetadata::Ico(_) => None,
+        }
+    }
+
+    /// Returns image dimensions as they should be displayed, i.e. with the orientation
+    /// applied.
+    ///
+    /// Unlike `dimensions()`, this method swaps width and height of images which should be
+    /// rotated by 90 or 270 degrees, e.g. portrait photos taken by phone cameras.
+    pub fn display_dimensions(&self) -> Dimensions {
+        match self.orientation() {
+            Some(orientation) => orientation.apply(self.dimensions()),
+            None => self.dimensions(),
+        }
+    }
+
+    /// Returns GPS information from the contained metadata, if it is present.
+    pub fn gps(&self) -> Option<Gps> {
+        self.exif().and_then(Exif::gps)
+    }
+
+    /// Returns the embedded ICC profile, if it is present.
+    ///
+    /// Use `common::icc::Profile::parse()` to read its header and description.
+    pub fn icc_profile(&self) -> Option<&[u8]> {
+        match *self {
+            GenericMetadata::Png(ref md) => md.icc_profile.as_deref(),
+            GenericMetadata::Gif(_) => None,
+            GenericMetadata::Jpeg(ref md) => md.icc_profile.as_deref(),
+            GenericMetadata::Webp(ref md) => md.icc_profile(),
+            GenericMetadata::Tiff(ref md) => md.icc_profile(),
+            GenericMetadata::Heif(ref md) => md.icc_profile.as_deref(),
+            GenericMetadata::Avif(ref md) => md.icc_profile.as_deref(),
+            GenericMetadata::Bmp(ref md) => md.icc_profile.as_deref(),
+            GenericMetadata::Ico(_) => None,
+        }
+    }
+
+    /// Returns the XMP packet from the contained metadata, if it is present.
+    ///
+    /// For JPEG images this is the main packet; the extended one, if any, is returned
+    /// by `extended_xmp()`.
+    pub fn xmp(&self) -> Option<&str> {
         match *self {
-            GenericMetadata::Png(_) => "image/png",
-            GenericMetadata::Gif(_) => "image/gif",
-            GenericMetadata::Jpeg(_) => "image/jpeg",
-            GenericMetadata::Webp(_) => "image/webp",
+            GenericMetadata::Png(ref md) => md.xmp.as_deref(),
+            GenericMetadata::Gif(ref md) => md.xmp.as_deref(),
+            GenericMetadata::Jpeg(ref md) => md.xmp.as_deref(),
+            GenericMetadata::Webp(ref md) => md.xmp(),
+            GenericMetadata::Tiff(ref md) => md.xmp(),
+            GenericMetadata::Heif(ref md) => md.xmp.as_deref(),
+            GenericMetadata::Avif(ref md) => md.xmp.as_deref(),
+            GenericMetadata::Bmp(_) => None,
+            GenericMetadata::Ico(_) => None,
+        }
+    }
+
+    /// Returns the extended XMP packet from the contained metadata, if it is present.
+    ///
+    /// Only JPEG images may have an extended packet, which holds the properties that did
+    /// not fit into the main one returned by `xmp()`.
+    pub fn extended_xmp(&self) -> Option<&str> {
+        match *self {
+            GenericMetadata::Jpeg(ref md) => md.extended_xmp.as_deref(),
+            _ => None,
         }
     }
 
@@ -260,6 +551,7 @@ impl GenericMetadata {
     /// This method is needed only to provide a convenient syntax and it is not necessary
     /// because one may just `match` on the `GenericMetadata` value.
     #[inline]
+    #[allow(clippy::result_large_err)]
     pub fn into<T: MetadataMarker>(self) -> result::Result<T::Metadata, GenericMetadata> {
         <T as MetadataMarker>::from_generic(self)
     }
@@ -276,36 +568,109 @@ impl GenericMetadata {
 
 /// Attempts to load metadata for an image contained in the provided input stream.
 ///
-/// This method automatically determines the format of the contained image. Because it may
-/// need to read the stream from the beginning several times, a `Seek` bound is necessary
-/// on the input stream. This may cause problems only with network streams as they are
-/// naturally not seekable, so one would need to buffer the data from them first.
+/// This method automatically determines the format of the contained image by its signature,
+/// see `detect_format()`, and then loads metadata with the parser for that format. If the
+/// signature is recognized but the image is invalid, the error from the parser is returned.
+///
+/// The stream is rewound to its beginning first, so a `Seek` bound is necessary on the input
+/// stream. Use `load_from_reader()` for streams which are not seekable.
 pub fn load<R: ?Sized + BufRead + Seek>(r: &mut R) -> Result<GenericMetadata> {
-    // try png
     r.rewind()?;
-    if let Ok(md) = png::Metadata::load_from_seek(r) {
-        return Ok(GenericMetadata::Png(md));
-    }
+    let format = detect_format_from_reader(r)?
+        .ok_or_else(|| invalid_format!("unknown or unsupported image type"))?;
 
-    // try gif
-    r.rewind()?;
-    if let Ok(md) = gif::Metadata::load_from_seek(r) {
-        return Ok(GenericMetadata::Gif(md));
-    }
+    Ok(match format {
+        ImageFormat::Png => GenericMetadata::Png(png::Metadata::load_from_seek(r)?),
+        ImageFormat::Gif => GenericMetadata::Gif(gif::Metadata::load_from_seek(r)?),
+        ImageFormat::Jpeg => GenericMetadata::Jpeg(jpeg::Metadata::load_from_seek(r)?),
+        ImageFormat::Webp => GenericMetadata::Webp(webp::Metadata::load_from_seek(r)?),
+        ImageFormat::Tiff => GenericMetadata::Tiff(tiff::Metadata::load_from_seek(r)?),
+        ImageFormat::Heif => GenericMetadata::Heif(heif::Metadata::load_from_seek(r)?),
+        ImageFormat::Avif => GenericMetadata::Avif(avif::Metadata::load_from_seek(r)?),
+        ImageFormat::Bmp => GenericMetadata::Bmp(bmp::Metadata::load_from_seek(r)?),
+        ImageFormat::Ico => GenericMetadata::Ico(ico::Metadata::load_from_seek(r)?),
+    })
+}
 
-    // try webp
-    r.rewind()?;
-    if let Ok(md) = webp::Metadata::load_from_seek(r) {
-        return Ok(GenericMetadata::Webp(md));
+/// Attempts to load metadata for an image contained in the provided input stream, which
+/// does not have to be seekable.
+///
+/// Unlike `load()`, this method reads the stream from its current position and works with
+/// pipes and sockets. The format is determined by peeking at the signature in the stream
+/// buffer, and then only the data needed by the parser for that format is consumed. Some
+/// parsers, e.g. the TIFF one, may need to read the whole image in this case.
+pub fn load_from_reader<R: ?Sized + BufRead>(r: &mut R) -> Result<GenericMetadata> {
+    let buf = r.fill_buf()?;
+    if buf.len() >= PEEK_LENGTH {
+        let format = detect_format(buf);
+        return load_format(r, format);
     }
 
-    // try jpeg
-    r.rewind()?;
-    if let Ok(md) = jpeg::Metadata::load_from_seek(r) {
-        return Ok(GenericMetadata::Jpeg(md));
+    // the buffer may be too small to peek at the whole signature, so it is read and then
+    // put back in front of the rest of the stream
+    let mut signature = [0u8; PEEK_LENGTH];
+    let n = r.read_exact_0(&mut signature)?;
+    let format = detect_format(&signature[..n]);
+    load_format(&mut (&signature[..n]).chain(r), format)
+}
+
+fn load_format<R: ?Sized + BufRead>(
+    r: &mut R,
+    format: Option<ImageFormat>,
+) -> Result<GenericMetadata> {
+    let format = format.ok_or_else(|| invalid_format!("unknown or unsupported image type"))?;
+    Ok(match format {
+        ImageFormat::Png => GenericMetadata::Png(png::Metadata::load(r)?),
+        ImageFormat::Gif => GenericMetadata::Gif(gif::Metadata::load(r)?),
+        ImageFormat::Jpeg => GenericMetadata::Jpeg(jpeg::Metadata::load(r)?),
+        ImageFormat::Webp => GenericMetadata::Webp(webp::Metadata::load(r)?),
+        ImageFormat::Tiff => GenericMetadata::Tiff(tiff::Metadata::load(r)?),
+        ImageFormat::Heif => GenericMetadata::Heif(heif::Metadata::load(r)?),
+        ImageFormat::Avif => GenericMetadata::Avif(avif::Metadata::load(r)?),
+        ImageFormat::Bmp => GenericMetadata::Bmp(bmp::Metadata::load(r)?),
+        ImageFormat::Ico => GenericMetadata::Ico(ico::Metadata::load(r)?),
+    })
+}
+
+/// Attempts to load metadata for an image contained in the provided asynchronous input stream.
+///
+/// The format is determined by the signature, and then data is read from the stream into
+/// memory until there is enough of it to load the metadata for that format. The stream may be
+/// read further than `load_from_reader()` would read it. Requires `async` feature.
+///
+/// Note that all data needed by the parser is kept in memory, and for many images this is
+/// the whole stream: TIFF and ICO images are always read completely, and PNG, GIF and
+/// animated WebP parsers walk through all chunks or frames up to the end of the image. Only
+/// JPEG, still WebP, BMP and most HEIF and AVIF images are loaded from their beginning.
+/// Limit the length of untrusted streams before passing them to this function, and use
+/// `load_async_seek()` for seekable streams.
+#[cfg(feature = "async")]
+pub async fn load_async<R: ?Sized + AsyncBufRead + Unpin>(r: &mut R) -> Result<GenericMetadata> {
+    let mut data = asynchronous::read_prefix(r, PEEK_LENGTH).await?;
+    let format = detect_format(&data);
+    if let Some(ImageFormat::Tiff | ImageFormat::Ico) = format {
+        // IFDs may be located anywhere in the file, and truncated icons are loaded
+        // successfully, so parsing cannot be retried with more data
+        asynchronous::read_to_end(r, &mut data).await?;
     }
+    asynchronous::load_buffered(r, data, |data| load_format(&mut &data[..], format)).await
+}
 
-    Err(invalid_format!("unknown or unsupported image type"))
+/// Attempts to load metadata for an image contained in the provided asynchronous seekable
+/// input stream.
+///
+/// This is the asynchronous counterpart of `load()`: the stream is rewound to its beginning,
+/// and only the ranges of it which the parser reads are fetched into memory. Parsers of TIFF,
+/// HEIF and AVIF images seek over the data they do not need, so unlike `load_async()` this
+/// function does not read them completely. Other parsers read the stream sequentially, so
+/// PNG, GIF and animated WebP images are still read up to their end. Requires `async`
+/// feature.
+#[cfg(feature = "async")]
+pub async fn load_async_seek<R: ?Sized + AsyncBufRead + AsyncSeek + Unpin>(
+    r: &mut R,
+) -> Result<GenericMetadata> {
+    asynchronous::seek(r, SeekFrom::Start(0)).await?;
+    asynchronous::load_seekable(r, load).await
 }
 
 /// Attempts to load metadata for an image contained in a file identified by the provided path.
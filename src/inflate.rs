@@ -0,0 +1,382 @@
+//! A small decoder of DEFLATE (RFC 1951) and zlib (RFC 1950) streams.
+//!
+//! Compressed streams occur in metadata of some formats, e.g. in PNG `iCCP` chunks.
+//! The decoder is not optimized for speed, because such streams are usually small.
+
+use crate::types::Result;
+
+const LENGTH_BASE: [u16; 29] = [
+    3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131,
+    163, 195, 227, 258,
+];
+const LENGTH_EXTRA: [u8; 29] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0,
+];
+const DISTANCE_BASE: [u16; 30] = [
+    1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537,
+    2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577,
+];
+const DISTANCE_EXTRA: [u8; 30] = [
+    0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13,
+    13,
+];
+// order in which code length code lengths are stored in dynamic blocks
+const CODE_LENGTH_ORDER: [usize; 19] = [
+    16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15,
+];
+
+const MAX_BITS: usize = 15;
+
+/// Decompresses a zlib stream, failing if the output becomes larger than `limit` bytes.
+pub fn zlib_decompress(data: &[u8], limit: usize) -> Result<Vec<u8>> {
+    if data.len() < 2 {
+        return Err(unexpected_eof!("when reading zlib header"));
+    }
+    let (cmf, flg) = (data[0], data[1]);
+    if cmf & 0x0f != 8 || cmf >> 4 > 7 || !(cmf as u16 * 256 + flg as u16).is_multiple_of(31) {
+        return Err(invalid_format!(
+            "invalid zlib header: {:#04x} {:#04x}",
+            cmf,
+            flg
+        ));
+    }
+    if flg & 0x20 != 0 {
+        return Err(invalid_format!(
+            "zlib streams with preset dictionaries are not supported"
+        ));
+    }
+
+    let mut r = BitReader::new(&data[2..]);
+    let result = inflate(&mut r, limit)?;
+
+    let checksum = r.rest();
+    if checksum.len() < 4 {
+        return Err(unexpected_eof!("when reading zlib checksum"));
+    }
+    let expected = u32::from_be_bytes([checksum[0], checksum[1], checksum[2], checksum[3]]);
+    if adler32(&result) != expected {
+        return Err(invalid_format!("zlib checksum mismatch"));
+    }
+    Ok(result)
+}
+
+fn inflate(r: &mut BitReader, limit: usize) -> Result<Vec<u8>> {
+    let mut out = Vec::new();
+    loop {
+        let last = r.bits(1)? == 1;
+        match r.bits(2)? {
+            0 => stored_block(r, &mut out, limit)?,
+            1 => {
+                let (lengths, distances) = fixed_codes();
+                codes(r, &mut out, limit, &lengths, &distances)?
+            }
+            2 => {
+                let (lengths, distances) = dynamic_codes(r)?;
+                codes(r, &mut out, limit, &lengths, &distances)?
+            }
+            _ => return Err(invalid_format!("invalid deflate block type")),
+        }
+        if last {
+            return Ok(out);
+        }
+    }
+}
+
+fn adler32(data: &[u8]) -> u32 {
+    const MOD: u32 = 65521;
+    let (mut a, mut b) = (1u32, 0u32);
+    // 5552 is the largest number of bytes which cannot overflow the sums
+    for chunk in data.chunks(5552) {
+        for &byte in chunk {
+            a += byte as u32;
+            b += a;
+        }
+        a %= MOD;
+        b %= MOD;
+    }
+    (b << 16) | a
+}
+
+struct BitReader<'a> {
+    data: &'a [u8],
+    position: usize,
+    buffer: u32,
+    count: u32,
+}
+
+impl<'a> BitReader<'a> {
+    fn new(data: &'a [u8]) -> BitReader<'a> {
+        BitReader {
+            data,
+            position: 0,
+            buffer: 0,
+            count: 0,
+        }
+    }
+
+    // reads up to 16 bits, least significant bit first
+    fn bits(&mut self, n: u32) -> Result<u32> {
+        while self.count < n {
+            let byte = *self
+                .data
+                .get(self.position)
+                .ok_or_else(|| unexpected_eof!("when reading deflate stream"))?;
+            self.buffer |= (byte as u32) << self.count;
+            self.position += 1;
+            self.count += 8;
+        }
+        let value = self.buffer & ((1 << n) - 1);
+        self.buffer >>= n;
+        self.count -= n;
+        Ok(value)
+    }
+
+    // discards the remaining bits of the current byte
+    fn align(&mut self) {
+        self.buffer = 0;
+        self.count = 0;
+    }
+
+    // returns the data after the current byte
+    fn rest(&self) -> &'a [u8] {
+        // whole bytes in the buffer have not been consumed yet
+        &self.data[self.position - (self.count / 8) as usize..]
+    }
+}
+
+struct Huffman {
+    // number of codes of each length
+    counts: [u16; MAX_BITS + 1],
+    // symbols ordered by their codes
+    symbols: Vec<u16>,
+}
+
+impl Huffman {
+    fn new(lengths: &[u8]) -> Result<Huffman> {
+        let mut counts = [0u16; MAX_BITS + 1];
+        for &len in lengths {
+            counts[len as usize] += 1;
+        }
+
+        // check that the code is not over-subscribed; incomplete codes are allowed
+        let mut left = 1i32;
+        for &count in &counts[1..] {
+            left = (left << 1) - count as i32;
+            if left < 0 {
+                return Err(invalid_format!("invalid deflate Huffman code lengths"));
+            }
+        }
+
+        let mut offsets = [0u16; MAX_BITS + 2];
+        for len in 1..=MAX_BITS {
+            offsets[len + 1] = offsets[len] + counts[len];
+        }
+        let mut symbols = vec![0; offsets[MAX_BITS + 1] as usize];
+        for (symbol, &len) in lengths.iter().enumerate() {
+            if len != 0 {
+                symbols[offsets[len as usize] as usize] = symbol as u16;
+                offsets[len as usize] += 1;
+            }
+        }
+
+        Ok(Huffman { counts, symbols })
+    }
+
+    fn decode(&self, r: &mut BitReader) -> Result<u16> {
+        // codes are canonical, so they can be decoded one bit at a time
+        let (mut code, mut first, mut index) = (0i32, 0i32, 0i32);
+        for &count in &self.counts[1..] {
+            code |= r.bits(1)? as i32;
+            let count = count as i32;
+            if code - count < first {
+                return Ok(self.symbols[(index + code - first) as usize]);
+            }
+            index += count;
+            first = (first + count) << 1;
+            code <<= 1;
+        }
+        Err(invalid_format!("invalid deflate Huffman code"))
+    }
+}
+
+fn stored_block(r: &mut BitReader, out: &mut Vec<u8>, limit: usize) -> Result<()> {
+    r.align();
+    let len = r.bits(16)?;
+    let nlen = r.bits(16)?;
+    if len != !nlen & 0xffff {
+        return Err(invalid_format!("invalid deflate stored block length"));
+    }
+    let data = r.rest();
+    let len = len as usize;
+    if data.len() < len {
+        return Err(unexpected_eof!("when reading deflate stored block"));
+    }
+    if out.len() + len > limit {
+        return Err(invalid_format!("decompressed data is too large"));
+    }
+    out.extend_from_slice(&data[..len]);
+    r.position += len;
+    Ok(())
+}
+
+fn fixed_codes() -> (Huffman, Huffman) {
+    let mut lengths = [0u8; 288];
+    lengths[..144].fill(8);
+    lengths[144..256].fill(9);
+    lengths[256..280].fill(7);
+    lengths[280..].fill(8);
+    // unwraps can't fail, the fixed codes are valid
+    (
+        Huffman::new(&lengths).unwrap(),
+        Huffman::new(&[5; 30]).unwrap(),
+    )
+}
+
+fn dynamic_codes(r: &mut BitReader) -> Result<(Huffman, Huffman)> {
+    let literals = r.bits(5)? as usize + 257;
+    let distances = r.bits(5)? as usize + 1;
+    let code_lengths = r.bits(4)? as usize + 4;
+    if literals > 286 || distances > 30 {
+        return Err(invalid_format!("invalid deflate dynamic block header"));
+    }
+
+    let mut lengths = [0u8; 19];
+    for &i in &CODE_LENGTH_ORDER[..code_lengths] {
+        lengths[i] = r.bits(3)? as u8;
+    }
+    let code_length_code = Huffman::new(&lengths)?;
+
+    let mut lengths = vec![0u8; literals + distances];
+    let mut i = 0;
+    while i < lengths.len() {
+        let symbol = code_length_code.decode(r)?;
+        let (value, repeat) = match symbol {
+            0..=15 => (symbol as u8, 1),
+            16 if i > 0 => (lengths[i - 1], 3 + r.bits(2)?),
+            17 => (0, 3 + r.bits(3)?),
+            18 => (0, 11 + r.bits(7)?),
+            _ => return Err(invalid_format!("invalid deflate code lengths")),
+        };
+        let end = i + repeat as usize;
+        if end > lengths.len() {
+            return Err(invalid_format!("invalid deflate code lengths"));
+        }
+        lengths[i..end].fill(value);
+        i = end;
+    }
+    if lengths[256] == 0 {
+        return Err(invalid_format!("deflate block has no end code"));
+    }
+
+    Ok((
+        Huffman::new(&lengths[..literals])?,
+        Huffman::new(&lengths[literals..])?,
+    ))
+}
+
+fn codes(
+    r: &mut BitReader,
+    out: &mut Vec<u8>,
+    limit: usize,
+    lengths: &Huffman,
+    distances: &Huffman,
+) -> Result<()> {
+    loop {
+        let symbol = lengths.decode(r)? as usize;
+        let len = match symbol {
+            0..=255 => {
+                if out.len() >= limit {
+                    return Err(invalid_format!("decompressed data is too large"));
+                }
+                out.push(symbol as u8);
+                continue;
+            }
+            256 => return Ok(()),
+            257..=285 => {
+                let i = symbol - 257;
+                LENGTH_BASE[i] as usize + r.bits(LENGTH_EXTRA[i] as u32)? as usize
+            }
+            _ => return Err(invalid_format!("invalid deflate length code")),
+        };
+
+        let symbol = distances.decode(r)? as usize;
+        if symbol >= 30 {
+            return Err(invalid_format!("invalid deflate distance code"));
+        }
+        let distance =
+            DISTANCE_BASE[symbol] as usize + r.bits(DISTANCE_EXTRA[symbol] as u32)? as usize;
+        if distance > out.len() {
+            return Err(invalid_format!("invalid deflate distance: {}", distance));
+        }
+        if out.len() + len > limit {
+            return Err(invalid_format!("decompressed data is too large"));
+        }
+        // the copied range may overlap with the output
+        let start = out.len() - distance;
+        for j in 0..len {
+            out.push(out[start + j]);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::zlib_decompress;
+
+    #[test]
+    fn test_stored() {
+        // zlib.compress(b"hello", 0)
+        let data = [
+            0x78, 0x01, 0x01, 0x05, 0x00, 0xfa, 0xff, b'h', b'e', b'l', b'l', b'o', 0x06, 0x2c,
+            0x02, 0x15,
+        ];
+        assert_eq!(zlib_decompress(&data, 100).unwrap(), b"hello");
+        assert!(zlib_decompress(&data, 4).is_err());
+    }
+
+    #[test]
+    fn test_fixed() {
+        // zlib.compress(b"hello hello hello hello")
+        let data = [
+            0x78, 0x9c, 0xcb, 0x48, 0xcd, 0xc9, 0xc9, 0x57, 0xc8, 0x40, 0x27, 0x01, 0x68, 0x03,
+            0x08, 0xb1,
+        ];
+        assert_eq!(
+            zlib_decompress(&data, 100).unwrap(),
+            b"hello hello hello hello"
+        );
+
+        let mut corrupted = data;
+        corrupted[15] ^= 1;
+        assert!(zlib_decompress(&corrupted, 100).is_err());
+    }
+
+    #[test]
+    fn test_dynamic() {
+        // pseudo-random letters with a skewed distribution make zlib use a dynamic block
+        let alphabet = b"aaaaaaaabbbbccdefgh";
+        let mut x = 1u32;
+        let expected: Vec<u8> = (0..300)
+            .map(|_| {
+                x = x.wrapping_mul(1103515245).wrapping_add(12345) & 0x7fffffff;
+                alphabet[(x >> 16) as usize % alphabet.len()]
+            })
+            .collect();
+
+        // zlib.compress(expected, 9)
+        let data = [
+            0x78, 0xda, 0x35, 0x8f, 0x0b, 0x12, 0x80, 0x40, 0x08, 0x42, 0xcf, 0x0a, 0x7e, 0xef,
+            0x7f, 0x82, 0xc0, 0xaa, 0xa9, 0x59, 0x35, 0x1e, 0xb8, 0x00, 0x1a, 0x00, 0x81, 0x58,
+            0xa4, 0x8a, 0xb9, 0x3a, 0x50, 0x68, 0x92, 0x6a, 0x56, 0xbd, 0x9f, 0x90, 0xa2, 0x5d,
+            0x33, 0x88, 0xec, 0xf2, 0x6c, 0x56, 0x4a, 0x58, 0x55, 0xc9, 0x6d, 0x9d, 0x7d, 0x30,
+            0x4c, 0x1d, 0xa7, 0x59, 0x97, 0x06, 0xfe, 0x68, 0x67, 0xbd, 0x4c, 0xd4, 0xf8, 0x27,
+            0xdb, 0xf0, 0x98, 0x5e, 0xb7, 0x74, 0x6e, 0xbe, 0xeb, 0xb0, 0xe2, 0x0c, 0xd2, 0x44,
+            0x86, 0xd9, 0x61, 0x9c, 0xe9, 0x45, 0x63, 0xe2, 0x98, 0xfc, 0x6d, 0xbc, 0xb1, 0xb2,
+            0x65, 0x31, 0x16, 0xed, 0x49, 0x05, 0xac, 0xb0, 0xd6, 0x0e, 0x59, 0x58, 0xb5, 0x51,
+            0x1d, 0xda, 0x2e, 0x31, 0x9f, 0x00, 0xef, 0x0d, 0x14, 0x10, 0x7d, 0xe7, 0xc5, 0xf2,
+            0xae, 0xf1, 0xd7, 0x8e, 0x68, 0x3e, 0xe0, 0x08, 0x73, 0x69,
+        ];
+        assert_eq!(zlib_decompress(&data, 1000).unwrap(), expected);
+        assert!(zlib_decompress(&data[..100], 1000).is_err());
+    }
+}
@@ -21,9 +21,25 @@
 //!   * JPEG
 //!   * PNG 1.2
 //!   * GIF (both 87a and 89a)
+//!   * WebP (lossy, lossless and extended)
+//!   * TIFF
+//!   * HEIF, including HEIC
+//!   * AVIF, including image sequences
+//!   * BMP
+//!   * ICO and CUR
 //!
-//! Support for more types will come in future versions, as well as support for particular
-//! metadata kinds (e.g. EXIF tags in JPEG) which are not yet available.
+//! Metadata can also be loaded from asynchronous streams implementing `futures_io::AsyncBufRead`
+//! with `load_async()` functions, and from seekable ones also implementing
+//! `futures_io::AsyncSeek` with `load_async_seek()` functions, which are available with `async`
+//! cargo feature.
+//!
+//! Besides the format-specific metadata, immeta reads metadata embedded into images in common
+//! formats: EXIF (including GPS information), XMP, IPTC and ICC profiles, wherever the image
+//! format allows storing them. Parsers for these formats are available in the `common` module,
+//! and `GenericMetadata` provides access to EXIF, XMP and ICC profiles regardless of the image
+//! format.
+//!
+//! Support for more image formats and metadata kinds will come in future versions.
 //!
 //! **Important note:** this library only allows inspecting image metadata, not the image
 //! contents. That is, it does not perform decoding and does not provide access to pixels
@@ -38,6 +54,9 @@ pub use types::*;
 mod macros;
 #[macro_use]
 mod generic;
+#[cfg(feature = "async")]
+mod asynchronous;
+mod inflate;
 mod traits;
 mod types;
 mod utils;
@@ -1,7 +1,12 @@
 use std::fs::File;
+#[cfg(feature = "async")]
+use std::future::Future;
 use std::io::{BufRead, BufReader, Cursor, Seek};
 use std::path::Path;
 
+#[cfg(feature = "async")]
+use futures_io::{AsyncBufRead, AsyncSeek};
+
 use crate::types::Result;
 
 /// Provides several convenience functions for loading metadata from various sources.
@@ -34,4 +39,34 @@ pub trait LoadableMetadata: Sized {
     fn load_from_buf(buf: &[u8]) -> Result<Self> {
         LoadableMetadata::load_from_seek(&mut Cursor::new(buf))
     }
+
+    /// Loads the implementing type from the given asynchronous buffered input stream.
+    ///
+    /// Data is read from the stream into memory until there is enough of it to load
+    /// the metadata, so the stream may be read further than `LoadableMetadata::load()` would
+    /// read it. Requires `async` feature.
+    ///
+    /// All data consumed by the parser is kept in memory, which is the whole stream for
+    /// formats whose parsers read images up to their end; see `immeta::load_async()`.
+    #[cfg(feature = "async")]
+    fn load_async<R: ?Sized + AsyncBufRead + Unpin>(
+        r: &mut R,
+    ) -> impl Future<Output = Result<Self>> {
+        crate::asynchronous::load_buffered(r, Vec::new(), Self::load_from_buf)
+    }
+
+    /// Loads the implementing type from the given asynchronous buffered and seekable input
+    /// stream, starting at its current position.
+    ///
+    /// Uses `LoadableMetadata::load_from_seek()`, fetching from the stream only the ranges
+    /// which it reads, so only the needed parts of TIFF, HEIF and AVIF images are read.
+    /// Parsers of other formats do not seek, so they read the stream as `load_async()`
+    /// does; in particular, PNG images are still read up to their end. Requires `async`
+    /// feature.
+    #[cfg(feature = "async")]
+    fn load_async_seek<R: ?Sized + AsyncBufRead + AsyncSeek + Unpin>(
+        r: &mut R,
+    ) -> impl Future<Output = Result<Self>> {
+        crate::asynchronous::load_seekable(r, Self::load_from_seek)
+    }
 }
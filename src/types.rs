@@ -86,3 +86,107 @@ impl<T: ToPrimitive, U: ToPrimitive> From<(T, U)> for Dimensions {
         }
     }
 }
+
+impl Dimensions {
+    /// Returns dimensions with width and height swapped.
+    #[inline]
+    pub fn transposed(self) -> Dimensions {
+        Dimensions {
+            width: self.height,
+            height: self.width,
+        }
+    }
+}
+
+/// Describes how an image should be transformed to be displayed correctly.
+///
+/// Variants correspond to the eight values of the EXIF orientation tag. Rotations are
+/// clockwise and are applied after flipping.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Orientation {
+    /// No transformation is needed.
+    Normal,
+    /// The image should be flipped horizontally.
+    FlipHorizontal,
+    /// The image should be rotated by 180 degrees.
+    Rotate180,
+    /// The image should be flipped vertically.
+    FlipVertical,
+    /// The image should be flipped horizontally and rotated by 270 degrees.
+    Transpose,
+    /// The image should be rotated by 90 degrees.
+    Rotate90,
+    /// The image should be flipped horizontally and rotated by 90 degrees.
+    Transverse,
+    /// The image should be rotated by 270 degrees.
+    Rotate270,
+}
+
+impl fmt::Display for Orientation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match *self {
+            Orientation::Normal => "Normal",
+            Orientation::FlipHorizontal => "Flip horizontal",
+            Orientation::Rotate180 => "Rotate 180",
+            Orientation::FlipVertical => "Flip vertical",
+            Orientation::Transpose => "Transpose",
+            Orientation::Rotate90 => "Rotate 90 CW",
+            Orientation::Transverse => "Transverse",
+            Orientation::Rotate270 => "Rotate 270 CW",
+        })
+    }
+}
+
+impl Orientation {
+    /// Converts a value of the EXIF orientation tag, returning `None` if the value is
+    /// not one of the defined values (1 to 8).
+    pub fn from_exif(n: u16) -> Option<Orientation> {
+        match n {
+            1 => Some(Orientation::Normal),
+            2 => Some(Orientation::FlipHorizontal),
+            3 => Some(Orientation::Rotate180),
+            4 => Some(Orientation::FlipVertical),
+            5 => Some(Orientation::Transpose),
+            6 => Some(Orientation::Rotate90),
+            7 => Some(Orientation::Transverse),
+            8 => Some(Orientation::Rotate270),
+            _ => None,
+        }
+    }
+
+    /// Returns the value of the EXIF orientation tag corresponding to this orientation.
+    pub fn to_exif(self) -> u16 {
+        match self {
+            Orientation::Normal => 1,
+            Orientation::FlipHorizontal => 2,
+            Orientation::Rotate180 => 3,
+            Orientation::FlipVertical => 4,
+            Orientation::Transpose => 5,
+            Orientation::Rotate90 => 6,
+            Orientation::Transverse => 7,
+            Orientation::Rotate270 => 8,
+        }
+    }
+
+    /// Returns `true` if width and height of the image are swapped when it is displayed.
+    #[inline]
+    pub fn swaps_dimensions(self) -> bool {
+        matches!(
+            self,
+            Orientation::Transpose
+                | Orientation::Rotate90
+                | Orientation::Transverse
+                | Orientation::Rotate270
+        )
+    }
+
+    /// Returns dimensions of the image when it is displayed, given its stored dimensions.
+    #[inline]
+    pub fn apply(self, dimensions: Dimensions) -> Dimensions {
+        if self.swaps_dimensions() {
+            dimensions.transposed()
+        } else {
+            dimensions
+        }
+    }
+}
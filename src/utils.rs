@@ -3,6 +3,7 @@ use std::io::{self, BufRead, ErrorKind, Read};
 use byteorder::ByteOrder as ByteOrderTrait;
 use byteorder::{BigEndian, LittleEndian, ReadBytesExt};
 
+#[allow(dead_code)]
 pub trait ReadExt: Read {
     fn read_exact_0(&mut self, mut buf: &mut [u8]) -> io::Result<usize> {
         let orig_len = buf.len() as u64;
@@ -47,28 +48,6 @@ pub trait BufReadExt: BufRead {
         }
         Ok(skipped)
     }
-
-    fn skip_until(&mut self, delim: u8) -> io::Result<usize> {
-        let mut read = 0;
-        loop {
-            let (done, used) = {
-                let available = match self.fill_buf() {
-                    Ok(n) => n,
-                    Err(ref e) if e.kind() == ErrorKind::Interrupted => continue,
-                    Err(e) => return Err(e),
-                };
-                match available.iter().position(|&b| b == delim) {
-                    Some(i) => (true, i + 1),
-                    None => (false, available.len()),
-                }
-            };
-            self.consume(used);
-            read += used;
-            if done || used == 0 {
-                return Ok(read);
-            }
-        }
-    }
 }
 
 impl<R: ?Sized + BufRead> BufReadExt for R {}
@@ -115,6 +94,7 @@ gen_byte_order_ops! {
 
 macro_rules! gen_read_byte_order_ext {
     ($tr:ident, $($name:ident -> $tpe:ty),+) => {
+        #[allow(dead_code)]
         pub trait $tr: Read {
             $(
             #[inline]
@@ -142,3 +122,45 @@ gen_read_byte_order_ext! {
 }
 
 impl<R: Read> ByteOrderReadExt for R {}
+
+const fn crc32_table() -> [u32; 256] {
+    let mut table = [0u32; 256];
+    let mut n = 0;
+    while n < 256 {
+        let mut c = n as u32;
+        let mut k = 0;
+        while k < 8 {
+            c = if c & 1 != 0 {
+                0xedb88320 ^ (c >> 1)
+            } else {
+                c >> 1
+            };
+            k += 1;
+        }
+        table[n] = c;
+        n += 1;
+    }
+    table
+}
+
+const CRC32_TABLE: [u32; 256] = crc32_table();
+
+/// CRC-32 checksum as used in PNG and zlib (ISO 3309).
+#[derive(Copy, Clone, Debug)]
+pub struct Crc32(u32);
+
+impl Crc32 {
+    pub fn new() -> Crc32 {
+        Crc32(0xffffffff)
+    }
+
+    pub fn update(&mut self, data: &[u8]) {
+        for &b in data {
+            self.0 = CRC32_TABLE[((self.0 ^ b as u32) & 0xff) as usize] ^ (self.0 >> 8);
+        }
+    }
+
+    pub fn finish(&self) -> u32 {
+        !self.0
+    }
+}
@@ -1,8 +1,22 @@
 extern crate immeta;
 
-use immeta::formats::{gif, jpeg, png};
-use immeta::markers::{Gif, Jpeg, Png, Webp};
-use immeta::Dimensions;
+use std::io::{BufReader, Read, Seek};
+
+use immeta::common::tiff::{EntryType, Value};
+use immeta::common::xmp::{self, Xmp};
+use immeta::common::{exif, icc, iptc, photoshop};
+use immeta::formats::{avif, bmp, gif, heif, ico, jpeg, png, tiff, webp};
+use immeta::markers::{Avif, Bmp, Gif, Heif, Ico, Jpeg, Png, Tiff, Webp};
+use immeta::{Dimensions, ImageFormat, LoadableMetadata, Orientation};
+
+fn assert_approx(actual: f64, expected: f64) {
+    assert!(
+        (actual - expected).abs() < 1e-6,
+        "{} is not approximately equal to {}",
+        actual,
+        expected
+    );
+}
 
 const OWLET_DIM: Dimensions = Dimensions {
     width: 1280,
@@ -19,21 +33,310 @@ const CHERRY_DIM: Dimensions = Dimensions {
     height: 772,
 };
 
+const PYTHON_DIM: Dimensions = Dimensions {
+    width: 16,
+    height: 16,
+};
+
+const PIXEL_DIM: Dimensions = Dimensions {
+    width: 1,
+    height: 1,
+};
+
 #[test]
 fn test_jpeg() {
     let md = immeta::load_from_file("tests/images/owlet.jpg").unwrap();
 
     assert_eq!(md.mime_type(), "image/jpeg");
     assert_eq!(md.dimensions(), OWLET_DIM);
+    assert_eq!(md.orientation(), None);
+    assert_eq!(md.display_dimensions(), OWLET_DIM);
 
     // let md = Jpeg::from(md).ok()
-    let md = md.into::<Jpeg>().ok().expect("not JPEG metadata");
+    let md = md.into::<Jpeg>().expect("not JPEG metadata");
     assert_eq!(md.dimensions, OWLET_DIM);
     assert_eq!(md.sample_precision, 8);
     assert_eq!(md.coding_process, jpeg::CodingProcess::DctSequential);
     assert_eq!(md.entropy_coding, jpeg::EntropyCoding::Huffman);
     assert!(md.baseline);
     assert!(!md.differential);
+    assert!(md.exif.is_none());
+}
+
+#[test]
+fn test_jpeg_exif() {
+    let md = immeta::load_from_file("tests/images/python-exif.jpg").unwrap();
+
+    // the EXIF thumbnail has its own frame header which must not be picked up
+    assert_eq!(md.dimensions(), PYTHON_DIM);
+
+    let md = md.into::<Jpeg>().expect("not JPEG metadata");
+    let exif = md.exif.as_ref().expect("no EXIF metadata");
+
+    assert_eq!(exif.make(), Some("immeta"));
+    assert_eq!(exif.model(), Some("Test Camera"));
+    assert_eq!(exif.software(), Some("immeta test suite"));
+    assert_eq!(exif.artist(), Some("Jane Doe"));
+    assert_eq!(exif.copyright(), Some("Public domain"));
+    assert_eq!(exif.orientation(), Some(Orientation::Normal));
+    assert_eq!(exif.date_time(), Some("2023:02:26 12:00:00"));
+
+    assert_eq!(exif.exif_version(), Some("0230"));
+    assert_eq!(exif.date_time_original(), Some("2023:02:26 11:59:58"));
+    assert_eq!(exif.date_time_digitized(), Some("2023:02:26 11:59:59"));
+    assert_eq!(exif.exposure_time(), Some((1, 250)));
+    assert_eq!(exif.f_number(), Some((28, 10)));
+    assert_eq!(exif.photographic_sensitivity(), Some(200));
+    assert_eq!(exif.exposure_bias(), Some((-1, 3)));
+    assert_eq!(exif.focal_length(), Some((50, 1)));
+    assert_eq!(exif.focal_length_in_35mm_film(), Some(75));
+    assert_eq!(exif.lens_make(), Some("immeta"));
+    assert_eq!(exif.lens_model(), Some("Test Lens 50mm"));
+    assert_eq!(exif.pixel_dimensions(), Some(PYTHON_DIM));
+
+    let gps = exif.gps_ifd.as_ref().expect("no GPS IFD");
+    assert_eq!(gps.fields.len(), 9);
+    assert_eq!(
+        gps.get(0x0002),
+        Some(&Value::Rational(vec![(48, 1), (51, 1), (2400, 100)]))
+    );
+    assert_eq!(gps.get(0x0001), Some(&Value::Ascii(vec!["N".to_owned()])));
+
+    assert_eq!(exif.interoperability_index(), Some("R98"));
+
+    let ifd1 = exif.ifd1.as_ref().expect("no IFD1");
+    assert_eq!(ifd1.get(0x0103), Some(&Value::Short(vec![6])));
+    assert_eq!(exif.thumbnail_location(), Some((944, 17)));
+
+    let gps = md.gps().expect("no GPS information");
+    assert_approx(gps.latitude.unwrap(), 48.856666666);
+    assert_approx(gps.longitude.unwrap(), 2.35);
+    assert_approx(gps.altitude.unwrap(), 35.0);
+    let date_time = gps.date_time.expect("no GPS date and time");
+    assert_eq!(date_time.to_string(), "2023-02-26T10:30:15Z");
+    assert_eq!(date_time.unix_timestamp(), 1677407415);
+}
+
+#[test]
+fn test_jpeg_exif_invalid_sub_ifd() {
+    // point the GPS sub-IFD past the end of the EXIF data
+    let mut data = std::fs::read("tests/images/python-exif.jpg").unwrap();
+    let entry = b"\x25\x88\x04\x00\x01\x00\x00\x00";
+    let i = data.windows(8).position(|w| w == entry).unwrap() + 8;
+    data[i..i + 4].copy_from_slice(&0xfff0u32.to_le_bytes());
+
+    let md = immeta::load_from_buf(&data).unwrap();
+    let md = md.into::<Jpeg>().expect("not JPEG metadata");
+    let exif = md.exif.expect("no EXIF metadata");
+    assert_eq!(exif.make(), Some("immeta"));
+    assert!(exif.exif_ifd.is_some());
+    assert!(exif.interoperability_ifd.is_some());
+    assert_eq!(exif.gps_ifd, None);
+    assert_eq!(exif.gps(), None);
+}
+
+#[test]
+fn test_jpeg_exif_fields() {
+    let md = immeta::load_from_file("tests/images/python-exif.jpg").unwrap();
+    let md = md.into::<Jpeg>().expect("not JPEG metadata");
+    let exif = md.exif.expect("no EXIF metadata");
+
+    assert_eq!(exif.exposure_program(), Some(exif::ExposureProgram::Normal));
+    assert_eq!(exif.metering_mode(), Some(exif::MeteringMode::Pattern));
+    assert_eq!(exif.color_space(), Some(exif::ColorSpace::Srgb));
+    assert_eq!(
+        exif.flash(),
+        Some(exif::Flash {
+            fired: false,
+            return_status: exif::FlashReturn::NoDetectionFunction,
+            mode: exif::FlashMode::CompulsorySuppression,
+            function_present: true,
+            red_eye_reduction: false,
+        })
+    );
+
+    let lines: Vec<String> = exif
+        .directories()
+        .into_iter()
+        .filter(|&(kind, _)| kind == exif::IfdKind::Exif)
+        .flat_map(|(kind, d)| d.fields.iter().map(move |f| f.display(kind).to_string()))
+        .collect();
+    assert_eq!(
+        lines,
+        vec![
+            "ExposureTime: 1/250 s",
+            "FNumber: f/2.8",
+            "ExposureProgram: Normal program",
+            "PhotographicSensitivity: 200",
+            "ExifVersion: 0230",
+            "DateTimeOriginal: 2023:02:26 11:59:58",
+            "DateTimeDigitized: 2023:02:26 11:59:59",
+            "ShutterSpeedValue: 797/100",
+            "ApertureValue: 297/100",
+            "ExposureBiasValue: -0.33 EV",
+            "MeteringMode: Pattern",
+            "Flash: Did not fire, compulsory flash suppression",
+            "FocalLength: 50 mm",
+            "ColorSpace: sRGB",
+            "PixelXDimension: 16",
+            "PixelYDimension: 16",
+            "InteroperabilityIFDPointer: 820",
+            "FocalLengthIn35mmFilm: 75 mm",
+            "LensMake: immeta",
+            "LensModel: Test Lens 50mm",
+        ]
+    );
+
+    let info = exif::tags::lookup(exif::IfdKind::Gps, 0x0002).unwrap();
+    assert_eq!(info.name, "GPSLatitude");
+    assert!(info.accepts(EntryType::Rational, 3));
+    assert!(!info.accepts(EntryType::Short, 3));
+    assert_eq!(
+        exif::tags::lookup_by_name("FNumber").map(|t| (t.ifd, t.tag)),
+        Some((exif::IfdKind::Exif, 0x829d))
+    );
+}
+
+#[test]
+fn test_jpeg_xmp() {
+    let md = immeta::load_from_file("tests/images/python-xmp.jpg").unwrap();
+
+    assert_eq!(md.dimensions(), PYTHON_DIM);
+    let xmp = md.xmp().expect("no XMP packet");
+    assert!(xmp.starts_with("<?xpacket begin="));
+    assert!(xmp.ends_with("<?xpacket end=\"w\"?>"));
+    assert!(xmp.contains("xmp:CreatorTool=\"immeta\""));
+    let extended = md.extended_xmp().expect("no extended XMP packet");
+    assert!(extended.contains("dc:format=\"image/jpeg\""));
+
+    let md = md.into::<Jpeg>().expect("not JPEG metadata");
+    assert!(md.exif.is_none());
+    let extended = md.extended_xmp.expect("no extended XMP packet");
+    assert!(extended.starts_with("<x:xmpmeta"));
+    assert!(extended.contains("dc:format=\"image/jpeg\""));
+    assert!(extended.ends_with("</x:xmpmeta>\n"));
+
+    let main = Xmp::parse(md.xmp.as_deref().unwrap()).unwrap();
+    assert_eq!(main.creator_tool(), Some("immeta"));
+    assert_eq!(
+        main.get(xmp::ns::XMP_NOTE, "HasExtendedXMP")
+            .and_then(xmp::Value::as_text),
+        Some("0123456789ABCDEF0123456789ABCDEF")
+    );
+    let extended = Xmp::parse(&extended).unwrap();
+    assert_eq!(
+        extended
+            .get(xmp::ns::DC, "format")
+            .and_then(xmp::Value::as_text),
+        Some("image/jpeg")
+    );
+}
+
+#[test]
+fn test_jpeg_extended_xmp_guid() {
+    // use another prefix for the XMP note namespace and refer to an extended packet
+    // which is not present in the file
+    let mut data = std::fs::read("tests/images/python-xmp.jpg").unwrap();
+    let guid = data
+        .windows(32)
+        .position(|w| w == b"0123456789ABCDEF0123456789ABCDEF")
+        .unwrap();
+    for i in 0..guid {
+        if data[i..].starts_with(b"xmpNote") {
+            data[i + 3] = b'n';
+        }
+    }
+    data[guid..guid + 32].copy_from_slice(b"FEDCBA9876543210FEDCBA9876543210");
+
+    let md = immeta::load_from_buf(&data).unwrap();
+    let xmp = Xmp::parse(md.xmp().expect("no XMP packet")).unwrap();
+    assert_eq!(
+        xmp.get(xmp::ns::XMP_NOTE, "HasExtendedXMP")
+            .and_then(xmp::Value::as_text),
+        Some("FEDCBA9876543210FEDCBA9876543210")
+    );
+    assert!(md.extended_xmp().is_none());
+}
+
+#[test]
+fn test_jpeg_iptc() {
+    let md = immeta::load_from_file("tests/images/python-iptc.jpg").unwrap();
+
+    assert_eq!(md.dimensions(), PYTHON_DIM);
+    let md = md.into::<Jpeg>().expect("not JPEG metadata");
+
+    let iptc = md.iptc.as_ref().expect("no IPTC metadata");
+    assert!(iptc.utf8);
+    assert_eq!(iptc.object_name.as_deref(), Some("Logo"));
+    assert_eq!(iptc.keywords, vec!["python", "logo", "schlange"]);
+    assert_eq!(iptc.by_line, vec!["Jane Doe", "John Doe"]);
+    assert_eq!(iptc.date_created.as_deref(), Some("20230226"));
+    assert_eq!(iptc.time_created.as_deref(), Some("120000+0100"));
+    assert_eq!(iptc.city.as_deref(), Some("München"));
+    assert_eq!(iptc.country_name.as_deref(), Some("Deutschland"));
+    assert_eq!(iptc.country_code.as_deref(), Some("DEU"));
+    assert_eq!(iptc.copyright_notice.as_deref(), Some("© 2023 immeta"));
+    // stored as an extended dataset
+    assert_eq!(iptc.caption.as_deref(), Some("Ein Bild über Python"));
+    assert_eq!(iptc.headline, None);
+    assert_eq!(
+        iptc.get(iptc::APPLICATION_RECORD, 0)
+            .map(|d| &d.data[..])
+            .collect::<Vec<_>>(),
+        vec![&[0, 4][..]]
+    );
+
+    let photoshop = md.photoshop.as_ref().expect("no image resources");
+    assert_eq!(
+        photoshop.resources.iter().map(|r| r.id).collect::<Vec<_>>(),
+        vec![0x03ed, 0x0404, 0x040c, 0x0fa0]
+    );
+    assert_eq!(photoshop.get(photoshop::IPTC_NAA).unwrap().name, "IPTC");
+    assert_eq!(photoshop.get(0x0fa0).unwrap().data, b"odd");
+
+    let resolution = photoshop.resolution_info().expect("no resolution info");
+    assert_approx(resolution.horizontal, 300.0);
+    assert_eq!(
+        resolution.horizontal_unit,
+        photoshop::ResolutionUnit::PixelsPerInch
+    );
+    assert!((resolution.vertical - 118.11).abs() < 1e-4);
+    assert_eq!(
+        resolution.vertical_unit,
+        photoshop::ResolutionUnit::PixelsPerCentimeter
+    );
+
+    let thumbnail = photoshop.thumbnail().expect("no thumbnail");
+    assert!(thumbnail.is_jpeg());
+    assert_eq!(thumbnail.dimensions, (16u32, 16u32).into());
+    assert_eq!(thumbnail.bits_per_pixel, 24);
+    assert_eq!(
+        immeta::load_from_buf(&thumbnail.data).unwrap().dimensions(),
+        PYTHON_DIM
+    );
+}
+
+#[test]
+fn test_jpeg_icc_profile() {
+    let md = immeta::load_from_file("tests/images/python-icc.jpg").unwrap();
+
+    assert_eq!(md.dimensions(), PYTHON_DIM);
+    let data = md.icc_profile().expect("no ICC profile");
+    assert_eq!(data.len(), 316);
+
+    let profile = icc::Profile::parse(data).unwrap();
+    assert_eq!(profile.size, 316);
+    assert_eq!(profile.cmm, Some(*b"lcms"));
+    assert_eq!(profile.version.to_string(), "4.3.0");
+    assert_eq!(profile.device_class, icc::DeviceClass::Display);
+    assert_eq!(profile.color_space, icc::ColorSpace::Rgb);
+    assert_eq!(profile.connection_space, icc::ColorSpace::Xyz);
+    assert_eq!(profile.rendering_intent, icc::RenderingIntent::Perceptual);
+    assert_eq!(profile.description.as_deref(), Some("Display P3"));
+    assert_eq!(
+        profile.copyright.as_deref(),
+        Some("Copyright Apple Inc., 2017")
+    );
 }
 
 #[test]
@@ -43,7 +346,7 @@ fn test_png() {
     assert_eq!(md.mime_type(), "image/png");
     assert_eq!(md.dimensions(), OWLET_DIM);
 
-    let md = md.into::<Png>().ok().expect("not PNG metadata");
+    let md = md.into::<Png>().expect("not PNG metadata");
     assert_eq!(md.dimensions, OWLET_DIM);
     assert_eq!(md.color_type, png::ColorType::Rgb);
     assert_eq!(md.color_depth, 24);
@@ -53,6 +356,364 @@ fn test_png() {
     );
     assert_eq!(md.filter_method, png::FilterMethod::AdaptiveFiltering);
     assert_eq!(md.interlace_method, png::InterlaceMethod::Disabled);
+    assert!(md.exif.is_none());
+    assert!(md.animation.is_none());
+    assert_eq!(md.frames_number(), 1);
+    assert!(!md.has_transparency());
+    assert!(!md.is_animated());
+    assert_eq!(md.total_duration_ms(), 0);
+}
+
+#[test]
+fn test_png_chunks() {
+    let md = immeta::load_from_file("tests/images/owlet.png").unwrap();
+    let md = md.into::<Png>().expect("not PNG metadata");
+
+    assert_eq!(md.chunks.len(), 59);
+    let types: Vec<_> = md.chunks.iter().map(|c| c.chunk_type.to_string()).collect();
+    assert_eq!(&types[..5], ["IHDR", "gAMA", "cHRM", "bKGD", "IDAT"]);
+    assert_eq!(&types[55..], ["IDAT", "tEXt", "tEXt", "IEND"]);
+
+    assert_eq!(
+        md.chunks[0],
+        png::ChunkInfo {
+            chunk_type: png::ChunkType(*b"IHDR"),
+            length: 13,
+            offset: 8,
+        }
+    );
+    let iend = md.chunks[58];
+    assert_eq!((iend.length, iend.offset), (0, 1683980));
+    assert_eq!(md.chunks[56].offset, 1683882);
+
+    let ihdr = md.chunks[0].chunk_type;
+    assert!(ihdr.is_critical());
+    assert!(ihdr.is_public());
+    assert!(ihdr.is_reserved_bit_valid());
+    assert!(!ihdr.is_safe_to_copy());
+
+    let text = md.chunks[56].chunk_type;
+    assert!(!text.is_critical());
+    assert!(text.is_public());
+    assert!(text.is_safe_to_copy());
+}
+
+#[test]
+fn test_png_crc() {
+    let mut data = std::fs::read("tests/images/python-exif.png").unwrap();
+    let md = png::Metadata::load_with_crc_check(&mut &data[..]).unwrap();
+    assert_eq!(md.dimensions, PYTHON_DIM);
+
+    // corrupt the IHDR chunk data
+    data[16] ^= 0xff;
+    assert!(png::Metadata::load_with_crc_check(&mut &data[..]).is_err());
+    data[16] ^= 0xff;
+
+    // corrupt the CRC of the last chunk; it is not checked by default
+    let len = data.len();
+    data[len - 1] ^= 0xff;
+    assert!(immeta::load_from_buf(&data).is_ok());
+    assert!(png::Metadata::load_with_crc_check(&mut &data[..]).is_err());
+
+    // truncated images are rejected even if CRC is not checked
+    assert!(immeta::load_from_buf(&data[..len - 12]).is_err());
+}
+
+#[test]
+fn test_png_animated() {
+    let md = immeta::load_from_file("tests/images/python-animated.png").unwrap();
+
+    assert_eq!(md.dimensions(), PYTHON_DIM);
+    let md = md.into::<Png>().expect("not PNG metadata");
+    assert!(md.is_animated());
+    assert_eq!(md.frames_number(), 3);
+    assert_eq!(md.total_duration_ms(), 183);
+
+    let animation = md.animation.as_ref().expect("no animation");
+    assert_eq!(animation.num_frames, 3);
+    assert_eq!(animation.num_plays, 2);
+    assert!(animation.default_image_is_first_frame);
+    assert_eq!(
+        animation.frames,
+        vec![
+            png::FrameControl {
+                sequence_number: 0,
+                dimensions: PYTHON_DIM,
+                x_offset: 0,
+                y_offset: 0,
+                delay_num: 1,
+                delay_den: 10,
+                dispose_op: png::DisposeOp::None,
+                blend_op: png::BlendOp::Source,
+            },
+            png::FrameControl {
+                sequence_number: 1,
+                dimensions: PYTHON_DIM,
+                x_offset: 0,
+                y_offset: 0,
+                delay_num: 1,
+                delay_den: 30,
+                dispose_op: png::DisposeOp::Background,
+                blend_op: png::BlendOp::Over,
+            },
+            png::FrameControl {
+                sequence_number: 3,
+                dimensions: (8, 8).into(),
+                x_offset: 2,
+                y_offset: 3,
+                delay_num: 5,
+                delay_den: 0,
+                dispose_op: png::DisposeOp::Previous,
+                blend_op: png::BlendOp::Source,
+            },
+        ]
+    );
+    assert_approx(animation.frames[1].delay_ms(), 100.0 / 3.0);
+    assert_approx(animation.frames[2].delay_ms(), 50.0);
+}
+
+#[test]
+fn test_png_text() {
+    let md = immeta::load_from_file("tests/images/owlet.png").unwrap();
+    let md = md.into::<Png>().expect("not PNG metadata");
+    assert_eq!(md.texts.len(), 2);
+    assert_eq!(md.text("date:create"), Some("2015-06-13T00:47:18+03:00"));
+    assert_eq!(md.text("date:modify"), Some("2015-06-13T00:47:18+03:00"));
+
+    let md = immeta::load_from_file("tests/images/python-text.png").unwrap();
+    assert_eq!(md.dimensions(), PYTHON_DIM);
+    let xmp = md.xmp().expect("no XMP packet");
+    assert!(xmp.contains("xmp:CreatorTool=\"immeta\""));
+
+    let md = md.into::<Png>().expect("not PNG metadata");
+    // the zTXt chunk with an unknown compression method is skipped
+    assert_eq!(md.texts.len(), 7);
+    assert_eq!(md.text("Software"), Some("immeta \u{e9}dition"));
+    assert_eq!(md.text("Comment"), Some("Größe: 16×16"));
+    assert_eq!(md.text("Broken"), None);
+    assert_eq!(
+        md.text("parameters"),
+        Some("a photo of a python, Steps: 20, Sampler: Euler a")
+    );
+
+    assert_eq!(md.texts[0].kind, png::TextKind::Text);
+    assert!(!md.texts[0].compressed);
+    assert_eq!(md.texts[0].language_tag, None);
+    assert_eq!(md.texts[1].kind, png::TextKind::CompressedText);
+    assert!(md.texts[1].compressed);
+    assert_eq!(
+        md.texts[2],
+        png::TextChunk {
+            kind: png::TextKind::InternationalText,
+            keyword: "Title".into(),
+            text: "Питон".into(),
+            compressed: false,
+            language_tag: Some("ru".into()),
+            translated_keyword: Some("Заголовок".into()),
+        }
+    );
+    assert!(md.texts[3].compressed);
+    assert_eq!(md.texts[3].language_tag.as_deref(), Some(""));
+    assert_eq!(md.texts[4].keyword, "XML:com.adobe.xmp");
+    assert_eq!(md.texts[5].keyword, "date:create");
+}
+
+#[test]
+fn test_png_color() {
+    let md = immeta::load_from_file("tests/images/owlet.png").unwrap();
+    let md = md.into::<Png>().expect("not PNG metadata");
+    assert_eq!(md.gamma, Some(45455));
+    assert_approx(md.gamma_approx().unwrap(), 0.45455);
+    assert_eq!(
+        md.chromaticities,
+        Some(png::Chromaticities {
+            white_point: (31270, 32900),
+            red: (64000, 33000),
+            green: (30000, 60000),
+            blue: (15000, 6000),
+        })
+    );
+    assert_eq!(
+        md.background_color,
+        Some(png::BackgroundColor::Rgb(255, 255, 255))
+    );
+    assert_eq!(md.transparency, None);
+    assert_eq!(md.physical_dimensions, None);
+    assert_eq!(md.srgb_intent, None);
+
+    let md = immeta::load_from_file("tests/images/python-color.png").unwrap();
+    let md = md.into::<Png>().expect("not PNG metadata");
+    assert_eq!(md.color_type, png::ColorType::Indexed);
+    assert_eq!(
+        md.background_color,
+        Some(png::BackgroundColor::PaletteIndex(90))
+    );
+    match md.transparency {
+        Some(png::Transparency::Palette(ref alpha)) => {
+            assert_eq!(alpha.len(), 86);
+            assert_eq!(&alpha[..3], [0x00, 0xaf, 0xed]);
+        }
+        ref t => panic!("unexpected transparency: {:?}", t),
+    }
+    assert!(md.has_transparency());
+
+    let phys = md.physical_dimensions.expect("no physical dimensions");
+    assert_eq!(
+        (phys.x, phys.y, phys.unit),
+        (11811, 5906, png::PhysicalUnit::Meter)
+    );
+    let (x_dpi, y_dpi) = phys.dpi().unwrap();
+    assert_approx(x_dpi, 299.9994);
+    assert_approx(y_dpi, 150.0124);
+
+    assert_eq!(md.srgb_intent, Some(icc::RenderingIntent::Perceptual));
+    assert_eq!(md.significant_bits, Some(vec![5, 6, 5]));
+    assert_eq!(
+        md.cicp,
+        Some(png::Cicp {
+            color_primaries: 9,
+            transfer_characteristics: 16,
+            matrix_coefficients: 0,
+            full_range: true,
+        })
+    );
+    assert_eq!(
+        md.mastering_display_color_volume,
+        Some(png::MasteringDisplayColorVolume {
+            primaries: [(35400, 14600), (8500, 39850), (6550, 2300)],
+            white_point: (15635, 16450),
+            max_luminance: 10000000,
+            min_luminance: 50,
+        })
+    );
+    assert_eq!(
+        md.content_light_level,
+        Some(png::ContentLightLevel {
+            max_content_light_level: 10000000,
+            max_frame_average_light_level: 4000000,
+        })
+    );
+}
+
+#[test]
+fn test_png_palette() {
+    let md = immeta::load_from_file("tests/images/python-exif.png").unwrap();
+    let md = md.into::<Png>().expect("not PNG metadata");
+    assert_eq!(md.palette, None);
+
+    let options = png::LoadOptions {
+        retain_palette: true,
+        ..png::LoadOptions::default()
+    };
+    let data = std::fs::read("tests/images/python-exif.png").unwrap();
+    let md = png::Metadata::load_with_options(&mut &data[..], &options).unwrap();
+    let palette = md.palette.expect("no palette");
+    assert_eq!(palette.len(), 151);
+    assert_eq!(palette[0], [0x00, 0x00, 0x00, 0x00]);
+    assert_eq!(palette[1], [0x4e, 0x8d, 0xc0, 0xaf]);
+    // entries without tRNS alpha values are opaque
+    assert_eq!(palette[86][3], 0xff);
+    assert_eq!(palette[150][3], 0xff);
+}
+
+#[test]
+fn test_png_exif() {
+    let md = immeta::load_from_file("tests/images/python-exif.png").unwrap();
+
+    assert_eq!(md.mime_type(), "image/png");
+    assert_eq!(md.dimensions(), PYTHON_DIM);
+    assert_eq!(md.exif().and_then(|e| e.make()), Some("immeta"));
+
+    let gps = md.gps().expect("no GPS information");
+    assert_approx(gps.latitude.unwrap(), -33.85875);
+    assert_approx(gps.longitude.unwrap(), -70.65);
+    assert_approx(gps.altitude.unwrap(), -12.0);
+
+    let date_time = gps.date_time.expect("no GPS date and time");
+    assert_eq!(
+        date_time,
+        exif::GpsDateTime {
+            year: 1999,
+            month: 12,
+            day: 31,
+            hour: 23,
+            minute: 59,
+            second: 59,
+            nanosecond: 500_000_000,
+        }
+    );
+    assert_eq!(date_time.to_string(), "1999-12-31T23:59:59.500000000Z");
+    assert_eq!(date_time.unix_timestamp(), 946684799);
+}
+
+#[test]
+fn test_png_xmp() {
+    let md = immeta::load_from_file("tests/images/python-xmp.png").unwrap();
+
+    assert_eq!(md.dimensions(), PYTHON_DIM);
+    let xmp = md.xmp().expect("no XMP packet");
+    assert!(xmp.starts_with("<?xpacket begin="));
+    assert!(xmp.contains("xmp:CreatorTool=\"immeta\""));
+    assert!(xmp.ends_with("<?xpacket end=\"w\"?>"));
+}
+
+#[test]
+fn test_png_icc_profile() {
+    let md = immeta::load_from_file("tests/images/python-icc.png").unwrap();
+
+    assert_eq!(md.dimensions(), PYTHON_DIM);
+    let profile = icc::Profile::parse(md.icc_profile().expect("no ICC profile")).unwrap();
+    assert_eq!(profile.version.to_string(), "2.1.0");
+    assert_eq!(profile.color_space, icc::ColorSpace::Rgb);
+    assert_eq!(profile.description.as_deref(), Some("sRGB IEC61966-2.1"));
+    assert_eq!(
+        profile.copyright.as_deref(),
+        Some("Copyright (c) 1998 Hewlett-Packard Company")
+    );
+}
+
+#[test]
+fn test_icc_profile_mluc_count() {
+    // a multi-localized description declaring far more records than it contains
+    let mut data = vec![0u8; 128];
+    data[36..40].copy_from_slice(b"acsp");
+    data.extend_from_slice(&1u32.to_be_bytes());
+    data.extend_from_slice(b"desc");
+    data.extend_from_slice(&144u32.to_be_bytes());
+    data.extend_from_slice(&32u32.to_be_bytes());
+    data.extend_from_slice(b"mluc\0\0\0\0");
+    data.extend_from_slice(&u32::MAX.to_be_bytes());
+    data.extend_from_slice(&12u32.to_be_bytes());
+    data.extend_from_slice(b"enUS");
+    data.extend_from_slice(&4u32.to_be_bytes());
+    data.extend_from_slice(&28u32.to_be_bytes());
+    data.extend_from_slice(b"\0H\0i");
+
+    let profile = icc::Profile::parse(&data).unwrap();
+    assert_eq!(profile.description.as_deref(), Some("Hi"));
+}
+
+#[test]
+fn test_gif_color_tables() {
+    let options = gif::LoadOptions {
+        retain_color_tables: true,
+    };
+    let data = std::fs::read("tests/images/owlet.gif").unwrap();
+    let md = gif::Metadata::load_with_options(&mut &data[..], &options).unwrap();
+    assert_eq!(md.frames_number(), 1);
+    let table = md.global_color_table.expect("no global color table");
+    assert_eq!(table.size, 256);
+    let entries = table.entries.expect("no color table entries");
+    assert_eq!(entries.len(), 256);
+    assert_eq!(
+        &entries[..4],
+        [
+            [0x02, 0x02, 0x01],
+            [0x0a, 0x05, 0x00],
+            [0x05, 0x0a, 0x01],
+            [0x0b, 0x0c, 0x02]
+        ]
+    );
 }
 
 #[test]
@@ -62,21 +723,22 @@ fn test_gif_plain() {
     assert_eq!(md.mime_type(), "image/gif");
     assert_eq!(md.dimensions(), OWLET_DIM);
 
-    let md = md.into::<Gif>().ok().expect("not GIF metadata");
+    let md = md.into::<Gif>().expect("not GIF metadata");
     assert_eq!(md.version, gif::Version::V89a);
     assert_eq!(md.dimensions, OWLET_DIM);
     assert_eq!(
         md.global_color_table,
         Some(gif::ColorTable {
             size: 256,
-            sorted: false
+            sorted: false,
+            entries: None,
         })
     );
     assert_eq!(md.color_resolution, 256);
     assert_eq!(md.background_color_index, 0);
     assert_eq!(md.pixel_aspect_ratio, 0);
     assert_eq!(md.frames_number(), 1);
-    assert_eq!(md.is_animated(), false);
+    assert!(!md.is_animated());
     assert_eq!(
         md.blocks,
         vec![
@@ -109,21 +771,22 @@ fn test_gif_animated() {
     assert_eq!(md.mime_type(), "image/gif");
     assert_eq!(md.dimensions(), DROP_DIM);
 
-    let md = md.into::<Gif>().ok().expect("not GIF metadata");
+    let md = md.into::<Gif>().expect("not GIF metadata");
     assert_eq!(md.version, gif::Version::V89a);
     assert_eq!(md.dimensions, DROP_DIM);
     assert_eq!(
         md.global_color_table,
         Some(gif::ColorTable {
             size: 256,
-            sorted: false
+            sorted: false,
+            entries: None,
         })
     );
     assert_eq!(md.color_resolution, 128);
     assert_eq!(md.background_color_index, 255);
     assert_eq!(md.pixel_aspect_ratio, 0);
     assert_eq!(md.frames_number(), 30);
-    assert_eq!(md.is_animated(), true);
+    assert!(md.is_animated());
 
     let mut blocks = md.blocks.iter();
 
@@ -177,6 +840,24 @@ fn test_gif_animated() {
     assert!(blocks.next().is_none());
 }
 
+#[test]
+fn test_gif_xmp() {
+    let md = immeta::load_from_file("tests/images/python-xmp.gif").unwrap();
+
+    assert_eq!(md.dimensions(), PYTHON_DIM);
+    let xmp = md.xmp().expect("no XMP packet").to_owned();
+    assert!(xmp.starts_with("<?xpacket begin="));
+    assert!(xmp.contains("xmp:CreatorTool=\"immeta\""));
+    assert!(xmp.ends_with("<?xpacket end=\"w\"?>"));
+
+    let md = md.into::<Gif>().expect("not GIF metadata");
+    assert_eq!(md.frames_number(), 1);
+    match md.blocks[0] {
+        gif::Block::ApplicationExtension(ref ext) => assert!(ext.is_xmp()),
+        ref b => panic!("unexpected block: {:?}", b),
+    }
+}
+
 #[test]
 fn test_webp() {
     let md = immeta::load_from_file("tests/images/cherry.webp").unwrap();
@@ -184,7 +865,1044 @@ fn test_webp() {
     assert_eq!(md.mime_type(), "image/webp");
     assert_eq!(md.dimensions(), CHERRY_DIM);
 
-    let md = md.into::<Webp>().ok().expect("not WEBP metadata");
+    let md = md.into::<Webp>().expect("not WEBP metadata");
 
     println!("{md:?}");
 }
+
+#[test]
+fn test_webp_lossless() {
+    let md = immeta::load_from_file("tests/images/pixel-lossless.webp").unwrap();
+
+    assert_eq!(md.mime_type(), "image/webp");
+    assert_eq!(md.dimensions(), PIXEL_DIM);
+
+    let md = md.into::<Webp>().expect("not WEBP metadata");
+    assert_eq!(
+        md,
+        webp::Metadata::VP8L(webp::VP8LMetadata {
+            version_number: 0,
+            dimensions: PIXEL_DIM,
+            alpha_is_used: true,
+        })
+    );
+}
+
+#[test]
+fn test_webp_extended() {
+    let md = immeta::load_from_file("tests/images/python.webp").unwrap();
+
+    assert_eq!(md.mime_type(), "image/webp");
+    assert_eq!(md.dimensions(), PYTHON_DIM);
+
+    let md = md.into::<Webp>().expect("not WEBP metadata");
+    assert_eq!(
+        md,
+        webp::Metadata::VP8X(webp::VP8XMetadata {
+            canvas_dimensions: PYTHON_DIM,
+            has_icc_profile: false,
+            has_alpha: true,
+            has_exif: false,
+            has_xmp: false,
+            has_animation: false,
+            animation: None,
+            icc_profile_chunk: None,
+            exif_chunk: None,
+            xmp_chunk: None,
+            exif: None,
+            xmp: None,
+        })
+    );
+    assert!(!md.is_animated());
+}
+
+#[test]
+fn test_webp_animated() {
+    let md = immeta::load_from_file("tests/images/pixel-animated.webp").unwrap();
+
+    assert_eq!(md.mime_type(), "image/webp");
+    assert_eq!(md.dimensions(), PIXEL_DIM);
+
+    let md = md.into::<Webp>().expect("not WEBP metadata");
+    assert_eq!(md.frames_number(), 2);
+    assert!(md.is_animated());
+
+    let animation = md.animation().expect("no animation");
+    assert_eq!(animation.background_color, [255, 255, 255, 255]);
+    assert_eq!(animation.loop_count, 0);
+    assert_eq!(animation.total_duration_ms(), 350);
+    assert_eq!(
+        animation.frames,
+        vec![
+            webp::AnimationFrame {
+                left: 0,
+                top: 0,
+                dimensions: PIXEL_DIM,
+                duration: 100,
+                blending_method: webp::BlendingMethod::AlphaBlending,
+                disposal_method: webp::DisposalMethod::None,
+                bitstream: webp::Bitstream::Lossless,
+                has_alpha_chunk: false,
+            },
+            webp::AnimationFrame {
+                left: 0,
+                top: 0,
+                dimensions: PIXEL_DIM,
+                duration: 250,
+                blending_method: webp::BlendingMethod::NoBlending,
+                disposal_method: webp::DisposalMethod::DisposeToBackground,
+                bitstream: webp::Bitstream::Lossless,
+                has_alpha_chunk: false,
+            }
+        ]
+    );
+}
+
+#[test]
+fn test_webp_metadata_chunks() {
+    let md = immeta::load_from_file("tests/images/python-meta.webp").unwrap();
+
+    assert_eq!(md.mime_type(), "image/webp");
+    assert_eq!(md.dimensions(), PYTHON_DIM);
+    assert_eq!(md.orientation(), Some(Orientation::Rotate90));
+    assert_eq!(md.display_dimensions(), PYTHON_DIM);
+
+    let md = md.into::<Webp>().expect("not WEBP metadata");
+
+    let icc = md.icc_profile_chunk().expect("no ICC profile");
+    assert_eq!(icc.offset, 38);
+    assert_eq!(icc.data.len(), 336);
+    assert_eq!(&icc.data[36..40], b"acsp");
+    let profile = icc::Profile::parse(md.icc_profile().unwrap()).unwrap();
+    assert_eq!(profile.description.as_deref(), Some("immeta test profile"));
+
+    let exif = md.exif_chunk().expect("no EXIF chunk");
+    assert_eq!(exif.offset, 784);
+    assert_eq!(exif.data.len(), 172);
+    assert_eq!(&exif.data[..4], b"MM\x00\x2a");
+
+    let xmp = md.xmp_chunk().expect("no XMP chunk");
+    assert_eq!(xmp.offset, 964);
+    assert!(xmp.data.starts_with(b"<?xpacket begin="));
+    assert!(md
+        .xmp()
+        .unwrap()
+        .contains("<rdf:li xml:lang=\"x-default\">Python logo</rdf:li>"));
+
+    let exif = md.exif().expect("EXIF is not parsed");
+    assert_eq!(exif.orientation(), Some(Orientation::Rotate90));
+    assert_eq!(exif.date_time(), Some("2023:02:26 12:00:00"));
+    assert_eq!(exif.date_time_original(), Some("2023:02:26 11:59:58"));
+}
+
+#[test]
+fn test_tiff() {
+    let md = immeta::load_from_file("tests/images/python.tiff").unwrap();
+
+    assert_eq!(md.mime_type(), "image/tiff");
+    assert_eq!(md.dimensions(), PYTHON_DIM);
+
+    let md = md.into::<Tiff>().expect("not TIFF metadata");
+    assert!(!md.big_tiff);
+    assert!(md.exif.is_none());
+    assert_eq!(md.pages_number(), 1);
+    assert_eq!(
+        md.pages[0],
+        tiff::Page {
+            dimensions: PYTHON_DIM,
+            bits_per_sample: vec![8, 8, 8, 8],
+            samples_per_pixel: 4,
+            compression: tiff::Compression::None,
+            photometric_interpretation: Some(tiff::PhotometricInterpretation::Rgb),
+            planar_configuration: tiff::PlanarConfiguration::Chunky,
+            data_layout: tiff::DataLayout::Strips {
+                rows_per_strip: 128,
+                strips_number: 1,
+            },
+            resolution: None,
+            orientation: Some(Orientation::Normal),
+            xmp: None,
+            icc_profile: None,
+        }
+    );
+    assert_eq!(md.pages[0].color_depth(), 32);
+}
+
+#[test]
+fn test_tiff_pages() {
+    let md = immeta::load_from_file("tests/images/pages.tiff").unwrap();
+
+    assert_eq!(md.mime_type(), "image/tiff");
+    assert_eq!(md.dimensions(), (64, 48).into());
+
+    let md = md.into::<Tiff>().expect("not TIFF metadata");
+    assert_eq!(
+        md.pages,
+        vec![
+            tiff::Page {
+                dimensions: (64, 48).into(),
+                bits_per_sample: vec![8, 8, 8],
+                samples_per_pixel: 3,
+                compression: tiff::Compression::Lzw,
+                photometric_interpretation: Some(tiff::PhotometricInterpretation::Rgb),
+                planar_configuration: tiff::PlanarConfiguration::Chunky,
+                data_layout: tiff::DataLayout::Tiles {
+                    tile_dimensions: (16, 16).into(),
+                    tiles_number: 12,
+                },
+                resolution: Some(tiff::Resolution {
+                    x: (300, 1),
+                    y: (300, 1),
+                    unit: tiff::ResolutionUnit::Inch,
+                }),
+                orientation: None,
+                xmp: None,
+                icc_profile: None,
+            },
+            tiff::Page {
+                dimensions: (32, 24).into(),
+                bits_per_sample: vec![1],
+                samples_per_pixel: 1,
+                compression: tiff::Compression::CcittGroup4,
+                photometric_interpretation: Some(tiff::PhotometricInterpretation::WhiteIsZero),
+                planar_configuration: tiff::PlanarConfiguration::Chunky,
+                data_layout: tiff::DataLayout::Strips {
+                    rows_per_strip: 8,
+                    strips_number: 3,
+                },
+                resolution: Some(tiff::Resolution {
+                    x: (118, 1),
+                    y: (236, 2),
+                    unit: tiff::ResolutionUnit::Centimeter,
+                }),
+                orientation: None,
+                xmp: None,
+                icc_profile: None,
+            },
+        ]
+    );
+    assert_eq!(md.pages[1].resolution.unwrap().y_approx(), 118.0);
+}
+
+#[test]
+fn test_tiff_gps() {
+    let md = immeta::load_from_file("tests/images/gps.tiff").unwrap();
+
+    assert_eq!(md.mime_type(), "image/tiff");
+    assert_eq!(md.dimensions(), (4, 4).into());
+
+    let md = md.into::<Tiff>().expect("not TIFF metadata");
+    let gps = md.gps().expect("no GPS information");
+    assert_approx(gps.latitude.unwrap(), 35.675);
+    assert_approx(gps.longitude.unwrap(), 139.75);
+    assert_approx(gps.altitude.unwrap(), 40.0);
+    assert!(gps.date_time.is_none());
+}
+
+#[test]
+fn test_tiff_rotated() {
+    let md = immeta::load_from_file("tests/images/rotated.tiff").unwrap();
+
+    assert_eq!(md.dimensions(), (6, 4).into());
+    assert_eq!(md.orientation(), Some(Orientation::Rotate90));
+    assert_eq!(md.display_dimensions(), (4, 6).into());
+
+    let md = md.into::<Tiff>().expect("not TIFF metadata");
+    assert_eq!(md.pages[0].orientation, Some(Orientation::Rotate90));
+    assert!(md.exif.is_none());
+}
+
+#[test]
+fn test_tiff_big() {
+    let md = immeta::load_from_file("tests/images/big.tiff").unwrap();
+
+    assert_eq!(md.mime_type(), "image/tiff");
+    assert_eq!(md.dimensions(), (32, 20).into());
+
+    let md = md.into::<Tiff>().expect("not TIFF metadata");
+    assert!(md.big_tiff);
+    assert_eq!(
+        md.pages,
+        vec![tiff::Page {
+            dimensions: (32, 20).into(),
+            bits_per_sample: vec![8],
+            samples_per_pixel: 1,
+            compression: tiff::Compression::None,
+            photometric_interpretation: Some(tiff::PhotometricInterpretation::BlackIsZero),
+            planar_configuration: tiff::PlanarConfiguration::Chunky,
+            data_layout: tiff::DataLayout::Strips {
+                rows_per_strip: 20,
+                strips_number: 1,
+            },
+            resolution: Some(tiff::Resolution {
+                x: (72, 1),
+                y: (72, 1),
+                unit: tiff::ResolutionUnit::Inch,
+            }),
+            orientation: None,
+            xmp: None,
+            icc_profile: None,
+        }]
+    );
+}
+
+#[test]
+fn test_tiff_xmp() {
+    let md = immeta::load_from_file("tests/images/xmp.tiff").unwrap();
+
+    assert_eq!(md.dimensions(), (4u32, 4u32).into());
+    let xmp = md.xmp().expect("no XMP packet");
+    assert!(xmp.starts_with("<?xpacket begin="));
+    assert!(xmp.contains("xmp:CreatorTool=\"immeta\""));
+    assert!(xmp.ends_with("<?xpacket end=\"w\"?>"));
+}
+
+#[test]
+fn test_tiff_icc_profile() {
+    let md = immeta::load_from_file("tests/images/icc.tiff").unwrap();
+
+    let profile = icc::Profile::parse(md.icc_profile().expect("no ICC profile")).unwrap();
+    assert_eq!(profile.device_class, icc::DeviceClass::Display);
+    assert_eq!(profile.color_space, icc::ColorSpace::Gray);
+    assert_eq!(profile.color_space.channels(), Some(1));
+    assert_eq!(profile.description.as_deref(), Some("Gray Gamma 2.2"));
+}
+
+#[test]
+fn test_heif() {
+    let md = immeta::load_from_file("tests/images/python.heic").unwrap();
+    assert_eq!(md.format(), ImageFormat::Heif);
+    assert_eq!(md.mime_type(), "image/heif");
+    assert_eq!(md.dimensions(), (64u32, 48u32).into());
+    // the rotation property takes precedence over the EXIF orientation
+    assert_eq!(md.orientation(), Some(Orientation::Rotate270));
+    assert_eq!(md.display_dimensions(), (48u32, 64u32).into());
+    assert!(md.xmp().unwrap().contains("xmp:CreatorTool=\"immeta\""));
+    let profile = icc::Profile::parse(md.icc_profile().expect("no ICC profile")).unwrap();
+    assert_eq!(profile.description.as_deref(), Some("immeta test profile"));
+
+    let md = md.into::<Heif>().expect("not HEIF metadata");
+    assert_eq!(md.major_brand.as_str(), Some("heic"));
+    assert_eq!(md.minor_version, 0);
+    let brands: Vec<_> = md.compatible_brands.iter().map(|b| b.to_string()).collect();
+    assert_eq!(brands, ["mif1", "heic"]);
+    assert_eq!(md.handler_type.as_str(), Some("pict"));
+    assert_eq!(md.primary_item_id, 1);
+    assert_eq!(md.items.len(), 3);
+    assert_eq!(md.items[1].item_type.as_str(), Some("Exif"));
+    assert_eq!(md.items[2].name, "XMP");
+    assert_eq!(
+        md.items[2].content_type.as_deref(),
+        Some("application/rdf+xml")
+    );
+    assert!(!md.has_grid());
+    assert_eq!(md.transformations, [heif::Transformation::Rotation(90)]);
+    assert_eq!(md.bits_per_channel.as_deref(), Some(&[8, 8, 8][..]));
+    assert_eq!(
+        md.nclx,
+        Some(heif::Nclx {
+            color_primaries: 12,
+            transfer_characteristics: 13,
+            matrix_coefficients: 6,
+            full_range: true,
+        })
+    );
+
+    // the EXIF item is split into two extents stored in reverse order
+    let exif = md.exif.as_ref().expect("no EXIF metadata");
+    assert_eq!(exif.make(), Some("immeta"));
+    assert_eq!(exif.orientation(), Some(Orientation::Rotate90));
+}
+
+#[test]
+fn test_heif_grid() {
+    let md = immeta::load_from_file("tests/images/grid.heic").unwrap();
+    let md = md.into::<Heif>().expect("not HEIF metadata");
+
+    assert_eq!(md.major_brand.as_str(), Some("mif1"));
+    assert!(md.has_grid());
+    assert_eq!(md.dimensions, (128u32, 96u32).into());
+    assert_eq!(md.items.len(), 5);
+    assert!(md.items[1..]
+        .iter()
+        .all(|i| i.hidden && i.item_type.as_str() == Some("hvc1")));
+    assert_eq!(
+        md.transformations,
+        [
+            heif::Transformation::Rotation(270),
+            heif::Transformation::Mirror(heif::MirrorAxis::Horizontal),
+        ]
+    );
+    assert_eq!(md.orientation(), Orientation::Transverse);
+    assert_eq!(md.nclx, None);
+    assert_eq!(md.icc_profile, None);
+    assert_eq!(md.exif, None);
+    assert_eq!(md.xmp, None);
+
+    // EXIF and XMP items are stored after the meta box, so they cannot be skipped
+    let data = std::fs::read("tests/images/python.heic").unwrap();
+    let meta_end = data.windows(4).position(|w| w == b"mdat").unwrap() - 4;
+    assert!(immeta::load_from_buf(&data[..meta_end]).is_err());
+
+    let mut data = std::fs::read("tests/images/grid.heic").unwrap();
+    data[4..8].copy_from_slice(b"free");
+    let err = heif::Metadata::load_from_buf(&data).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "invalid image format: invalid HEIF header: free"
+    );
+}
+
+#[test]
+fn test_avif() {
+    let md = immeta::load_from_file("tests/images/alpha.avif").unwrap();
+    assert_eq!(md.format(), ImageFormat::Avif);
+    assert_eq!(md.mime_type(), "image/avif");
+    assert_eq!(md.dimensions(), (32u32, 24u32).into());
+    assert_eq!(md.orientation(), Some(Orientation::Normal));
+
+    let md = md.into::<Avif>().expect("not AVIF metadata");
+    assert_eq!(md.major_brand.as_str(), Some("avif"));
+    assert_eq!(md.primary_item().map(|i| &*i.name), Some("Color"));
+    assert_eq!(md.items.len(), 3);
+    assert!(!md.is_animated());
+    assert_eq!(md.frames_number(), 1);
+    assert_eq!(md.sequence, None);
+
+    let config = md.av1_config.expect("no AV1 configuration");
+    assert_eq!(config.profile, 0);
+    assert_eq!(config.level, 8);
+    assert_eq!(config.tier, 0);
+    assert_eq!(config.bit_depth, 10);
+    assert!(!config.monochrome);
+    assert_eq!(config.chroma_subsampling(), avif::ChromaSubsampling::Yuv420);
+    assert_eq!(config.chroma_subsampling().to_string(), "4:2:0");
+    assert_eq!(config.chroma_sample_position, 1);
+    assert_eq!(md.bits_per_channel.as_deref(), Some(&[10, 10, 10][..]));
+
+    assert_eq!(
+        md.nclx,
+        Some(heif::Nclx {
+            color_primaries: 9,
+            transfer_characteristics: 16,
+            matrix_coefficients: 9,
+            full_range: true,
+        })
+    );
+    assert_eq!(
+        md.content_light_level,
+        Some(avif::ContentLightLevel {
+            max_content_light_level: 1000,
+            max_picture_average_light_level: 400,
+        })
+    );
+    assert_eq!(
+        md.mastering_display_color_volume,
+        Some(png::MasteringDisplayColorVolume {
+            primaries: [(35400, 14600), (8500, 39850), (6550, 2300)],
+            white_point: (15635, 16450),
+            max_luminance: 10000000,
+            min_luminance: 50,
+        })
+    );
+
+    assert!(md.has_alpha());
+    assert_eq!(md.alpha_item_id, Some(2));
+    assert_eq!(
+        md.exif.as_ref().and_then(|e| e.model()),
+        Some("Test Camera")
+    );
+}
+
+#[test]
+fn test_avif_sequence() {
+    let md = immeta::load_from_file("tests/images/animated.avif").unwrap();
+    assert_eq!(md.format(), ImageFormat::Avif);
+    assert_eq!(md.orientation(), Some(Orientation::Rotate180));
+
+    let md = md.into::<Avif>().expect("not AVIF metadata");
+    assert_eq!(md.major_brand.as_str(), Some("avis"));
+    assert!(!md.has_alpha());
+    assert!(md.is_animated());
+    assert_eq!(md.frames_number(), 10);
+
+    // the alpha track is skipped
+    let sequence = md.sequence.as_ref().expect("no sequence");
+    assert_eq!(sequence.timescale, 1000);
+    assert_eq!(sequence.duration, 1000);
+    assert_eq!(sequence.frame_durations, [(8, 100), (2, 100)]);
+    assert_approx(sequence.duration_ms(), 1000.0);
+
+    let data = std::fs::read("tests/images/animated.avif").unwrap();
+    let md = immeta::load_from_reader(&mut &data[..]).unwrap();
+    assert_eq!(md.into::<Avif>().unwrap().frames_number(), 10);
+}
+
+#[test]
+fn test_bmp() {
+    let md = immeta::load_from_file("tests/images/python.bmp").unwrap();
+    assert_eq!(md.format(), ImageFormat::Bmp);
+    assert_eq!(md.mime_type(), "image/bmp");
+    assert_eq!(md.dimensions(), PYTHON_DIM);
+    assert_eq!(md.orientation(), None);
+
+    let md = md.into::<Bmp>().expect("not BMP metadata");
+    assert_eq!(md.file_size, 1162);
+    assert_eq!(md.pixel_data_offset, 138);
+    let header = &md.header;
+    assert_eq!(header.kind, bmp::HeaderKind::V5);
+    assert!(!header.top_down);
+    assert_eq!(header.bit_count, 32);
+    assert_eq!(header.compression, bmp::Compression::Bitfields);
+    assert_eq!(
+        header.masks,
+        Some(bmp::ChannelMasks {
+            red: 0xff0000,
+            green: 0xff00,
+            blue: 0xff,
+            alpha: 0xff000000,
+        })
+    );
+    assert_eq!(header.palette_size, 0);
+    assert_eq!(header.pixels_per_meter, None);
+    assert_eq!(header.color_space, Some(bmp::ColorSpace::Srgb));
+    assert_eq!(md.icc_profile, None);
+    assert_eq!(md.linked_profile, None);
+}
+
+#[test]
+fn test_bmp_headers() {
+    let md = immeta::load_from_file("tests/images/core.bmp").unwrap();
+    let md = md.into::<Bmp>().expect("not BMP metadata");
+    assert_eq!(md.header.kind, bmp::HeaderKind::Core);
+    assert_eq!(md.dimensions(), (3u32, 2u32).into());
+    assert_eq!(md.header.bit_count, 4);
+    assert_eq!(md.header.compression, bmp::Compression::Rgb);
+    assert_eq!(md.header.palette_size, 16);
+    assert_eq!(md.header.dpi(), None);
+
+    let md = immeta::load_from_file("tests/images/rgb565.bmp").unwrap();
+    let md = md.into::<Bmp>().expect("not BMP metadata");
+    assert_eq!(md.header.kind, bmp::HeaderKind::Info);
+    assert_eq!(md.header.kind.to_string(), "BITMAPINFOHEADER");
+    assert_eq!(md.dimensions(), (2u32, 2u32).into());
+    assert_eq!(md.header.bit_count, 16);
+    assert_eq!(md.header.compression.to_string(), "Bitfields");
+    assert_eq!(
+        md.header.masks,
+        Some(bmp::ChannelMasks {
+            red: 0xf800,
+            green: 0x07e0,
+            blue: 0x001f,
+            alpha: 0,
+        })
+    );
+    assert_eq!(md.header.pixels_per_meter, Some((3780, 3780)));
+    let (x, y) = md.header.dpi().unwrap();
+    assert_approx(x, 96.012);
+    assert_approx(y, 96.012);
+    assert_eq!(md.header.color_space, None);
+
+    // a bitmap with an embedded PNG image
+    let mut data = b"BM\0\0\0\0\0\0\0\0\x36\0\0\0".to_vec();
+    data.extend_from_slice(&40u32.to_le_bytes());
+    data.extend_from_slice(&2u32.to_le_bytes());
+    data.extend_from_slice(&2u32.to_le_bytes());
+    data.extend_from_slice(&1u16.to_le_bytes());
+    data.extend_from_slice(&0u16.to_le_bytes());
+    data.extend_from_slice(&5u32.to_le_bytes());
+    data.resize(14 + 40, 0);
+    let md = bmp::Metadata::load_from_buf(&data).unwrap();
+    assert_eq!(md.header.bit_count, 0);
+    assert_eq!(md.header.compression, bmp::Compression::Png);
+    assert_eq!(md.header.palette_size, 0);
+
+    let err = immeta::load_from_buf(b"BM\0\0\0\0\0\0\0\0\0\0\0\0\x07\0\0\0").unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "invalid image format: invalid DIB header size: 7"
+    );
+}
+
+#[test]
+fn test_bmp_color_profile() {
+    let md = immeta::load_from_file("tests/images/icc.bmp").unwrap();
+    assert_eq!(md.dimensions(), PYTHON_DIM);
+    let profile = icc::Profile::parse(md.icc_profile().expect("no ICC profile")).unwrap();
+    assert_eq!(profile.description.as_deref(), Some("immeta test profile"));
+
+    let md = md.into::<Bmp>().expect("not BMP metadata");
+    assert!(md.header.top_down);
+    assert_eq!(md.header.pixels_per_meter, Some((2835, 2835)));
+    assert_eq!(
+        md.header.color_space,
+        Some(bmp::ColorSpace::EmbeddedProfile)
+    );
+
+    // the profile is stored after the pixel data
+    let data = std::fs::read("tests/images/icc.bmp").unwrap();
+    let mut r = BufReader::new(Pipe(&data));
+    let md = immeta::load_from_reader(&mut r).unwrap();
+    assert!(md.icc_profile().is_some());
+    assert!(immeta::load_from_buf(&data[..data.len() - 1]).is_err());
+
+    let md = immeta::load_from_file("tests/images/linked.bmp").unwrap();
+    let md = md.into::<Bmp>().expect("not BMP metadata");
+    assert_eq!(md.header.color_space, Some(bmp::ColorSpace::LinkedProfile));
+    assert_eq!(md.icc_profile, None);
+    assert_eq!(
+        md.linked_profile.as_deref(),
+        Some("C:\\Windows\\System32\\spool\\drivers\\color\\test.icm")
+    );
+}
+
+#[test]
+fn test_ico() {
+    let md = immeta::load_from_file("tests/images/favicon.ico").unwrap();
+    assert_eq!(md.format(), ImageFormat::Ico);
+    assert_eq!(md.mime_type(), "image/vnd.microsoft.icon");
+    assert_eq!(md.dimensions(), (48u32, 48u32).into());
+
+    let md = md.into::<Ico>().expect("not ICO metadata");
+    assert_eq!(md.kind, ico::Kind::Icon);
+    assert_eq!(md.entries.len(), 4);
+
+    let entry = &md.entries[0];
+    assert_eq!(entry.declared_dimensions, PYTHON_DIM);
+    assert_eq!(entry.bit_count, 32);
+    assert_eq!(entry.hotspot, None);
+    match entry.image {
+        Some(ico::Image::Bmp(ref header)) => {
+            assert_eq!(header.kind, bmp::HeaderKind::Info);
+            assert_eq!(header.dimensions, (16u32, 32u32).into());
+        }
+        ref image => panic!("unexpected image: {:?}", image),
+    }
+    assert_eq!(entry.dimensions(), PYTHON_DIM);
+
+    let entry = &md.entries[1];
+    assert_eq!(entry.dimensions(), (32u32, 32u32).into());
+    assert_eq!(entry.bits_per_pixel(), 8);
+
+    // the directory entry of the PNG image is inaccurate
+    let entry = &md.entries[2];
+    assert_eq!(entry.declared_dimensions, (256u32, 256u32).into());
+    match entry.image {
+        Some(ico::Image::Png(ref md)) => assert_eq!(md.color_type, png::ColorType::RgbAlpha),
+        ref image => panic!("unexpected image: {:?}", image),
+    }
+    assert_eq!(entry.dimensions(), (48u32, 48u32).into());
+    assert_eq!(entry.bits_per_pixel(), 32);
+
+    // the PNG image is preferred to the 4-bit image of the same size
+    assert_eq!(md.largest_entry(), Some(entry));
+    assert_eq!(md.entries[3].dimensions(), (48u32, 48u32).into());
+    assert_eq!(md.entries[3].bits_per_pixel(), 4);
+
+    // images which are not stored completely are omitted, the rest are still loaded
+    let data = std::fs::read("tests/images/favicon.ico").unwrap();
+    let last = md.entries.iter().max_by_key(|e| e.data_offset).unwrap();
+    assert_eq!(
+        last.data_offset as usize + last.data_size as usize,
+        data.len()
+    );
+    let truncated = ico::Metadata::load_from_buf(&data[..data.len() - 1]).unwrap();
+    for (entry, expected) in truncated.entries.iter().zip(&md.entries) {
+        if entry.data_offset == last.data_offset {
+            assert_eq!(entry.image, None);
+        } else {
+            assert_eq!(entry, expected);
+        }
+    }
+
+    // the directory itself must be complete
+    assert!(immeta::load_from_buf(&data[..20]).is_err());
+}
+
+#[test]
+fn test_cur() {
+    let md = immeta::load_from_file("tests/images/pointer.cur").unwrap();
+    assert_eq!(md.format(), ImageFormat::Ico);
+    assert_eq!(md.dimensions(), (32u32, 32u32).into());
+
+    let md = md.into::<Ico>().expect("not ICO metadata");
+    assert_eq!(md.kind, ico::Kind::Cursor);
+    assert_eq!(md.kind.to_string(), "Cursor");
+    assert_eq!(md.entries.len(), 1);
+    let entry = &md.entries[0];
+    assert_eq!(entry.hotspot, Some((5, 3)));
+    assert_eq!(entry.bit_count, 0);
+    assert_eq!(entry.color_count, 2);
+    assert_eq!(entry.bits_per_pixel(), 1);
+}
+
+#[test]
+fn test_detect_format() {
+    let cases = [
+        ("owlet.png", ImageFormat::Png),
+        ("owlet.gif", ImageFormat::Gif),
+        ("owlet.jpg", ImageFormat::Jpeg),
+        ("cherry.webp", ImageFormat::Webp),
+        ("python.tiff", ImageFormat::Tiff),
+        ("big.tiff", ImageFormat::Tiff),
+        ("python.heic", ImageFormat::Heif),
+        ("grid.heic", ImageFormat::Heif),
+        ("alpha.avif", ImageFormat::Avif),
+        ("animated.avif", ImageFormat::Avif),
+        ("python.bmp", ImageFormat::Bmp),
+        ("favicon.ico", ImageFormat::Ico),
+        ("pointer.cur", ImageFormat::Ico),
+    ];
+    for &(name, format) in &cases {
+        let path = format!("tests/images/{name}");
+        let data = std::fs::read(&path).unwrap();
+        assert_eq!(immeta::detect_format(&data), Some(format), "{name}");
+
+        let mut f = std::fs::File::open(&path).unwrap();
+        assert_eq!(
+            immeta::detect_format_from_reader(&mut f).unwrap(),
+            Some(format),
+            "{name}"
+        );
+        assert_eq!(f.stream_position().unwrap(), 0);
+
+        let md = immeta::load_from_buf(&data).unwrap();
+        assert_eq!(md.format(), format);
+        assert_eq!(md.mime_type(), format.mime_type());
+    }
+
+    // AVIF brands take priority over the generic HEIF major brand
+    let mut data = std::fs::read("tests/images/alpha.avif").unwrap();
+    data[8..12].copy_from_slice(b"mif1");
+    assert_eq!(immeta::detect_format(&data), Some(ImageFormat::Avif));
+    assert_eq!(
+        immeta::load_from_buf(&data).unwrap().format(),
+        ImageFormat::Avif
+    );
+    assert_eq!(
+        immeta::load_from_reader(&mut &data[..]).unwrap().format(),
+        ImageFormat::Avif
+    );
+    // compatible brands are not available in the signature alone
+    assert_eq!(
+        immeta::detect_format(&data[..immeta::SIGNATURE_LENGTH]),
+        Some(ImageFormat::Heif)
+    );
+
+    assert_eq!(immeta::detect_format(b"MZ"), None);
+    assert_eq!(immeta::detect_format(b"RIFF\0\0\0\0WAVE"), None);
+    assert_eq!(immeta::detect_format(b""), None);
+}
+
+#[test]
+fn test_load_error() {
+    // unknown signatures result in a generic error
+    let err = immeta::load_from_buf(b"not an image").unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "invalid image format: unknown or unsupported image type"
+    );
+
+    // errors of the parser are reported if the signature is recognized
+    let mut data = std::fs::read("tests/images/owlet.png").unwrap();
+    data[25] = 5;
+    let err = immeta::load_from_buf(&data).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "invalid image format: invalid color type: 5"
+    );
+}
+
+// a non-seekable stream which returns data in small pieces
+struct Pipe<'a>(&'a [u8]);
+
+impl Read for Pipe<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let n = buf.len().min(self.0.len()).min(5);
+        buf[..n].copy_from_slice(&self.0[..n]);
+        self.0 = &self.0[n..];
+        Ok(n)
+    }
+}
+
+#[test]
+fn test_load_from_reader() {
+    for name in [
+        "owlet.png",
+        "owlet.gif",
+        "owlet.jpg",
+        "cherry.webp",
+        "python.tiff",
+        "python.heic",
+        "alpha.avif",
+        "python.bmp",
+        "favicon.ico",
+    ] {
+        let data = std::fs::read(format!("tests/images/{name}")).unwrap();
+        let expected = immeta::load_from_buf(&data).unwrap();
+
+        // the signature can be peeked at in the buffer
+        let mut r = BufReader::new(Pipe(&data));
+        assert_eq!(
+            immeta::load_from_reader(&mut r).unwrap(),
+            expected,
+            "{name}"
+        );
+
+        // the buffer is smaller than the signature
+        let mut r = BufReader::with_capacity(4, Pipe(&data));
+        assert_eq!(
+            immeta::load_from_reader(&mut r).unwrap(),
+            expected,
+            "{name}"
+        );
+    }
+
+    // JPEG image data after the frame header is not consumed
+    let data = std::fs::read("tests/images/owlet.jpg").unwrap();
+    let mut r = &data[..];
+    let md = immeta::load_from_reader(&mut r).unwrap();
+    assert_eq!(md.dimensions(), OWLET_DIM);
+    assert!(!r.is_empty());
+
+    let err = immeta::load_from_reader(&mut &b"MZ"[..]).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "invalid image format: unknown or unsupported image type"
+    );
+}
+
+#[cfg(feature = "async")]
+mod asynchronous {
+    use std::future::Future;
+    use std::io;
+    use std::pin::{pin, Pin};
+    use std::task::{ready, Context, Poll, Waker};
+
+    use futures_io::{AsyncBufRead, AsyncRead, AsyncSeek};
+    use immeta::markers::{Ico, MetadataMarker, Png, Tiff};
+
+    use super::OWLET_DIM;
+
+    // an asynchronous stream which is ready every other time and returns data in small pieces
+    struct Stream<'a> {
+        data: &'a [u8],
+        ready: bool,
+    }
+
+    impl AsyncRead for Stream<'_> {
+        fn poll_read(
+            mut self: Pin<&mut Self>,
+            cx: &mut Context,
+            buf: &mut [u8],
+        ) -> Poll<io::Result<usize>> {
+            let data = ready!(self.as_mut().poll_fill_buf(cx))?;
+            let n = data.len().min(buf.len());
+            buf[..n].copy_from_slice(&data[..n]);
+            self.consume(n);
+            Poll::Ready(Ok(n))
+        }
+    }
+
+    impl AsyncBufRead for Stream<'_> {
+        fn poll_fill_buf(self: Pin<&mut Self>, cx: &mut Context) -> Poll<io::Result<&[u8]>> {
+            let this = self.get_mut();
+            this.ready = !this.ready;
+            if this.ready {
+                Poll::Ready(Ok(&this.data[..this.data.len().min(100)]))
+            } else {
+                cx.waker().wake_by_ref();
+                Poll::Pending
+            }
+        }
+
+        fn consume(self: Pin<&mut Self>, amt: usize) {
+            let this = self.get_mut();
+            this.data = &this.data[amt..];
+        }
+    }
+
+    // a seekable stream which otherwise behaves like `Stream` and counts the data consumed
+    struct SeekableStream<'a> {
+        data: &'a [u8],
+        position: usize,
+        ready: bool,
+        consumed: usize,
+    }
+
+    impl<'a> SeekableStream<'a> {
+        fn new(data: &'a [u8]) -> SeekableStream<'a> {
+            SeekableStream {
+                data,
+                position: 0,
+                ready: false,
+                consumed: 0,
+            }
+        }
+    }
+
+    impl AsyncRead for SeekableStream<'_> {
+        fn poll_read(
+            mut self: Pin<&mut Self>,
+            cx: &mut Context,
+            buf: &mut [u8],
+        ) -> Poll<io::Result<usize>> {
+            let data = ready!(self.as_mut().poll_fill_buf(cx))?;
+            let n = data.len().min(buf.len());
+            buf[..n].copy_from_slice(&data[..n]);
+            self.consume(n);
+            Poll::Ready(Ok(n))
+        }
+    }
+
+    impl AsyncBufRead for SeekableStream<'_> {
+        fn poll_fill_buf(self: Pin<&mut Self>, cx: &mut Context) -> Poll<io::Result<&[u8]>> {
+            let this = self.get_mut();
+            this.ready = !this.ready;
+            if this.ready {
+                let rest = &this.data[this.position.min(this.data.len())..];
+                Poll::Ready(Ok(&rest[..rest.len().min(100)]))
+            } else {
+                cx.waker().wake_by_ref();
+                Poll::Pending
+            }
+        }
+
+        fn consume(self: Pin<&mut Self>, amt: usize) {
+            let this = self.get_mut();
+            this.position += amt;
+            this.consumed += amt;
+        }
+    }
+
+    impl AsyncSeek for SeekableStream<'_> {
+        fn poll_seek(
+            self: Pin<&mut Self>,
+            _: &mut Context,
+            pos: io::SeekFrom,
+        ) -> Poll<io::Result<u64>> {
+            let this = self.get_mut();
+            let position = match pos {
+                io::SeekFrom::Start(offset) => offset as i64,
+                io::SeekFrom::End(offset) => this.data.len() as i64 + offset,
+                io::SeekFrom::Current(offset) => this.position as i64 + offset,
+            };
+            if position < 0 {
+                return Poll::Ready(Err(io::ErrorKind::InvalidInput.into()));
+            }
+            this.position = position as usize;
+            Poll::Ready(Ok(position as u64))
+        }
+    }
+
+    fn block_on<F: Future>(f: F) -> F::Output {
+        let mut f = pin!(f);
+        let mut cx = Context::from_waker(Waker::noop());
+        loop {
+            if let Poll::Ready(result) = f.as_mut().poll(&mut cx) {
+                return result;
+            }
+        }
+    }
+
+    fn assert_send<T: Send>(t: T) -> T {
+        t
+    }
+
+    #[test]
+    fn test_load_async() {
+        for name in [
+            "owlet.png",
+            "owlet.gif",
+            "owlet.jpg",
+            "cherry.webp",
+            "pixel-animated.webp",
+            "python.tiff",
+            "python-iptc.jpg",
+            "python.heic",
+            "animated.avif",
+            "icc.bmp",
+            "favicon.ico",
+        ] {
+            let data = std::fs::read(format!("tests/images/{name}")).unwrap();
+            let expected = immeta::load_from_buf(&data).unwrap();
+
+            let mut stream = Stream {
+                data: &data,
+                ready: false,
+            };
+            let md = block_on(assert_send(immeta::load_async(&mut stream))).unwrap();
+            assert_eq!(md, expected, "{name}");
+        }
+
+        let data = std::fs::read("tests/images/owlet.png").unwrap();
+        let mut stream = Stream {
+            data: &data,
+            ready: false,
+        };
+        let md = block_on(assert_send(Png::load_async(&mut stream))).unwrap();
+        assert_eq!(md.dimensions, OWLET_DIM);
+
+        // truncated icons are loaded successfully, so they are read completely
+        let data = std::fs::read("tests/images/favicon.ico").unwrap();
+        let mut stream = Stream {
+            data: &data,
+            ready: false,
+        };
+        let md = block_on(assert_send(Ico::load_async(&mut stream))).unwrap();
+        assert_eq!(md, Ico::load_from_buf(&data).unwrap());
+
+        // truncated images are still rejected
+        let mut stream = Stream {
+            data: &data[..data.len() - 1],
+            ready: false,
+        };
+        assert!(block_on(Png::load_async(&mut stream)).is_err());
+
+        let mut stream = Stream {
+            data: b"not an image",
+            ready: false,
+        };
+        assert!(block_on(immeta::load_async(&mut stream)).is_err());
+    }
+
+    #[test]
+    fn test_load_async_seek() {
+        for name in [
+            "owlet.png",
+            "owlet.gif",
+            "owlet.jpg",
+            "cherry.webp",
+            "pixel-animated.webp",
+            "python.tiff",
+            "python-exif.jpg",
+            "python.heic",
+            "animated.avif",
+            "icc.bmp",
+            "favicon.ico",
+        ] {
+            let data = std::fs::read(format!("tests/images/{name}")).unwrap();
+            let expected = immeta::load_from_buf(&data).unwrap();
+
+            let mut stream = SeekableStream::new(&data);
+            stream.position = 10;
+            let md = block_on(assert_send(immeta::load_async_seek(&mut stream))).unwrap();
+            assert_eq!(md, expected, "{name}");
+        }
+
+        // only the beginning of the image is read, while its data may be anywhere after it
+        let mut data = std::fs::read("tests/images/python.tiff").unwrap();
+        data.resize(1 << 20, 0);
+        let mut stream = SeekableStream::new(&data);
+        let md = block_on(assert_send(Tiff::load_async_seek(&mut stream))).unwrap();
+        assert_eq!(md, Tiff::load_from_buf(&data).unwrap());
+        assert!(stream.consumed < 16 * 1024, "{}", stream.consumed);
+
+        let data = std::fs::read("tests/images/owlet.png").unwrap();
+        let mut stream = SeekableStream::new(&data[..data.len() - 1]);
+        assert!(block_on(Png::load_async_seek(&mut stream)).is_err());
+
+        let mut stream = SeekableStream::new(b"not an image");
+        assert!(block_on(immeta::load_async_seek(&mut stream)).is_err());
+    }
+}